@@ -0,0 +1,510 @@
+// Opening books in the Polyglot format, which most engines and GUIs share.
+// A book is a list of entries sorted by the key of their position, each with a move and the weight it is picked with.
+// See: http://hgm.nubati.net/book_format.html
+use std::{collections::HashMap, fs};
+
+use crate::{
+    game::{board::Board, pgn},
+    generators::{Move, MoveGen, Square},
+    tables::PAWN_ATTACKS,
+    PieceKind, Player,
+};
+
+use keys::KEYS;
+
+mod keys;
+
+const ENTRY_SIZE: usize = 16;
+// Books are usually built from the first moves only, since later positions rarely repeat between games.
+const DEFAULT_PLIES: usize = 20;
+
+const CASTLING_OFFSET: usize = 768;
+const EP_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+// The key of a position, which is not the same as the hash the engine uses itself.
+pub fn get_key(board: &Board) -> u64 {
+    let mut key = 0;
+
+    for index in 0..64 {
+        if let Some(piece) = board.pieces.get_piece(Square(index)) {
+            let kind = match piece.piece_kind {
+                PieceKind::Pawn => 0,
+                PieceKind::Knight => 1,
+                PieceKind::Bishop => 2,
+                PieceKind::Rook => 3,
+                PieceKind::Queen => 4,
+                PieceKind::King => 5,
+            };
+            let is_white = (piece.player == Player::White) as usize;
+
+            key ^= KEYS[64 * (2 * kind + is_white) + index as usize];
+        }
+    }
+
+    let white = board.get_player_state(Player::White);
+    let black = board.get_player_state(Player::Black);
+    let castling_rights = [
+        white.can_castle_ks,
+        white.can_castle_qs,
+        black.can_castle_ks,
+        black.can_castle_qs,
+    ];
+
+    for (offset, can_castle) in castling_rights.into_iter().enumerate() {
+        if can_castle {
+            key ^= KEYS[CASTLING_OFFSET + offset];
+        }
+    }
+
+    // The en-passant file is only part of the key when a pawn is able to capture there.
+    let capture_point = board.ep_info.capture_point;
+
+    if capture_point.isnt_empty() {
+        let capture_square = capture_point.first_one_square();
+        let capturers =
+            PAWN_ATTACKS[!board.current_player][capture_square] & board.moving_player.pawns;
+
+        if capturers.isnt_empty() {
+            key ^= KEYS[EP_OFFSET + (capture_square.0 % 8) as usize];
+        }
+    }
+
+    if board.current_player == Player::White {
+        key ^= KEYS[TURN_OFFSET];
+    }
+
+    key
+}
+
+// Moves are stored with their origin and target squares, and the piece for promotions.
+// Castling is stored as the king capturing its own rook.
+pub fn encode_move(board: &Board, chess_move: Move) -> u16 {
+    let (origin, target, promotion) = match (chess_move, board.current_player) {
+        (Move::Regular { origin, target, .. }, _) => (origin, target, 0),
+        (Move::EnPassant { origin }, _) => {
+            (origin, board.ep_info.capture_point.first_one_square(), 0)
+        }
+        (
+            Move::Promotion {
+                origin,
+                target,
+                promotion_to,
+            },
+            _,
+        ) => (
+            origin,
+            target,
+            match promotion_to {
+                PieceKind::Knight => 1,
+                PieceKind::Bishop => 2,
+                PieceKind::Rook => 3,
+                _ => 4,
+            },
+        ),
+        (Move::CastleKS, Player::White) => (Square::E1, Square::H1, 0),
+        (Move::CastleQS, Player::White) => (Square::E1, Square::A1, 0),
+        (Move::CastleKS, Player::Black) => (Square::E8, Square::H8, 0),
+        (Move::CastleQS, Player::Black) => (Square::E8, Square::A8, 0),
+    };
+
+    (promotion << 12) | ((origin.0 as u16) << 6) | target.0 as u16
+}
+
+// Books can contain illegal moves (like from a key collision), which are never returned.
+pub fn decode_move(board: &Board, raw_move: u16) -> Option<Move> {
+    MoveGen::run(*board)
+        .into_iter()
+        .find(|&chess_move| encode_move(board, chess_move) == raw_move)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BookEntry {
+    pub key: u64,
+    pub raw_move: u16,
+    pub weight: u16,
+    // Left to the programs which write the book, and not used here.
+    pub learn: u32,
+}
+
+impl BookEntry {
+    // Entries are stored in big-endian.
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            raw_move: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+
+        bytes
+    }
+}
+
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err("Book file has an invalid size");
+        }
+
+        let mut entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(BookEntry::from_bytes)
+            .collect::<Vec<_>>();
+
+        // Books should already be sorted, but the lookup relies on it so it is made sure of.
+        entries.sort_by_key(|entry| entry.key);
+
+        Ok(Self { entries })
+    }
+
+    pub fn load(path: &str) -> Result<Self, &'static str> {
+        Self::from_bytes(&fs::read(path).map_err(|_| "Book file couldn't be read")?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map(|entry| entry.to_bytes())
+            .collect()
+    }
+
+    // Builds a book from the first plies of the games, where the moves are weighted by how well they scored for the player who made them.
+    // A win counts twice as much as a draw, and moves which only lost are left out. Games without a result are skipped.
+    pub fn from_games(games: &[pgn::Game], max_plies: usize) -> Self {
+        let mut scores = HashMap::<(u64, u16), u64>::new();
+
+        for game in games {
+            let Some(result) = game.result else {
+                continue;
+            };
+
+            let mut board = game.board;
+
+            for &chess_move in game.moves.iter().take(max_plies) {
+                let player_result = match board.current_player {
+                    Player::White => result,
+                    Player::Black => 1.0 - result,
+                };
+                let score = scores
+                    .entry((get_key(&board), encode_move(&board, chess_move)))
+                    .or_default();
+
+                *score += (player_result * 2.0).round() as u64;
+                board.make_move(chess_move);
+            }
+        }
+
+        // The weights are scaled down together when they don't fit, so their ratios are kept.
+        let max_score = scores.values().copied().max().unwrap_or(0);
+        let scale = |score: u64| {
+            if max_score > u16::MAX as u64 {
+                score * u16::MAX as u64 / max_score
+            } else {
+                score
+            }
+        };
+
+        let mut entries = scores
+            .into_iter()
+            .map(|((key, raw_move), score)| BookEntry {
+                key,
+                raw_move,
+                weight: scale(score) as u16,
+                learn: 0,
+            })
+            .filter(|entry| entry.weight > 0)
+            .collect::<Vec<_>>();
+
+        // Within a position, the best moves come first like in other books.
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+
+        Self { entries }
+    }
+
+    pub fn get_len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get_entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+
+        &self.entries[start..end]
+    }
+
+    // Returns the legal book moves of the position with their weights.
+    pub fn get_moves(&self, board: &Board) -> Vec<(Move, u16)> {
+        self.get_entries(get_key(board))
+            .iter()
+            .filter_map(|entry| Some((decode_move(board, entry.raw_move)?, entry.weight)))
+            .collect()
+    }
+
+    // Picks a move with a chance in proportion to its weight, where "random" is any random number.
+    pub fn pick_move(&self, board: &Board, random: u64) -> Option<Move> {
+        let moves = self.get_moves(board);
+        let total_weight = moves.iter().map(|&(_, weight)| weight as u64).sum::<u64>();
+
+        if total_weight == 0 {
+            return None;
+        }
+
+        let mut pick = random % total_weight;
+
+        moves.into_iter().find_map(|(chess_move, weight)| {
+            if pick < weight as u64 {
+                Some(chess_move)
+            } else {
+                pick -= weight as u64;
+                None
+            }
+        })
+    }
+}
+
+pub fn run(args: &[String]) -> Result<(), &'static str> {
+    let (pgn_path, output_path, max_plies) = match args {
+        [pgn_path, output_path] => (pgn_path, output_path, DEFAULT_PLIES),
+        [pgn_path, output_path, max_plies] => (
+            pgn_path,
+            output_path,
+            max_plies.parse().map_err(|_| "Plies must be a number")?,
+        ),
+        _ => return Err("Usage: book <pgn file> <output file> [plies]"),
+    };
+
+    // Game files aren't always valid UTF-8, but the moves themselves are always ASCII.
+    let text = fs::read(pgn_path).map_err(|_| "Game file couldn't be read")?;
+    let games = pgn::parse_games(&String::from_utf8_lossy(&text));
+    let book = Book::from_games(&games, max_plies);
+
+    fs::write(output_path, book.to_bytes()).map_err(|_| "Book file couldn't be written")?;
+
+    println!(
+        "Read {} games, wrote {} entries",
+        games.len(),
+        book.get_len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        game::{board::Board, pgn},
+        generators::Move,
+        uci::parse_move,
+    };
+
+    use super::{decode_move, encode_move, get_key, Book, BookEntry};
+
+    // The reference keys of the format's description.
+    const REFERENCE_KEYS: [(&str, u64); 9] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            0x463b96181691fc9c,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            0x823c9b50fd114196,
+        ),
+        (
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+            0x0756b94461c50fb0,
+        ),
+        (
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+            0x662fafb965db29d4,
+        ),
+        (
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            0x22a48b5a8e47ff78,
+        ),
+        (
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+            0x652a607ca3f242c1,
+        ),
+        (
+            "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+            0x00fdd303c946bdd9,
+        ),
+        (
+            "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+            0x3c8123ea7b067637,
+        ),
+        (
+            "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 1 4",
+            0x5c3f9b829b279560,
+        ),
+    ];
+
+    fn play(moves: &[&str]) -> Board {
+        let mut board = Board::default();
+
+        for text in moves {
+            board.make_move(parse_move(&board, text).unwrap());
+        }
+
+        board
+    }
+
+    #[test]
+    fn reference_keys() {
+        for (fen, key) in REFERENCE_KEYS {
+            assert_eq!(get_key(&Board::from_str(fen).unwrap()), key, "{}", fen);
+        }
+
+        // The keys don't depend on how the position was reached.
+        let moves = ["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"];
+
+        for length in 0..=moves.len() {
+            assert_eq!(get_key(&play(&moves[..length])), REFERENCE_KEYS[length].1);
+        }
+
+        let moves = ["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"];
+
+        assert_eq!(get_key(&play(&moves[..5])), REFERENCE_KEYS[7].1);
+        assert_eq!(get_key(&play(&moves)), REFERENCE_KEYS[8].1);
+    }
+
+    #[test]
+    fn move_encoding() {
+        let board = Board::from_str("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let encodings = [
+            ("e5e6", 0x092C),
+            ("e5d6", 0x092B),
+            ("b7b8q", 0x4C79),
+            ("b7b8n", 0x1C79),
+            ("e1g1", 0x0107),
+            ("e1c1", 0x0100),
+        ];
+
+        for (text, raw_move) in encodings {
+            let chess_move = parse_move(&board, text).unwrap();
+
+            assert_eq!(encode_move(&board, chess_move), raw_move, "{}", text);
+            assert_eq!(decode_move(&board, raw_move), Some(chess_move));
+        }
+
+        let board = Board::from_str("r3k2r/8/8/8/8/8/8/4K3 b kq - 0 1").unwrap();
+
+        assert_eq!(decode_move(&board, 0x0F3F), Some(Move::CastleKS));
+        assert_eq!(decode_move(&board, 0x0F38), Some(Move::CastleQS));
+        // Moving the king onto the rook isn't a legal regular move, and empty squares can't move.
+        assert_eq!(decode_move(&board, 0x0000), None);
+    }
+
+    #[test]
+    fn lookup_and_picking() {
+        let board = Board::default();
+        let key = get_key(&board);
+        let e4 = Move::from_str("pe2e4").unwrap();
+        let d4 = Move::from_str("pd2d4").unwrap();
+        let entries = [
+            BookEntry {
+                key,
+                raw_move: encode_move(&board, e4),
+                weight: 3,
+                learn: 0,
+            },
+            BookEntry {
+                key: key + 1,
+                raw_move: 0,
+                weight: 5,
+                learn: 7,
+            },
+            BookEntry {
+                key,
+                raw_move: encode_move(&board, d4),
+                weight: 1,
+                learn: 0,
+            },
+            // The move is illegal in the position, so it is never picked.
+            BookEntry {
+                key,
+                raw_move: 0x0000,
+                weight: 100,
+                learn: 0,
+            },
+        ];
+        let bytes = entries
+            .iter()
+            .flat_map(|entry| entry.to_bytes())
+            .collect::<Vec<_>>();
+        let book = Book::from_bytes(&bytes).unwrap();
+
+        assert_eq!(book.get_len(), 4);
+        assert_eq!(book.get_entries(key).len(), 3);
+        assert_eq!(book.get_entries(key + 1)[0], entries[1]);
+        assert!(book.get_entries(key + 2).is_empty());
+        assert_eq!(book.get_moves(&board), vec![(e4, 3), (d4, 1)]);
+
+        assert_eq!(book.pick_move(&board, 0), Some(e4));
+        assert_eq!(book.pick_move(&board, 2), Some(e4));
+        assert_eq!(book.pick_move(&board, 3), Some(d4));
+        assert_eq!(book.pick_move(&board, 4), Some(e4));
+        assert_eq!(book.pick_move(&play(&["e2e4"]), 0), None);
+
+        assert_eq!(
+            Book::from_bytes(&book.to_bytes()).unwrap().to_bytes(),
+            book.to_bytes()
+        );
+        assert!(Book::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn from_games() {
+        let games = pgn::parse_games(
+            r#"
+[Result "1-0"]
+1. e4 e5 2. Nf3 1-0
+
+[Result "1/2-1/2"]
+1. e4 c5 1/2-1/2
+
+[Result "0-1"]
+1. d4 d5 0-1
+
+[Event "Unfinished"]
+1. c4 *
+"#,
+        );
+        let book = Book::from_games(&games, 1);
+        let board = Board::default();
+
+        // The lost and unfinished games add nothing, and only the first ply is kept.
+        assert_eq!(
+            book.get_moves(&board),
+            vec![(Move::from_str("pe2e4").unwrap(), 3)]
+        );
+        assert_eq!(book.get_len(), 1);
+
+        let book = Book::from_games(&games, 2);
+
+        assert_eq!(
+            book.get_moves(&play(&["e2e4"])),
+            vec![(Move::from_str("pc7c5").unwrap(), 1)]
+        );
+        assert_eq!(
+            book.get_moves(&play(&["d2d4"])),
+            vec![(Move::from_str("pd7d5").unwrap(), 2)]
+        );
+    }
+}
@@ -0,0 +1,316 @@
+// Generates training data for the evaluation, from games the engine plays against itself.
+// Every game starts with a few random moves so that the games differ, and then every move is searched to a fixed amount of nodes.
+// The quiet positions of the games are written with their score and the result of the game, in the format the tuner reads.
+use std::{
+    fmt::Display,
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    engine::{
+        search::MATE_BOUND,
+        threads::SearchThreads,
+        time::{SearchLimits, SystemClock, TimeManager},
+        tt::TranspositionTable,
+    },
+    game::{board::Board, zobrist::splitmix64},
+    generators::{Move, MoveGen},
+    Player,
+};
+
+const DEFAULT_NODES: u64 = 5000;
+const RANDOM_PLIES: usize = 8;
+// Openings which are already won by one side after the random moves are thrown away.
+const MAX_OPENING_SCORE: i32 = 400;
+// The game is adjudicated as a win once the score is this high, instead of being played until the mate.
+const WIN_SCORE: i32 = 1000;
+// The games which go on for this long are adjudicated as draws.
+const MAX_PLIES: usize = 400;
+const FIFTY_MOVE_PLIES: u32 = 100;
+const TT_SIZE_MB: usize = 16;
+
+pub struct Record {
+    pub fen: String,
+    // The score of the search, from the perspective of white.
+    pub score: i32,
+    // The result of the game, from the perspective of white: one for a win, a half for a draw and zero for a loss.
+    pub result: f64,
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}; {}; {:.1}", self.fen, self.score, self.result)
+    }
+}
+
+// The random moves of the openings come from SplitMix64, so every game can be played again from its seed.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        splitmix64(&mut self.0)
+    }
+}
+
+// Whether neither player can ever mate, with at most a single knight or bishop on the board.
+pub fn is_insufficient_material(board: &Board) -> bool {
+    let (white, black) = (&board.moving_player, &board.moved_player);
+
+    (white.pawns | white.rooks | white.queens | black.pawns | black.rooks | black.queens).is_empty()
+        && (white.knights | white.bishops | black.knights | black.bishops).count_ones() <= 1
+}
+
+// Plays random moves from the starting position. Nothing is returned if the game ended during them.
+fn play_random_opening(random: &mut Random) -> Option<Board> {
+    let mut board = Board::default();
+
+    for _ in 0..RANDOM_PLIES {
+        let moves = MoveGen::run(board);
+
+        if moves.is_empty() {
+            return None;
+        }
+
+        board.make_move(moves[(random.next() % moves.len() as u64) as usize]);
+    }
+
+    Some(board)
+}
+
+// Plays a game with the given seed, searching every move to the amount of nodes.
+// Positions in check, or where the best move is a capture or a promotion, aren't quiet and thus aren't recorded.
+pub fn play_game(
+    seed: u64,
+    nodes: u64,
+    threads: &mut SearchThreads,
+    tt: &TranspositionTable,
+) -> Vec<Record> {
+    let mut random = Random(seed);
+
+    'game: loop {
+        let Some(mut board) = play_random_opening(&mut random) else {
+            continue;
+        };
+        let mut positions = Vec::new();
+        let mut hashes = vec![board.hash];
+        let mut fifty_move_plies = 0;
+
+        threads.clear();
+        tt.clear();
+
+        let result = loop {
+            if MoveGen::run(board).is_empty() {
+                break match (board.is_in_check(), board.current_player) {
+                    (false, _) => 0.5,
+                    (true, Player::White) => 0.0,
+                    (true, Player::Black) => 1.0,
+                };
+            }
+
+            let repetitions = hashes.iter().filter(|&&hash| hash == board.hash).count();
+
+            if is_insufficient_material(&board)
+                || fifty_move_plies >= FIFTY_MOVE_PLIES
+                || repetitions >= 3
+                || hashes.len() > MAX_PLIES
+            {
+                break 0.5;
+            }
+
+            let limits = SearchLimits {
+                nodes: Some(nodes),
+                ..Default::default()
+            };
+            // The current position is the last one of the hashes, and the search keeps track of it on its own.
+            threads.set_game_hashes(hashes[..hashes.len() - 1].to_vec());
+
+            let time =
+                TimeManager::new(&limits, board.current_player, Arc::new(SystemClock::new()));
+            let lines = threads.search(tt, &board, time, Arc::new(AtomicBool::new(false)), |_| {});
+            let (best_move, score, _) = lines[0];
+            let white_score = match board.current_player {
+                Player::White => score,
+                Player::Black => -score,
+            };
+
+            if positions.is_empty() && hashes.len() == 1 && score.abs() > MAX_OPENING_SCORE {
+                continue 'game;
+            }
+
+            if score.abs() >= WIN_SCORE {
+                break if white_score > 0 { 1.0 } else { 0.0 };
+            }
+
+            if !board.is_in_check()
+                && !board.is_capture(best_move)
+                && !matches!(best_move, Move::Promotion { .. })
+                && score.abs() < MATE_BOUND
+            {
+                positions.push((board.to_fen(), white_score));
+            }
+
+            fifty_move_plies = if board.is_zeroing(best_move) {
+                0
+            } else {
+                fifty_move_plies + 1
+            };
+
+            board.make_move(best_move);
+            hashes.push(board.hash);
+        };
+
+        return positions
+            .into_iter()
+            .map(|(fen, score)| Record { fen, score, result })
+            .collect();
+    }
+}
+
+// Runs the generation from the command line, given "<output file> <games> [nodes] [threads]".
+// The records are appended to the file, so the output of several runs can be gathered in one file.
+pub fn run(args: &[String]) -> Result<(), &'static str> {
+    let parse = |text: &String| {
+        text.parse::<u64>()
+            .map_err(|_| "Games, nodes and threads must be numbers")
+    };
+    let (output_path, games, nodes, thread_count) = match args {
+        [output_path, games] => (output_path, parse(games)?, DEFAULT_NODES, 1),
+        [output_path, games, nodes] => (output_path, parse(games)?, parse(nodes)?, 1),
+        [output_path, games, nodes, thread_count] => (
+            output_path,
+            parse(games)?,
+            parse(nodes)?,
+            parse(thread_count)?.max(1),
+        ),
+        _ => return Err("Usage: datagen <output file> <games> [nodes] [threads]"),
+    };
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(output_path)
+        .map_err(|_| "Output file couldn't be opened")?;
+    let mut writer = BufWriter::new(file);
+    // Every game is seeded differently, so the runs (and the threads) don't play the same games.
+    let base_seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64);
+    let next_game = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel::<Vec<Record>>();
+
+    thread::scope(|scope| {
+        for _ in 0..thread_count {
+            let sender = sender.clone();
+            let next_game = &next_game;
+
+            scope.spawn(move || {
+                let tt = TranspositionTable::new(TT_SIZE_MB);
+                let mut threads = SearchThreads::new(1);
+
+                loop {
+                    let game = next_game.fetch_add(1, Ordering::Relaxed) as u64;
+
+                    if game >= games {
+                        break;
+                    }
+
+                    let records = play_game(base_seed.wrapping_add(game), nodes, &mut threads, &tt);
+
+                    // The receiver is only gone when writing failed.
+                    if sender.send(records).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        drop(sender);
+
+        let mut position_count = 0;
+
+        for (index, records) in receiver.iter().enumerate() {
+            for record in &records {
+                writeln!(writer, "{}", record).map_err(|_| "Output file couldn't be written")?;
+            }
+
+            position_count += records.len();
+            println!(
+                "Game {} of {}, {} positions",
+                index + 1,
+                games,
+                position_count
+            );
+        }
+
+        writer
+            .flush()
+            .map_err(|_| "Output file couldn't be written")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        engine::{threads::SearchThreads, tt::TranspositionTable},
+        game::board::Board,
+        tuner::TuningPosition,
+    };
+
+    use super::{is_insufficient_material, play_game};
+
+    #[test]
+    fn games_are_recorded() {
+        let tt = TranspositionTable::new(1);
+        let mut threads = SearchThreads::new(1);
+        let records = play_game(7, 500, &mut threads, &tt);
+
+        assert!(!records.is_empty());
+
+        for record in &records {
+            let position = TuningPosition::from_str(&record.to_string()).unwrap();
+
+            assert!(!position.board.is_in_check());
+            assert_eq!(position.result, records[0].result);
+            assert_eq!(position.board.to_fen(), record.fen);
+        }
+
+        // The search is deterministic with a single thread, so the seed decides the whole game.
+        let replayed = play_game(7, 500, &mut threads, &tt);
+
+        assert_eq!(
+            replayed
+                .iter()
+                .map(|record| &record.fen)
+                .collect::<Vec<_>>(),
+            records.iter().map(|record| &record.fen).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn insufficient_material() {
+        for (fen, insufficient) in [
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+            ("4kn2/8/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4kn2/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", false),
+        ] {
+            assert_eq!(
+                is_insufficient_material(&Board::from_str(fen).unwrap()),
+                insufficient,
+                "{}",
+                fen
+            );
+        }
+    }
+}
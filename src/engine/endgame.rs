@@ -0,0 +1,362 @@
+// Recognizes endgames where the usual evaluation is misleading, and either replaces its score or scales it down.
+// The endgames are told apart by a material key, which packs the amount of every piece of a player.
+// See: https://www.chessprogramming.org/Endgame
+use crate::{
+    game::board::{Board, PlayerState},
+    generators::Square,
+    tables::FILE_MASKS,
+    BitBoard, PieceKind, Player,
+};
+
+use super::{
+    eval::get_material,
+    kpk,
+    params::{EvalParams, WEIGHTED_PIECES},
+    pawns,
+};
+
+// The score of an endgame which is known to be won, on top of the material. It stays far from the mate scores.
+pub const KNOWN_WIN: i32 = 10000;
+// The evaluation is multiplied by the scale, and then divided by this.
+pub const SCALE_NORMAL: i32 = 64;
+const OPPOSITE_BISHOPS_SCALE: i32 = 32;
+// The bonuses which guide the winning side: pushing the king of the opponent to the edge (or to a corner) and getting close to it.
+const PUSH_TO_EDGE: i32 = 20;
+const PUSH_TO_CORNER: i32 = 20;
+const PUSH_CLOSE: i32 = 10;
+const PUSH_PAWN: i32 = 20;
+const DARK_SQUARES: BitBoard = BitBoard(0xAA55AA55AA55AA55);
+
+// Every piece gets four bits, in the order of "WEIGHTED_PIECES" (so the pawns take the lowest ones).
+const fn get_key(queens: u32, rooks: u32, bishops: u32, knights: u32, pawns: u32) -> u32 {
+    queens << 16 | rooks << 12 | bishops << 8 | knights << 4 | pawns
+}
+
+const BARE_KING: u32 = get_key(0, 0, 0, 0, 0);
+const KING_BISHOP: u32 = get_key(0, 0, 1, 0, 0);
+const KING_KNIGHT: u32 = get_key(0, 0, 0, 1, 0);
+const KING_TWO_KNIGHTS: u32 = get_key(0, 0, 0, 2, 0);
+const KING_BISHOP_KNIGHT: u32 = get_key(0, 0, 1, 1, 0);
+const KING_ROOK: u32 = get_key(0, 1, 0, 0, 0);
+const KING_QUEEN: u32 = get_key(1, 0, 0, 0, 0);
+const KING_PAWN: u32 = get_key(0, 0, 0, 0, 1);
+// The bits of the pawns, which are masked out to match the pieces only.
+const PAWN_BITS: u32 = get_key(0, 0, 0, 0, 15);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Recognition {
+    // The score (from the perspective of the moving player) replaces the evaluation.
+    Score(i32),
+    // The evaluation is multiplied by the scale, out of "SCALE_NORMAL".
+    Scale(i32),
+}
+
+pub fn get_material_key(state: &PlayerState) -> u32 {
+    WEIGHTED_PIECES
+        .into_iter()
+        .rev()
+        .enumerate()
+        .map(|(index, piece_kind)| {
+            state.get_piece_bitboard(piece_kind).count_ones().min(15) << (4 * index)
+        })
+        .sum()
+}
+
+// The distance of a king walking between the squares.
+fn get_distance(first: Square, second: Square) -> i32 {
+    let file_distance = (first.0 % 8).abs_diff(second.0 % 8);
+    let rank_distance = (first.0 / 8).abs_diff(second.0 / 8);
+
+    file_distance.max(rank_distance) as i32
+}
+
+// How many steps (along the files and ranks) the square is from the closest edges, which is six in the center.
+fn get_edge_distance(square: Square) -> i32 {
+    let (file, rank) = (square.0 % 8, square.0 / 8);
+
+    (file.min(7 - file) + rank.min(7 - rank)) as i32
+}
+
+// The bare king can only be mated on the edge, so the winning side pushes it there with its own king.
+// See: https://www.chessprogramming.org/Mop-up_Evaluation
+fn evaluate_mop_up(params: &EvalParams, strong: &PlayerState, weak_king: Square) -> i32 {
+    let strong_king = strong.king.first_one_square();
+
+    KNOWN_WIN
+        + get_material(params, strong)
+        + PUSH_TO_EDGE * (6 - get_edge_distance(weak_king))
+        + PUSH_CLOSE * (7 - get_distance(strong_king, weak_king))
+}
+
+// A bishop and a knight can only mate in a corner of the colour of the bishop, so the king is pushed to the closest one.
+fn evaluate_bishop_knight(params: &EvalParams, strong: &PlayerState, weak_king: Square) -> i32 {
+    let strong_king = strong.king.first_one_square();
+    let corners = if (strong.bishops & DARK_SQUARES).isnt_empty() {
+        [Square::A1, Square::H8]
+    } else {
+        [Square::H1, Square::A8]
+    };
+    let corner_distance = corners
+        .into_iter()
+        .map(|corner| get_distance(corner, weak_king))
+        .min()
+        .unwrap();
+
+    KNOWN_WIN
+        + get_material(params, strong)
+        + PUSH_TO_CORNER * (7 - corner_distance)
+        + PUSH_CLOSE * (7 - get_distance(strong_king, weak_king))
+}
+
+fn get_promotion_square(player: Player, pawn: Square) -> Square {
+    match player {
+        Player::White => Square(56 + pawn.0 % 8),
+        Player::Black => Square(pawn.0 % 8),
+    }
+}
+
+fn is_rook_file(square: Square) -> bool {
+    matches!(square.0 % 8, 0 | 7)
+}
+
+// The bitbase is from the perspective of white, so the board is flipped when black has the pawn.
+fn evaluate_king_pawn(
+    params: &EvalParams,
+    strong_player: Player,
+    strong: &PlayerState,
+    weak_king: Square,
+    side: Player,
+) -> i32 {
+    let flip = |square: Square| match strong_player {
+        Player::White => square,
+        Player::Black => Square(square.0 ^ 56),
+    };
+    let pawn = strong.pawns.first_one_square();
+    let side = if side == strong_player {
+        Player::White
+    } else {
+        Player::Black
+    };
+
+    if !kpk::probe(
+        flip(strong.king.first_one_square()),
+        flip(pawn),
+        flip(weak_king),
+        side,
+    ) {
+        return 0;
+    }
+
+    KNOWN_WIN
+        + params.get_material(PieceKind::Pawn)
+        + PUSH_PAWN * pawns::get_relative_rank(strong_player, pawn.0) as i32
+}
+
+// Rook pawns with a bishop which doesn't control the promotion square can't be promoted against a king in the corner.
+fn is_wrong_bishop_draw(strong_player: Player, strong: &PlayerState, weak_king: Square) -> bool {
+    let pawn = strong.pawns.first_one_square();
+
+    if !is_rook_file(pawn) || (strong.pawns - FILE_MASKS[(pawn.0 % 8) as usize]).isnt_empty() {
+        return false;
+    }
+
+    // The pawns all stand on the same rook file, so they share the promotion square.
+    let promotion_square = BitBoard::from(get_promotion_square(strong_player, pawn));
+    let is_dark_bishop = (strong.bishops & DARK_SQUARES).isnt_empty();
+    let is_dark_promotion = (promotion_square & DARK_SQUARES).isnt_empty();
+
+    is_dark_bishop != is_dark_promotion
+        && get_distance(weak_king, promotion_square.first_one_square()) <= 1
+}
+
+// Recognizes the endgames where the player is the only one with winning chances.
+fn recognize_strong_side(
+    board: &Board,
+    params: &EvalParams,
+    strong_player: Player,
+) -> Option<Recognition> {
+    let strong = board.get_player_state(strong_player);
+    let weak = board.get_player_state(!strong_player);
+    let (strong_key, weak_key) = (get_material_key(strong), get_material_key(weak));
+    let weak_king = weak.king.first_one_square();
+
+    if weak_key != BARE_KING {
+        return None;
+    }
+
+    let score = match strong_key {
+        BARE_KING | KING_BISHOP | KING_KNIGHT | KING_TWO_KNIGHTS => 0,
+        KING_BISHOP_KNIGHT => evaluate_bishop_knight(params, strong, weak_king),
+        KING_ROOK | KING_QUEEN => evaluate_mop_up(params, strong, weak_king),
+        KING_PAWN => evaluate_king_pawn(
+            params,
+            strong_player,
+            strong,
+            weak_king,
+            board.current_player,
+        ),
+        // Any pieces with a queen or a rook (and no pawns) mate as easily.
+        _ if strong_key & PAWN_BITS == 0 && (strong.queens | strong.rooks).isnt_empty() => {
+            evaluate_mop_up(params, strong, weak_king)
+        }
+        _ if strong_key & !PAWN_BITS == KING_BISHOP
+            && is_wrong_bishop_draw(strong_player, strong, weak_king) =>
+        {
+            0
+        }
+        _ => return None,
+    };
+
+    Some(Recognition::Score(
+        if strong_player == board.current_player {
+            score
+        } else {
+            -score
+        },
+    ))
+}
+
+pub fn recognize(board: &Board, params: &EvalParams) -> Option<Recognition> {
+    for player in [Player::White, Player::Black] {
+        if let Some(recognition) = recognize_strong_side(board, params, player) {
+            return Some(recognition);
+        }
+    }
+
+    let (moving, moved) = (&board.moving_player, &board.moved_player);
+    let is_bishop_ending =
+        |state: &PlayerState| get_material_key(state) & !PAWN_BITS == KING_BISHOP;
+
+    // With a bishop of each colour, each side can blockade the pawns on the squares of its bishop, so even some pawns more are hard to win with.
+    if is_bishop_ending(moving)
+        && is_bishop_ending(moved)
+        && (moving.bishops & DARK_SQUARES).is_empty() != (moved.bishops & DARK_SQUARES).is_empty()
+    {
+        return Some(Recognition::Scale(OPPOSITE_BISHOPS_SCALE));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        engine::{eval, params::EvalParams, pawns::PawnTable},
+        game::board::Board,
+    };
+
+    use super::{get_material_key, recognize, Recognition, KING_BISHOP_KNIGHT, KNOWN_WIN};
+
+    fn recognize_fen(fen: &str) -> Option<Recognition> {
+        recognize(&Board::from_str(fen).unwrap(), &EvalParams::default())
+    }
+
+    fn get_score(fen: &str) -> i32 {
+        match recognize_fen(fen) {
+            Some(Recognition::Score(score)) => score,
+            recognition => panic!("{} is recognized as {:?}", fen, recognition),
+        }
+    }
+
+    #[test]
+    fn material_keys() {
+        let board = Board::from_str("4k3/8/8/8/8/8/8/1N2KB2 w - - 0 1").unwrap();
+
+        assert_eq!(get_material_key(&board.moving_player), KING_BISHOP_KNIGHT);
+        assert_eq!(get_material_key(&board.moved_player), 0);
+
+        let board = Board::default();
+
+        assert_eq!(get_material_key(&board.moving_player), 0x12228);
+        assert_eq!(
+            get_material_key(&board.moving_player),
+            get_material_key(&board.moved_player)
+        );
+    }
+
+    #[test]
+    fn insufficient_material_draws() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 b - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1",
+            "1n2k1n1/8/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            assert_eq!(get_score(fen), 0, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn mop_up() {
+        // The rook side is winning, whoever moves, and more so with the king of the opponent on the edge.
+        let center = get_score("8/8/8/3k4/8/8/8/R3K3 w - - 0 1");
+        let edge = get_score("3k4/8/8/8/8/8/8/R3K3 w - - 0 1");
+
+        assert!(center > KNOWN_WIN);
+        assert!(edge > center);
+        assert_eq!(get_score("3k4/8/8/8/8/8/8/R3K3 b - - 0 1"), -edge);
+
+        // Getting the kings closer is better too.
+        assert!(get_score("3k4/8/3K4/8/8/8/8/R7 w - - 0 1") > edge);
+        // This works for black, and with more pieces.
+        assert!(get_score("3k4/8/8/8/8/8/8/R1Q1K3 b - - 0 1") < -KNOWN_WIN);
+        assert!(get_score("3k4/8/8/8/8/8/q7/4K3 b - - 0 1") > KNOWN_WIN);
+    }
+
+    #[test]
+    fn bishop_and_knight() {
+        // The bishop on c1 is dark-squared, so the king must be driven to a1 or h8.
+        let right_corner = get_score("7k/8/8/8/8/8/8/2B1KN2 w - - 0 1");
+        let wrong_corner = get_score("k7/8/8/8/8/8/8/2B1KN2 w - - 0 1");
+
+        assert!(wrong_corner > KNOWN_WIN);
+        assert!(right_corner > wrong_corner);
+    }
+
+    #[test]
+    fn king_and_pawn() {
+        // The king of black is outside of the square of the pawn.
+        let win = get_score("8/8/k7/8/4P3/8/8/4K3 w - - 0 1");
+
+        assert!(win > KNOWN_WIN);
+        assert!(get_score("8/8/k7/4P3/8/8/8/4K3 w - - 0 1") > win);
+        // With black to move, the king gets into the square in time.
+        assert_eq!(get_score("8/8/k7/8/4P3/8/8/4K3 b - - 0 1"), 0);
+        // The same position for black, flipped.
+        assert_eq!(get_score("4k3/8/8/4p3/8/K7/8/8 b - - 0 1"), win);
+
+        // The king in front of a rook pawn can't be driven out.
+        assert_eq!(get_score("8/k7/8/8/P7/8/8/4K3 w - - 0 1"), 0);
+        assert_eq!(get_score("4k3/8/8/8/8/7p/8/7K w - - 0 1"), 0);
+    }
+
+    #[test]
+    fn wrong_bishop() {
+        // The bishop on b2 is dark-squared, and can't drive the king out of the light corner on a8.
+        assert_eq!(get_score("k7/8/8/P7/P7/8/1B6/4K3 w - - 0 1"), 0);
+        assert_eq!(recognize_fen("8/8/8/P3k3/8/8/1B6/4K3 w - - 0 1"), None);
+        assert_eq!(recognize_fen("k7/8/8/P7/8/8/2B5/4K3 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn opposite_bishops() {
+        let board = Board::from_str("4k3/5pb1/8/8/3P4/2PB4/8/4K3 w - - 0 1").unwrap();
+        let params = EvalParams::default();
+        let mut table = PawnTable::new();
+
+        assert_eq!(
+            recognize(&board, &params),
+            Some(Recognition::Scale(super::OPPOSITE_BISHOPS_SCALE))
+        );
+        assert!(eval::evaluate(&board, &params, &mut table) > 0);
+
+        // Bishops of the same colour are a usual endgame.
+        assert_eq!(
+            recognize_fen("4k3/5p2/5b2/8/3P4/2P5/3B4/4K3 w - - 0 1"),
+            None
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{
+    game::board::{Board, PlayerState},
+    generators::slides,
+    tables::{KING_MOVES, KNIGHT_MOVES},
+    BitBoard, PieceKind, Player,
+};
+
+use super::{
+    endgame::{self, Recognition},
+    king_safety, mobility,
+    params::{EvalParams, WEIGHTED_PIECES},
+    pawns::{self, PawnTable},
+};
+
+// These are the default material weights of the evaluation. The search always uses them to judge exchanges, whatever the weights are.
+pub const PAWN_VALUE: i32 = 100;
+pub const KNIGHT_VALUE: i32 = 320;
+pub const BISHOP_VALUE: i32 = 330;
+pub const ROOK_VALUE: i32 = 500;
+pub const QUEEN_VALUE: i32 = 900;
+
+pub(super) fn get_material(params: &EvalParams, player: &PlayerState) -> i32 {
+    WEIGHTED_PIECES
+        .into_iter()
+        .map(|piece_kind| {
+            player.get_piece_bitboard(piece_kind).count_ones() as i32
+                * params.get_material(piece_kind)
+        })
+        .sum()
+}
+
+// The squares attacked by the pieces of a kind (other than pawns), which are all blocked by anything but the empty squares.
+pub fn get_attacks(piece_kind: PieceKind, mut pieces: BitBoard, empty: BitBoard) -> BitBoard {
+    match piece_kind {
+        PieceKind::Queen => {
+            slides::get_diagonal_attacks(pieces, empty) | slides::get_cross_attacks(pieces, empty)
+        }
+        PieceKind::Rook => slides::get_cross_attacks(pieces, empty),
+        PieceKind::Bishop => slides::get_diagonal_attacks(pieces, empty),
+        PieceKind::Knight | PieceKind::King => {
+            let table = if piece_kind == PieceKind::Knight {
+                &KNIGHT_MOVES
+            } else {
+                &KING_MOVES
+            };
+            let mut attacks = BitBoard::empty();
+
+            while pieces.isnt_empty() {
+                attacks |= table[pieces.pop_first_one()];
+            }
+
+            attacks
+        }
+        PieceKind::Pawn => unreachable!("Pawn attacks depend on the player"),
+    }
+}
+
+// Returns the score of the position from the perspective of the moving player.
+pub fn evaluate(board: &Board, params: &EvalParams, pawn_table: &mut PawnTable) -> i32 {
+    let recognition = endgame::recognize(board, params);
+
+    if let Some(Recognition::Score(score)) = recognition {
+        return score;
+    }
+
+    // These are from the perspective of white.
+    let positional = pawns::evaluate_pawns(board, params, pawn_table)
+        + king_safety::evaluate_king(board, params, Player::White)
+        - king_safety::evaluate_king(board, params, Player::Black)
+        + mobility::evaluate_activity(board, params, Player::White)
+        - mobility::evaluate_activity(board, params, Player::Black);
+
+    let positional = match board.current_player {
+        Player::White => positional,
+        Player::Black => -positional,
+    };
+
+    let score = get_material(params, &board.moving_player)
+        - get_material(params, &board.moved_player)
+        + positional;
+
+    match recognition {
+        Some(Recognition::Scale(scale)) => score * scale / endgame::SCALE_NORMAL,
+        _ => score,
+    }
+}
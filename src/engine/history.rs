@@ -0,0 +1,303 @@
+// See: https://www.chessprogramming.org/History_Heuristic
+use crate::{
+    game::board::Board,
+    generators::{Move, Square},
+    PieceKind, Player,
+};
+
+use super::search::MAX_PLY;
+
+// History scores are kept within these bounds by the gravity of their updates.
+pub const MAX_HISTORY: i32 = 16384;
+const MAX_BONUS: i32 = 1200;
+
+// Identifies a move by what moves and where, which is what the quiet move tables are indexed by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveKey {
+    // The player and the piece kind, as "player * 6 + piece kind".
+    pub piece: usize,
+    pub origin: Square,
+    pub target: Square,
+}
+
+impl MoveKey {
+    // The move must be one of the moving player's.
+    pub fn new(board: &Board, chess_move: Move) -> Self {
+        let player = board.current_player;
+        let (piece_kind, origin, target) = match chess_move {
+            Move::Regular {
+                origin,
+                target,
+                piece_kind,
+                ..
+            } => (piece_kind, origin, target),
+            Move::EnPassant { origin } => (
+                PieceKind::Pawn,
+                origin,
+                board.ep_info.capture_point.first_one_square(),
+            ),
+            Move::Promotion { origin, target, .. } => (PieceKind::Pawn, origin, target),
+            // Castling is seen as a king move.
+            Move::CastleKS => match player {
+                Player::White => (PieceKind::King, Square::E1, Square::G1),
+                Player::Black => (PieceKind::King, Square::E8, Square::G8),
+            },
+            Move::CastleQS => match player {
+                Player::White => (PieceKind::King, Square::E1, Square::C1),
+                Player::Black => (PieceKind::King, Square::E8, Square::C8),
+            },
+        };
+
+        Self {
+            piece: player as usize * 6 + piece_kind as usize,
+            origin,
+            target,
+        }
+    }
+
+    fn get_index(&self) -> usize {
+        self.piece * 64 + self.target.0 as usize
+    }
+}
+
+// The moves played one and two plies before the current position, when they weren't null moves.
+pub type PreviousMoves = [Option<MoveKey>; 2];
+
+// The bonus for a good move, or the penalty for a bad one, grows with the depth it was found at.
+fn get_bonus(depth: i32) -> i32 {
+    (depth * depth).min(MAX_BONUS)
+}
+
+// Moves the entry towards the bonus, slower the closer it gets to the bounds, so it never leaves them.
+fn apply_gravity(entry: &mut i16, bonus: i32) {
+    let value = *entry as i32;
+
+    *entry = (value + bonus - value * bonus.abs() / MAX_HISTORY) as i16;
+}
+
+// The quiet move ordering tables a search thread learns from its own search.
+// They aren't shared between threads, since they are updated at every cutoff.
+pub struct SearchContext {
+    // Quiet moves which caused a cutoff at the same ply, in a sibling position.
+    // See: https://www.chessprogramming.org/Killer_Heuristic
+    killers: Vec<[Option<Move>; 2]>,
+    // Indexed by the moving player, the origin and the target of the move (a butterfly board).
+    history: Box<[[[i16; 64]; 64]; 2]>,
+    // Indexed by the piece and target of a previous move, and then by the piece and target of the move.
+    // See: https://www.chessprogramming.org/History_Heuristic#Continuation_History
+    continuation_history: Vec<[[i16; 64]; 12]>,
+    // The quiet move which last refuted each previous move, indexed by its piece and target.
+    // See: https://www.chessprogramming.org/Countermove_Heuristic
+    counter_moves: Vec<Option<Move>>,
+}
+
+impl Default for SearchContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchContext {
+    pub fn new() -> Self {
+        Self {
+            killers: vec![[None; 2]; MAX_PLY + 1],
+            history: Box::new([[[0; 64]; 64]; 2]),
+            continuation_history: vec![[[0; 64]; 12]; 12 * 64],
+            counter_moves: vec![None; 12 * 64],
+        }
+    }
+
+    // Forgets everything, like when a new game starts.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    // Called before every search: the killers belong to a different position, and the history is weighed down so that newer results matter more.
+    pub fn age(&mut self) {
+        self.killers.fill([None; 2]);
+
+        for entry in self
+            .history
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .chain(self.continuation_history.iter_mut().flatten().flatten())
+        {
+            *entry /= 2;
+        }
+    }
+
+    pub fn get_killers(&self, ply: usize) -> [Option<Move>; 2] {
+        self.killers[ply]
+    }
+
+    pub fn get_counter_move(&self, previous_moves: &PreviousMoves) -> Option<Move> {
+        previous_moves[0].and_then(|previous| self.counter_moves[previous.get_index()])
+    }
+
+    // The sum of the butterfly history and the continuation histories of the quiet move.
+    pub fn get_quiet_score(
+        &self,
+        player: Player,
+        key: MoveKey,
+        previous_moves: &PreviousMoves,
+    ) -> i32 {
+        let mut score = self.history[player][key.origin][key.target] as i32;
+
+        for previous in previous_moves.iter().flatten() {
+            score += self.continuation_history[previous.get_index()][key.piece][key.target] as i32;
+        }
+
+        score
+    }
+
+    fn update_quiet_score(
+        &mut self,
+        player: Player,
+        key: MoveKey,
+        previous_moves: &PreviousMoves,
+        bonus: i32,
+    ) {
+        apply_gravity(&mut self.history[player][key.origin][key.target], bonus);
+
+        for previous in previous_moves.iter().flatten() {
+            apply_gravity(
+                &mut self.continuation_history[previous.get_index()][key.piece][key.target],
+                bonus,
+            );
+        }
+    }
+
+    // Called when a quiet move causes a beta cutoff. The quiet moves searched before it are penalized, since they failed to do so.
+    pub fn update_cutoff(
+        &mut self,
+        board: &Board,
+        ply: usize,
+        depth: i32,
+        chess_move: Move,
+        previous_moves: &PreviousMoves,
+        failed_quiets: &[MoveKey],
+    ) {
+        let player = board.current_player;
+        let bonus = get_bonus(depth);
+
+        if self.killers[ply][0] != Some(chess_move) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(chess_move);
+        }
+
+        if let Some(previous) = previous_moves[0] {
+            self.counter_moves[previous.get_index()] = Some(chess_move);
+        }
+
+        self.update_quiet_score(
+            player,
+            MoveKey::new(board, chess_move),
+            previous_moves,
+            bonus,
+        );
+
+        for &key in failed_quiets {
+            self.update_quiet_score(player, key, previous_moves, -bonus);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        game::board::Board,
+        generators::{Move, Square},
+        Player,
+    };
+
+    use super::{MoveKey, SearchContext, MAX_HISTORY};
+
+    #[test]
+    fn keys() {
+        let board = Board::from_str("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+
+        assert_eq!(
+            MoveKey::new(&board, Move::CastleKS),
+            MoveKey {
+                piece: 0,
+                origin: Square::E1,
+                target: Square::G1
+            }
+        );
+
+        let board = Board::from_str("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+
+        assert_eq!(
+            MoveKey::new(&board, Move::from_str("d4").unwrap()),
+            MoveKey {
+                piece: 6 + 5,
+                origin: Square::D4,
+                target: Square::E3
+            }
+        );
+    }
+
+    #[test]
+    fn cutoffs_are_learned() {
+        let board = Board::default();
+        let mut context = SearchContext::new();
+        let good = Move::from_str("ng1f3").unwrap();
+        let bad = Move::from_str("pa2a3").unwrap();
+        let previous = [
+            Some(MoveKey::new(&board, Move::from_str("pe2e4").unwrap())),
+            None,
+        ];
+
+        context.update_cutoff(&board, 3, 4, good, &previous, &[MoveKey::new(&board, bad)]);
+
+        assert_eq!(context.get_killers(3), [Some(good), None]);
+        assert_eq!(context.get_killers(4), [None, None]);
+        assert_eq!(context.get_counter_move(&previous), Some(good));
+
+        let good_score =
+            context.get_quiet_score(Player::White, MoveKey::new(&board, good), &previous);
+        let bad_score =
+            context.get_quiet_score(Player::White, MoveKey::new(&board, bad), &previous);
+
+        // Both the butterfly and the continuation history were updated.
+        assert_eq!(good_score, 2 * 16);
+        assert_eq!(bad_score, -2 * 16);
+
+        // A second killer pushes the first one to the second slot, but the same killer isn't stored twice.
+        context.update_cutoff(&board, 3, 4, bad, &previous, &[]);
+        context.update_cutoff(&board, 3, 4, bad, &previous, &[]);
+
+        assert_eq!(context.get_killers(3), [Some(bad), Some(good)]);
+
+        context.age();
+
+        assert_eq!(context.get_killers(3), [None, None]);
+        assert_eq!(
+            context.get_quiet_score(Player::White, MoveKey::new(&board, good), &previous),
+            good_score / 2
+        );
+
+        context.clear();
+
+        assert_eq!(context.get_counter_move(&previous), None);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let board = Board::default();
+        let mut context = SearchContext::new();
+        let chess_move = Move::from_str("ng1f3").unwrap();
+        let key = MoveKey::new(&board, chess_move);
+
+        for _ in 0..1000 {
+            context.update_cutoff(&board, 0, 100, chess_move, &[None, None], &[]);
+        }
+
+        let score = context.get_quiet_score(Player::White, key, &[None, None]);
+
+        assert!(score > MAX_HISTORY / 2 && score <= MAX_HISTORY);
+    }
+}
@@ -0,0 +1,184 @@
+// See: https://www.chessprogramming.org/King_Safety
+use crate::{game::board::Board, tables::KING_MOVES, BitBoard, Player};
+
+use super::{
+    eval,
+    params::{EvalParams, WEIGHTED_PIECES},
+    pawns::move_forward,
+};
+
+// The danger grows with the square of the attack, since attacks of several pieces support each other.
+const DANGER_DIVISOR: i32 = 4;
+const MAX_DANGER: i32 = 500;
+
+// The squares around the king, and those in front of them, which the king needs to be defended on.
+pub fn get_king_zone(player: Player, king: BitBoard) -> BitBoard {
+    let around = KING_MOVES[king.first_one_square()] | king;
+
+    around | move_forward(player, around)
+}
+
+// Scores the pawns around the king: those of the player shelter it, while those of the opponent storm it.
+fn evaluate_pawns(
+    params: &EvalParams,
+    player: Player,
+    king: BitBoard,
+    pawns: BitBoard,
+    opponent_pawns: BitBoard,
+) -> i32 {
+    let king_row = king | king.move_left() | king.move_right();
+    let files = king_row.smear_ones_files();
+    let mut score = 0;
+    let mut row = king_row;
+
+    for (distance, storm_penalty) in params.storm_penalty.into_iter().enumerate() {
+        row = move_forward(player, row);
+
+        if let Some(bonus) = params.shield_bonus.get(distance) {
+            score += bonus * (pawns & row).count_ones() as i32;
+        }
+
+        score -= storm_penalty * (opponent_pawns & row).count_ones() as i32;
+    }
+
+    for file in [king, king.move_left(), king.move_right()] {
+        let file = file.smear_ones_files() & files;
+
+        if file.is_empty() || (pawns & file).isnt_empty() {
+            continue;
+        }
+
+        score -= if (opponent_pawns & file).is_empty() {
+            params.open_file_penalty
+        } else {
+            params.semi_open_file_penalty
+        };
+    }
+
+    score
+}
+
+// Returns the danger of the pieces of the opponent attacking the king zone of the player.
+// A single attacker is seldom a threat, so there needs to be at least two.
+pub fn get_king_danger(board: &Board, params: &EvalParams, player: Player) -> i32 {
+    let king = board.get_player_state(player).king;
+    let opponent = board.get_player_state(!player);
+    let zone = get_king_zone(player, king);
+    let empty = !(board.moving_player.pieces | board.moved_player.pieces);
+    let mut attackers = 0;
+    let mut attack = 0;
+
+    for (piece_kind, weight) in WEIGHTED_PIECES.into_iter().zip(params.attack_weights) {
+        let mut pieces = opponent.get_piece_bitboard(piece_kind);
+
+        while pieces.isnt_empty() {
+            let attacked = eval::get_attacks(piece_kind, pieces.pfo_as_bitboard(), empty) & zone;
+
+            if attacked.isnt_empty() {
+                attackers += 1;
+                attack += weight * attacked.count_ones() as i32;
+            }
+        }
+    }
+
+    if attackers < 2 {
+        return 0;
+    }
+
+    (attack * attack / DANGER_DIVISOR).min(MAX_DANGER)
+}
+
+// Scores the safety of the king of the player, from the perspective of the player.
+pub fn evaluate_king(board: &Board, params: &EvalParams, player: Player) -> i32 {
+    let state = board.get_player_state(player);
+
+    evaluate_pawns(
+        params,
+        player,
+        state.king,
+        state.pawns,
+        board.get_player_state(!player).pawns,
+    ) - get_king_danger(board, params, player)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        engine::{params::EvalParams, test_utils::mirror_fen},
+        game::board::Board,
+        Player,
+    };
+
+    use super::{evaluate_king, get_king_danger};
+
+    fn king_score(fen: &str, player: Player) -> i32 {
+        evaluate_king(
+            &Board::from_str(fen).unwrap(),
+            &EvalParams::default(),
+            player,
+        )
+    }
+
+    #[test]
+    fn pawn_shelter() {
+        let intact = king_score("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1", Player::White);
+        let advanced = king_score("4k3/8/8/8/8/6P1/5P1P/6K1 w - - 0 1", Player::White);
+        let semi_open = king_score("4k3/6p1/8/8/8/8/5P1P/6K1 w - - 0 1", Player::White);
+        let open = king_score("4k3/8/8/8/8/8/5P1P/6K1 w - - 0 1", Player::White);
+
+        assert!(intact > advanced);
+        assert!(advanced > open);
+        assert!(semi_open > open);
+
+        // Pawns of the opponent closing in on the king are a threat.
+        let stormed = king_score("4k3/8/8/8/6p1/8/5PPP/6K1 w - - 0 1", Player::White);
+
+        assert!(intact > stormed);
+    }
+
+    #[test]
+    fn attacks_on_the_king() {
+        let params = EvalParams::default();
+
+        // The queen alone isn't dangerous yet, but it is once another piece joins the attack.
+        let queen = Board::from_str("6k1/5ppp/8/8/8/8/5PPP/Q5K1 b - - 0 1").unwrap();
+        let queen_and_rook = Board::from_str("6k1/5ppp/8/8/7q/3r4/5PPP/6K1 w - - 0 1").unwrap();
+        let queen_alone = Board::from_str("6k1/5ppp/8/8/7q/8/5PPP/6K1 w - - 0 1").unwrap();
+
+        assert_eq!(get_king_danger(&queen, &params, Player::Black), 0);
+        assert_eq!(get_king_danger(&queen_alone, &params, Player::White), 0);
+        assert!(get_king_danger(&queen_and_rook, &params, Player::White) > 0);
+
+        // Every attacker adds to the danger.
+        let with_knight = Board::from_str("6k1/5ppp/8/8/4n2q/3r4/5PPP/6K1 w - - 0 1").unwrap();
+
+        assert!(
+            get_king_danger(&with_knight, &params, Player::White)
+                > get_king_danger(&queen_and_rook, &params, Player::White)
+        );
+    }
+
+    #[test]
+    fn symmetric() {
+        let params = EvalParams::default();
+
+        for fen in [
+            "r1bq1rk1/pp1nbppp/2p1pn2/3p2B1/2PP4/2NBPN2/PP3PPP/R2QK2R w KQ - 2 8",
+            "r1b2rk1/pp3ppp/2n1pq2/3p4/3P3Q/2PB1N2/P4PPP/R4RK1 w - - 0 13",
+        ] {
+            let board = Board::from_str(fen).unwrap();
+            let mirrored = Board::from_str(&mirror_fen(fen)).unwrap();
+
+            assert_eq!(
+                evaluate_king(&board, &params, Player::White),
+                evaluate_king(&mirrored, &params, Player::Black)
+            );
+            assert_eq!(
+                evaluate_king(&board, &params, Player::Black),
+                evaluate_king(&mirrored, &params, Player::White)
+            );
+        }
+    }
+}
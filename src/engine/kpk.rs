@@ -0,0 +1,407 @@
+// A bitbase of every king and pawn versus king position, telling whether the side with the pawn wins.
+// It is generated by retrograde analysis the first time it is probed, and keeps a single bit per position.
+// See: https://www.chessprogramming.org/KPK
+use std::sync::LazyLock;
+
+use crate::{
+    generators::Square,
+    tables::{KING_MOVES, PAWN_ATTACKS},
+    BitBoard, PieceKind, Player,
+};
+
+use super::eval;
+
+// The pawn is always white, and on the files from "a" to "d" (the others are mirrored), on the ranks from 2 to 7.
+const PAWN_SQUARES: usize = 24;
+const POSITIONS: usize = 2 * 64 * 64 * PAWN_SQUARES;
+
+static BITBASE: LazyLock<Vec<u64>> = LazyLock::new(generate);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Outcome {
+    Invalid,
+    Unknown,
+    Draw,
+    Win,
+}
+
+fn get_index(side: Player, white_king: Square, pawn: Square, black_king: Square) -> usize {
+    let pawn_index = (pawn.0 / 8 - 1) * 4 + pawn.0 % 8;
+
+    side as usize
+        + 2 * (black_king.0 as usize + 64 * (white_king.0 as usize + 64 * pawn_index as usize))
+}
+
+fn decode_index(index: usize) -> (Player, Square, Square, Square) {
+    let side = if index.is_multiple_of(2) {
+        Player::White
+    } else {
+        Player::Black
+    };
+    let black_king = Square((index / 2 % 64) as u32);
+    let white_king = Square((index / 128 % 64) as u32);
+    let pawn_index = (index / (128 * 64)) as u32;
+
+    (
+        side,
+        white_king,
+        Square((pawn_index / 4 + 1) * 8 + pawn_index % 4),
+        black_king,
+    )
+}
+
+// Pushing the pawn on the seventh rank wins when the new queen (or rook, which avoids some stalemates) can't be taken, and the king of black isn't stalemated.
+fn is_promotion_win(white_king: Square, pawn: Square, black_king: Square) -> bool {
+    let target = pawn.move_up(1);
+
+    if target == white_king || target == black_king {
+        return false;
+    }
+
+    if KING_MOVES[black_king].get_bit(target) && !KING_MOVES[white_king].get_bit(target) {
+        return false;
+    }
+
+    // The king of black doesn't block the attacks, since it can't escape along them.
+    let empty = !(BitBoard::from(white_king) | BitBoard::from(target));
+
+    [PieceKind::Queen, PieceKind::Rook]
+        .into_iter()
+        .any(|piece_kind| {
+            let attacks =
+                eval::get_attacks(piece_kind, target.into(), empty) | KING_MOVES[white_king];
+
+            attacks.get_bit(black_king) || (KING_MOVES[black_king] - attacks).isnt_empty()
+        })
+}
+
+// Classifies the positions which don't depend on any other: the illegal ones, the winning promotions, and the draws where black takes the pawn or is stalemated.
+fn classify_initial(index: usize) -> Outcome {
+    let (side, white_king, pawn, black_king) = decode_index(index);
+
+    if white_king == black_king
+        || white_king == pawn
+        || black_king == pawn
+        || KING_MOVES[white_king].get_bit(black_king)
+        || (side == Player::White && PAWN_ATTACKS[Player::White][pawn].get_bit(black_king))
+    {
+        return Outcome::Invalid;
+    }
+
+    match side {
+        Player::White => {
+            if pawn.get_row() == 6 && is_promotion_win(white_king, pawn, black_king) {
+                Outcome::Win
+            } else {
+                Outcome::Unknown
+            }
+        }
+        Player::Black => {
+            let attacked = KING_MOVES[white_king] | PAWN_ATTACKS[Player::White][pawn];
+            let escapes = KING_MOVES[black_king] - attacked;
+
+            if escapes.get_bit(pawn) || escapes.is_empty() && !attacked.get_bit(black_king) {
+                Outcome::Draw
+            } else {
+                Outcome::Unknown
+            }
+        }
+    }
+}
+
+// Finds the outcome from those of the positions after every move, where white wins when any of its moves wins, and black draws when any of its moves draws.
+fn classify(index: usize, outcomes: &[Outcome]) -> Outcome {
+    let (side, white_king, pawn, black_king) = decode_index(index);
+    let (good, bad) = match side {
+        Player::White => (Outcome::Win, Outcome::Draw),
+        Player::Black => (Outcome::Draw, Outcome::Win),
+    };
+    let mut successors = Vec::with_capacity(10);
+
+    match side {
+        Player::White => {
+            let mut targets = KING_MOVES[white_king] - KING_MOVES[black_king] - pawn.into();
+
+            while targets.isnt_empty() {
+                successors.push(get_index(
+                    Player::Black,
+                    targets.pop_first_one(),
+                    pawn,
+                    black_king,
+                ));
+            }
+
+            // Promotions were already classified, and a pawn is blocked by either king.
+            if pawn.get_row() < 6 {
+                let push = pawn.move_up(1);
+
+                if push != white_king && push != black_king {
+                    successors.push(get_index(Player::Black, white_king, push, black_king));
+
+                    let double_push = push.move_up(1);
+
+                    if pawn.get_row() == 1 && double_push != white_king && double_push != black_king
+                    {
+                        successors.push(get_index(
+                            Player::Black,
+                            white_king,
+                            double_push,
+                            black_king,
+                        ));
+                    }
+                }
+            }
+        }
+        Player::Black => {
+            // Taking the pawn was already classified, so it is left out with the attacked squares.
+            let mut targets = KING_MOVES[black_king]
+                - KING_MOVES[white_king]
+                - PAWN_ATTACKS[Player::White][pawn]
+                - pawn.into();
+
+            while targets.isnt_empty() {
+                successors.push(get_index(
+                    Player::White,
+                    white_king,
+                    pawn,
+                    targets.pop_first_one(),
+                ));
+            }
+        }
+    }
+
+    let mut outcome = bad;
+
+    for successor in successors {
+        match outcomes[successor] {
+            outcome if outcome == good => return good,
+            Outcome::Unknown => outcome = Outcome::Unknown,
+            _ => {}
+        }
+    }
+
+    outcome
+}
+
+// Classifies every position again, until none changes. The positions still unknown then can't be won.
+fn generate() -> Vec<u64> {
+    let mut outcomes = (0..POSITIONS).map(classify_initial).collect::<Vec<_>>();
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for index in 0..POSITIONS {
+            if outcomes[index] == Outcome::Unknown {
+                outcomes[index] = classify(index, &outcomes);
+                changed |= outcomes[index] != Outcome::Unknown;
+            }
+        }
+    }
+
+    let mut bitbase = vec![0; POSITIONS / 64];
+
+    for (index, &outcome) in outcomes.iter().enumerate() {
+        if outcome == Outcome::Win {
+            bitbase[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    bitbase
+}
+
+// Returns whether white, with the pawn, wins with the side to move. Illegal positions aren't won.
+pub fn probe(white_king: Square, pawn: Square, black_king: Square, side: Player) -> bool {
+    if !(1..=6).contains(&pawn.get_row()) {
+        return false;
+    }
+
+    // The position is mirrored so that the pawn is on the files from "a" to "d".
+    let mirror = |square: Square| {
+        if pawn.0 % 8 >= 4 {
+            Square(square.0 ^ 7)
+        } else {
+            square
+        }
+    };
+    let index = get_index(side, mirror(white_king), mirror(pawn), mirror(black_king));
+
+    (BITBASE[index / 64] >> (index % 64)) & 1 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        game::board::Board,
+        generators::{Move, MoveGen, Square},
+        PieceKind, Player,
+    };
+
+    use super::{classify_initial, decode_index, get_index, probe, Outcome, POSITIONS};
+
+    fn probe_squares(white_king: &str, pawn: &str, black_king: &str, side: Player) -> bool {
+        probe(
+            Square::from_str(white_king).unwrap(),
+            Square::from_str(pawn).unwrap(),
+            Square::from_str(black_king).unwrap(),
+            side,
+        )
+    }
+
+    fn to_board(index: usize) -> Board {
+        let (side, white_king, pawn, black_king) = decode_index(index);
+        let mut rows = [['1'; 8]; 8];
+
+        for (square, piece) in [(white_king, 'K'), (pawn, 'P'), (black_king, 'k')] {
+            rows[square.get_row() as usize][(square.0 % 8) as usize] = piece;
+        }
+
+        let placement = rows
+            .iter()
+            .rev()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("/");
+        let side = if side == Player::White { 'w' } else { 'b' };
+
+        Board::from_str(&format!("{} {} - - 0 1", placement, side)).unwrap()
+    }
+
+    // The outcome of a move which ends the pawn endgame, by promoting or taking the pawn.
+    fn get_terminal_outcome(board: &Board, chess_move: Move) -> Option<bool> {
+        let mut board_copy = *board;
+        board_copy.make_move(chess_move);
+
+        match chess_move {
+            Move::Promotion {
+                target,
+                promotion_to,
+                ..
+            } => {
+                let replies = MoveGen::run(board_copy);
+                let is_taken = replies.iter().any(|reply| {
+                    matches!(reply, Move::Regular { target: reply_target, .. } if *reply_target == target)
+                });
+
+                Some(
+                    matches!(promotion_to, PieceKind::Queen | PieceKind::Rook)
+                        && !is_taken
+                        && (!replies.is_empty() || board_copy.is_in_check()),
+                )
+            }
+            _ if board_copy.get_player_state(Player::White).pawns.is_empty() => Some(false),
+            _ => None,
+        }
+    }
+
+    // Solves every position again with the move generator of the engine, without any of the rules of the bitbase.
+    #[test]
+    fn matches_brute_force() {
+        let mut successors = vec![Vec::new(); POSITIONS];
+        let mut wins = vec![false; POSITIONS];
+        let mut is_valid = vec![false; POSITIONS];
+
+        for index in 0..POSITIONS {
+            if classify_initial(index) == Outcome::Invalid {
+                continue;
+            }
+
+            let board = to_board(index);
+            let moves = MoveGen::run(board);
+            is_valid[index] = true;
+
+            // Without any moves, a stalemate is a draw and a checkmate is a win.
+            if moves.is_empty() && !board.is_in_check() {
+                successors[index].push(Err(false));
+            }
+
+            for chess_move in moves {
+                let successor = match get_terminal_outcome(&board, chess_move) {
+                    Some(outcome) => Err(outcome),
+                    None => {
+                        let mut board_copy = board;
+                        board_copy.make_move(chess_move);
+
+                        Ok(get_index(
+                            board_copy.current_player,
+                            board_copy
+                                .get_player_state(Player::White)
+                                .king
+                                .first_one_square(),
+                            board_copy
+                                .get_player_state(Player::White)
+                                .pawns
+                                .first_one_square(),
+                            board_copy
+                                .get_player_state(Player::Black)
+                                .king
+                                .first_one_square(),
+                        ))
+                    }
+                };
+
+                successors[index].push(successor);
+            }
+        }
+
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for index in 0..POSITIONS {
+                if !is_valid[index] || wins[index] {
+                    continue;
+                }
+
+                let mut outcomes = successors[index].iter().map(|successor| match *successor {
+                    Ok(successor) => wins[successor],
+                    Err(outcome) => outcome,
+                });
+                let (side, ..) = decode_index(index);
+                let win = match side {
+                    Player::White => outcomes.any(|win| win),
+                    Player::Black => outcomes.all(|win| win),
+                };
+
+                wins[index] = win;
+                changed |= win;
+            }
+        }
+
+        for index in (0..POSITIONS).filter(|&index| is_valid[index]) {
+            let (side, white_king, pawn, black_king) = decode_index(index);
+
+            assert_eq!(
+                probe(white_king, pawn, black_king, side),
+                wins[index],
+                "{}",
+                to_board(index).to_fen()
+            );
+        }
+    }
+
+    #[test]
+    fn known_positions() {
+        // The king in front of its pawn wins with the opposition, and draws without it.
+        assert!(probe_squares("e5", "e4", "e7", Player::Black));
+        assert!(!probe_squares("e5", "e4", "e7", Player::White));
+        // On the sixth rank, the opposition doesn't matter.
+        assert!(probe_squares("e6", "e5", "e8", Player::White));
+        assert!(probe_squares("e6", "e5", "e8", Player::Black));
+        // The king of black is outside of the square of the pawn.
+        assert!(probe_squares("a1", "c4", "h8", Player::White));
+        assert!(!probe_squares("a1", "c4", "f7", Player::White));
+        // A rook pawn can't be won against the king in the corner.
+        assert!(!probe_squares("h6", "h5", "h8", Player::White));
+        // The files are mirrored, so the same position on the other side gives the same outcome.
+        assert_eq!(
+            probe_squares("b6", "b5", "b8", Player::Black),
+            probe_squares("g6", "g5", "g8", Player::Black)
+        );
+        // Pawns on the first or last rank can't exist.
+        assert!(!probe_squares("a1", "e8", "h8", Player::White));
+    }
+}
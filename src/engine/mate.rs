@@ -0,0 +1,303 @@
+// Proves forced mates, instead of estimating the best move like the regular search.
+// The attacker needs one move which mates against every reply of the defender, so it's an AND/OR tree search.
+// See: https://www.chessprogramming.org/Mate_Search
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    game::board::Board,
+    generators::{Move, MoveGen},
+};
+
+use super::time::TimeManager;
+
+const NODES_BETWEEN_CHECKS: u64 = 2048;
+
+pub struct MateSearcher {
+    pub nodes: u64,
+    // When set, the attacker only tries checking moves. Most problems are still solved, in far less time, but quiet mates are missed.
+    checks_only: bool,
+    time: TimeManager,
+    stop_signal: Arc<AtomicBool>,
+    stopped: bool,
+}
+
+impl Default for MateSearcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MateSearcher {
+    pub fn new() -> Self {
+        Self {
+            nodes: 0,
+            checks_only: false,
+            time: TimeManager::default(),
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            stopped: false,
+        }
+    }
+
+    pub fn set_checks_only(&mut self, checks_only: bool) {
+        self.checks_only = checks_only;
+    }
+
+    // Only the node limit and the hard time limit of the time manager apply.
+    pub fn set_time_manager(&mut self, time: TimeManager) {
+        self.time = time;
+    }
+
+    pub fn set_stop_signal(&mut self, stop_signal: Arc<AtomicBool>) {
+        self.stop_signal = stop_signal;
+    }
+
+    pub fn get_elapsed(&self) -> Duration {
+        self.time.get_elapsed()
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    // Looks for a mate in at most the given amount of moves for the player to move, trying the shorter mates first.
+    // Returns the mating line, where the defender plays the reply which delays the mate the longest.
+    // Nothing is returned when there is no such mate, or when the search was stopped before finding one.
+    pub fn search(&mut self, board: &Board, max_moves: u32) -> Option<Vec<Move>> {
+        self.nodes = 0;
+        self.stopped = false;
+
+        (1..=max_moves).find_map(|moves| {
+            if self.stopped {
+                return None;
+            }
+
+            self.attack(board, moves)
+        })
+    }
+
+    fn count_node(&mut self) {
+        self.nodes += 1;
+
+        if self.time.is_node_limit_reached(self.nodes) {
+            self.stopped = true;
+        }
+
+        if self.nodes.is_multiple_of(NODES_BETWEEN_CHECKS)
+            && (self.stop_signal.load(Ordering::Relaxed) || self.time.is_hard_limit_reached())
+        {
+            self.stopped = true;
+        }
+    }
+
+    // Returns the line of a move which mates in at most "moves" moves, if there is one.
+    fn attack(&mut self, board: &Board, moves: u32) -> Option<Vec<Move>> {
+        self.count_node();
+
+        let mut checks = Vec::new();
+        let mut quiets = Vec::new();
+
+        for chess_move in MoveGen::run(*board) {
+            let mut board = *board;
+            board.make_move(chess_move);
+
+            if board.is_in_check() {
+                checks.push((chess_move, board));
+            } else if moves > 1 && !self.checks_only {
+                // The last move has to give check to mate, so it's the only one which can.
+                quiets.push((chess_move, board));
+            }
+        }
+
+        // Checks leave the defender few replies, so they are refuted or proven the fastest.
+        for (chess_move, board) in checks.into_iter().chain(quiets) {
+            if self.stopped {
+                return None;
+            }
+
+            if let Some(mut line) = self.defend(&board, moves) {
+                line.insert(0, chess_move);
+
+                return Some(line);
+            }
+        }
+
+        None
+    }
+
+    // Returns the line where the defender is mated the latest, if every reply gets mated within the remaining moves.
+    // "moves" includes the attacker move which was just played.
+    fn defend(&mut self, board: &Board, moves: u32) -> Option<Vec<Move>> {
+        self.count_node();
+
+        let replies = MoveGen::run(*board);
+
+        if replies.is_empty() {
+            // A stalemate is no mate.
+            return board.is_in_check().then(Vec::new);
+        }
+
+        if moves == 1 {
+            return None;
+        }
+
+        let mut longest_line: Option<Vec<Move>> = None;
+
+        for reply in replies {
+            if self.stopped {
+                return None;
+            }
+
+            let mut board = *board;
+            board.make_move(reply);
+
+            let mut line = self.attack(&board, moves - 1)?;
+            line.insert(0, reply);
+
+            if longest_line
+                .as_ref()
+                .is_none_or(|longest_line| line.len() > longest_line.len())
+            {
+                longest_line = Some(line);
+            }
+        }
+
+        longest_line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use crate::{
+        engine::time::{FakeClock, SearchLimits, TimeManager},
+        game::board::Board,
+        generators::{Move, MoveGen},
+    };
+
+    use super::MateSearcher;
+
+    // Plays the line, and tells whether it ends in a mate.
+    fn is_mating_line(board: &Board, line: &[Move]) -> bool {
+        let mut board = *board;
+
+        for &chess_move in line {
+            board.make_move(chess_move);
+        }
+
+        board.is_in_check() && MoveGen::run(board).is_empty()
+    }
+
+    #[test]
+    fn finds_mates_in_two() {
+        for (fen, first_move) in [
+            // A back rank mate, through the sacrifice of the first rook.
+            ("2r3k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1", "re2e8"),
+            // Black to move, with the rook sacrifice leading to a mate by the bishops.
+            (
+                "6k1/pp4p1/2p5/2bp4/8/P5Pb/1P3rrP/2BRRN1K b - - 0 1",
+                "rg2g1",
+            ),
+            // Legal's mate, with a knight check first.
+            (
+                "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1",
+                "nd5f6",
+            ),
+            // A problem with a quiet key move, after which the pawn mates or the rook does.
+            ("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", "ra1a6"),
+            // The rooks mate the lone king on the edge, after a quiet move which cuts it off.
+            ("2k5/8/8/8/8/8/1R6/R5K1 w - - 0 1", "ra1a7"),
+        ] {
+            let board = Board::from_str(fen).unwrap();
+            let line = MateSearcher::new().search(&board, 5).unwrap();
+
+            assert_eq!(line.len(), 3, "{}", fen);
+            assert_eq!(line[0], Move::from_str(first_move).unwrap(), "{}", fen);
+            assert!(is_mating_line(&board, &line), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn finds_mates_in_three() {
+        for (fen, first_move) in [
+            // The black king is dragged out of its camp.
+            (
+                "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1",
+                "bf8c5",
+            ),
+            // A queen sacrifice in the corner, after which the bishop and the rook mate.
+            (
+                "r1b3kr/ppp1Bp1p/1b6/n2P4/2p3q1/2Q2N2/P4PPP/RN2R1K1 w - - 1 1",
+                "qc3h8",
+            ),
+            // Black to move, chasing the exposed white king.
+            (
+                "2r3k1/p4p2/3Rp2p/1p2P1pK/8/1P4P1/P3Q2P/1q6 b - - 0 1",
+                "qb1g6",
+            ),
+        ] {
+            let board = Board::from_str(fen).unwrap();
+
+            let mut checks_only = MateSearcher::new();
+            checks_only.set_checks_only(true);
+
+            for searcher in [&mut MateSearcher::new(), &mut checks_only] {
+                let line = searcher.search(&board, 3).unwrap();
+
+                assert_eq!(line.len(), 5, "{}", fen);
+                assert_eq!(line[0], Move::from_str(first_move).unwrap(), "{}", fen);
+                assert!(is_mating_line(&board, &line), "{}", fen);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_non_mates() {
+        let mut searcher = MateSearcher::new();
+
+        // There are only longer mates.
+        let board = Board::from_str("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1").unwrap();
+
+        assert_eq!(searcher.search(&board, 1), None);
+
+        // The mates start with quiet moves, which the checks-only search doesn't try.
+        searcher.set_checks_only(true);
+
+        assert_eq!(searcher.search(&board, 2), None);
+
+        let board = Board::from_str("2k5/8/8/8/8/8/1R6/R5K1 w - - 0 1").unwrap();
+
+        assert_eq!(searcher.search(&board, 3), None);
+
+        // A stalemate isn't a mate.
+        let board = Board::from_str("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+
+        assert_eq!(searcher.search(&board, 1), None);
+
+        // The node limit ends the search.
+        let board =
+            Board::from_str("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 2 3")
+                .unwrap();
+        let limits = SearchLimits {
+            nodes: Some(1000),
+            ..Default::default()
+        };
+
+        searcher.set_checks_only(false);
+        searcher.set_time_manager(TimeManager::new(
+            &limits,
+            board.current_player,
+            Arc::new(FakeClock::new()),
+        ));
+
+        assert_eq!(searcher.search(&board, 5), None);
+        assert!(searcher.is_stopped());
+        assert_eq!(searcher.nodes, 1000);
+    }
+}
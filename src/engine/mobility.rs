@@ -0,0 +1,274 @@
+// See: https://www.chessprogramming.org/Mobility
+use crate::{
+    game::board::{Board, PlayerState},
+    generators::Square,
+    tables::FILE_MASKS,
+    BitBoard, PieceKind, Player, SEVENTH_RANK,
+};
+
+use super::{
+    eval,
+    params::{EvalParams, WEIGHTED_PIECES},
+    pawns,
+};
+
+// The amount of squares each piece usually has (in the order of "WEIGHTED_PIECES").
+// Pieces with fewer squares than usual get a penalty for every missing one, instead of a bonus for every square.
+const USUAL_MOBILITY: [i32; 4] = [14, 7, 7, 4];
+// A rook next to its king, with at most this many safe squares, is trapped in the corner.
+const TRAPPED_ROOK_MOBILITY: u32 = 3;
+// The squares (from the perspective of white) where a bishop can be trapped by a pawn of the opponent on the second square.
+const BISHOP_TRAPS: [(Square, Square); 4] = [
+    (Square::A7, Square::B6),
+    (Square::H7, Square::G6),
+    (Square::B8, Square::C7),
+    (Square::G8, Square::F7),
+];
+
+// Flips the bitboard for black, so that masks written from the perspective of white apply to both players.
+fn relative(player: Player, bitboard: BitBoard) -> BitBoard {
+    match player {
+        Player::White => bitboard,
+        Player::Black => BitBoard(bitboard.0.swap_bytes()),
+    }
+}
+
+// Whether the knight stands on the side of the opponent, defended by a pawn and where no pawn of the opponent can chase it away.
+// See: https://www.chessprogramming.org/Outposts
+fn is_outpost(player: Player, knight: Square, pawns: BitBoard, opponent_pawns: BitBoard) -> bool {
+    let knight = BitBoard::from(knight);
+
+    (3..=5).contains(&pawns::get_relative_rank(
+        player,
+        knight.first_one_square().0,
+    )) && (knight & pawns::get_attacks(player, pawns)).isnt_empty()
+        && (knight & pawns::get_attack_span(!player, opponent_pawns)).is_empty()
+}
+
+// Whether the rook is stuck in the corner by its own king, which blocks it from leaving the first rank.
+fn is_trapped_rook(player: Player, rook: Square, king: Square) -> bool {
+    let (rook_file, king_file) = (rook.0 % 8, king.0 % 8);
+
+    pawns::get_relative_rank(player, rook.0) == 0
+        && pawns::get_relative_rank(player, king.0) == 0
+        && match king_file {
+            5 | 6 => rook_file > king_file,
+            1 | 2 => rook_file < king_file,
+            _ => false,
+        }
+}
+
+fn count_trapped_bishops(player: Player, bishops: BitBoard, opponent_pawns: BitBoard) -> u32 {
+    BISHOP_TRAPS
+        .into_iter()
+        .filter(|&(bishop, pawn)| {
+            (bishops & relative(player, bishop.into())).isnt_empty()
+                && (opponent_pawns & relative(player, pawn.into())).isnt_empty()
+        })
+        .count() as u32
+}
+
+fn evaluate_rook(
+    params: &EvalParams,
+    player: Player,
+    rook: Square,
+    mobility: u32,
+    state: &PlayerState,
+    opponent: &PlayerState,
+) -> i32 {
+    let file = FILE_MASKS[(rook.0 % 8) as usize];
+    let mut score = 0;
+
+    if (file & state.pawns).is_empty() {
+        score += if (file & opponent.pawns).is_empty() {
+            params.rook_open_file_bonus
+        } else {
+            params.rook_semi_open_file_bonus
+        };
+    }
+
+    // The seventh rank only matters when there are pawns to attack there, or the king of the opponent is confined behind it.
+    let seventh_rank = relative(player, SEVENTH_RANK);
+
+    if pawns::get_relative_rank(player, rook.0) == 6
+        && ((opponent.pawns & seventh_rank).isnt_empty()
+            || pawns::get_relative_rank(player, opponent.king.first_one_square().0) == 7)
+    {
+        score += params.rook_seventh_rank_bonus;
+    }
+
+    if mobility <= TRAPPED_ROOK_MOBILITY
+        && is_trapped_rook(player, rook, state.king.first_one_square())
+    {
+        score -= params.trapped_rook_penalty;
+    }
+
+    score
+}
+
+// Scores how active the pieces of the player are, from the perspective of the player.
+pub fn evaluate_activity(board: &Board, params: &EvalParams, player: Player) -> i32 {
+    let state = board.get_player_state(player);
+    let opponent = board.get_player_state(!player);
+    let empty = !(state.pieces | opponent.pieces);
+    // Squares attacked by pawns of the opponent aren't safe for any piece.
+    let safe = !state.pieces - pawns::get_attacks(!player, opponent.pawns);
+    let mut score = 0;
+
+    for ((piece_kind, weight), usual_mobility) in WEIGHTED_PIECES
+        .into_iter()
+        .zip(params.mobility_weights)
+        .zip(USUAL_MOBILITY)
+    {
+        let mut pieces = state.get_piece_bitboard(piece_kind);
+
+        while pieces.isnt_empty() {
+            let (square, piece) = pieces.pfo_with_bitboard();
+            let mobility = (eval::get_attacks(piece_kind, piece, empty) & safe).count_ones();
+
+            score += weight * (mobility as i32 - usual_mobility);
+
+            match piece_kind {
+                PieceKind::Rook => {
+                    score += evaluate_rook(params, player, square, mobility, state, opponent);
+                }
+                PieceKind::Knight if is_outpost(player, square, state.pawns, opponent.pawns) => {
+                    score += params.knight_outpost_bonus;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if state.bishops.count_ones() >= 2 {
+        score += params.bishop_pair_bonus;
+    }
+
+    score
+        - params.trapped_bishop_penalty
+            * count_trapped_bishops(player, state.bishops, opponent.pawns) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        engine::{params::EvalParams, test_utils::mirror_fen},
+        game::board::Board,
+        generators::Square,
+        Player,
+    };
+
+    use super::{count_trapped_bishops, evaluate_activity, is_outpost, is_trapped_rook};
+
+    fn activity(fen: &str, player: Player) -> i32 {
+        evaluate_activity(
+            &Board::from_str(fen).unwrap(),
+            &EvalParams::default(),
+            player,
+        )
+    }
+
+    #[test]
+    fn mobility() {
+        let blocked = activity("4k3/8/8/8/8/8/1PPPPPP1/2B1K3 w - - 0 1", Player::White);
+        let developed = activity("4k3/8/8/8/8/3P4/1PP1PPP1/2B1K3 w - - 0 1", Player::White);
+
+        assert!(developed > blocked);
+
+        // Squares attacked by pawns of the opponent don't count.
+        let free = activity("4k3/8/8/8/8/8/8/N3K3 w - - 0 1", Player::White);
+        let covered = activity("4k3/8/8/8/2p5/8/8/N3K3 w - - 0 1", Player::White);
+
+        assert!(free > covered);
+    }
+
+    #[test]
+    fn rooks() {
+        let open = activity("4k3/pp4pp/8/8/8/8/PP4PP/3RK3 w - - 0 1", Player::White);
+        let semi_open = activity("4k3/pp1p2pp/8/8/8/8/PP4PP/3RK3 w - - 0 1", Player::White);
+        let closed = activity("4k3/pp1p2pp/8/8/8/8/PP1P2PP/3RK3 w - - 0 1", Player::White);
+
+        assert!(open > semi_open);
+        assert!(semi_open > closed);
+
+        // The rook has as many squares on both ranks.
+        assert_eq!(
+            activity("4k3/3R4/8/8/8/8/8/4K3 w - - 0 1", Player::White)
+                - activity("4k3/8/3R4/8/8/8/8/4K3 w - - 0 1", Player::White),
+            EvalParams::default().rook_seventh_rank_bonus
+        );
+
+        assert!(is_trapped_rook(Player::White, Square::H1, Square::G1));
+        assert!(is_trapped_rook(Player::Black, Square::A8, Square::C8));
+        assert!(!is_trapped_rook(Player::White, Square::H1, Square::E1));
+        assert!(!is_trapped_rook(Player::White, Square::A1, Square::G1));
+    }
+
+    #[test]
+    fn minor_pieces() {
+        let board = Board::from_str("4k3/pp5p/8/3N4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let white = board.get_player_state(Player::White);
+        let black = board.get_player_state(Player::Black);
+
+        assert!(is_outpost(
+            Player::White,
+            Square::D5,
+            white.pawns,
+            black.pawns
+        ));
+
+        // A pawn on the c-file could still chase the knight away.
+        let board = Board::from_str("4k3/ppp4p/8/3N4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let black = board.get_player_state(Player::Black);
+
+        assert!(!is_outpost(
+            Player::White,
+            Square::D5,
+            white.pawns,
+            black.pawns
+        ));
+
+        let board = Board::from_str("4k3/B7/1p6/8/8/1P6/b7/4K3 w - - 0 1").unwrap();
+
+        for player in [Player::White, Player::Black] {
+            assert_eq!(
+                count_trapped_bishops(
+                    player,
+                    board.get_player_state(player).bishops,
+                    board.get_player_state(!player).pawns
+                ),
+                1
+            );
+        }
+
+        // The bishops are on opposite sides of the board, so their mobility is the same.
+        assert_eq!(
+            activity("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", Player::White)
+                - activity("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", Player::White)
+                - activity("4k3/8/8/8/8/8/8/4KB2 w - - 0 1", Player::White),
+            EvalParams::default().bishop_pair_bonus
+        );
+    }
+
+    #[test]
+    fn symmetric() {
+        let params = EvalParams::default();
+
+        for fen in [
+            "r2q1rk1/1b2bppp/p2ppn2/1p6/3NP3/1BN1B3/PPP2PPP/R2Q1RK1 w - - 0 11",
+            "8/5pk1/3p2p1/p1pP3p/P1P1r2P/1R4P1/5PK1/8 w - - 3 40",
+        ] {
+            let board = Board::from_str(fen).unwrap();
+            let mirrored = Board::from_str(&mirror_fen(fen)).unwrap();
+
+            for player in [Player::White, Player::Black] {
+                assert_eq!(
+                    evaluate_activity(&board, &params, player),
+                    evaluate_activity(&mirrored, &params, !player)
+                );
+            }
+        }
+    }
+}
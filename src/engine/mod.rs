@@ -1 +1,18 @@
-
+pub mod endgame;
+pub mod eval;
+pub mod history;
+pub mod king_safety;
+pub mod kpk;
+pub mod mate;
+pub mod mobility;
+#[cfg(feature = "nnue")]
+pub mod nnue;
+pub mod params;
+pub mod pawns;
+pub mod search;
+pub mod syzygy;
+#[cfg(test)]
+mod test_utils;
+pub mod threads;
+pub mod time;
+pub mod tt;
@@ -0,0 +1,473 @@
+// An efficiently updatable neural network, which replaces the evaluation when one is loaded.
+// The first layer only depends on the pieces, so its output (the accumulator) is updated by the pieces a move changes, instead of recomputed.
+// The features are HalfKA: every piece on every square, for every square of the king of the perspective.
+// See: https://www.chessprogramming.org/NNUE
+use std::{
+    fs,
+    simd::{cmp::SimdOrd, num::SimdInt, Simd},
+};
+
+use crate::{
+    game::board::Board,
+    generators::{Move, Square},
+    Piece, PieceKind, Player,
+};
+
+pub const INPUT_SIZE: usize = 64 * 12 * 64;
+pub const HIDDEN_SIZE: usize = 256;
+// The activations are clipped to this range, which is what one represents in the accumulator.
+const ACTIVATION_SCALE: i32 = 255;
+// What one represents in the output weights.
+const WEIGHT_SCALE: i32 = 64;
+// The output is scaled to centipawns.
+const OUTPUT_SCALE: i32 = 400;
+const LANES: usize = 16;
+const MAGIC: &[u8; 4] = b"FNUE";
+const VERSION: u32 = 1;
+
+type Lane<T> = Simd<T, LANES>;
+
+// The squares are flipped for black, so both perspectives see their own pieces from the bottom of the board.
+fn orient(perspective: Player, square: Square) -> usize {
+    match perspective {
+        Player::White => square.0 as usize,
+        Player::Black => square.0 as usize ^ 56,
+    }
+}
+
+fn get_feature(perspective: Player, king: Square, piece: Piece, square: Square) -> usize {
+    let piece_index = (piece.player != perspective) as usize * 6 + piece.piece_kind as usize;
+
+    (orient(perspective, king) * 12 + piece_index) * 64 + orient(perspective, square)
+}
+
+// The pieces a move adds to and removes from the board. There are at most two of each (like when castling).
+#[derive(Default)]
+struct Changes {
+    added: [Option<(Piece, Square)>; 2],
+    removed: [Option<(Piece, Square)>; 2],
+}
+
+fn push_change(changes: &mut [Option<(Piece, Square)>; 2], change: (Piece, Square)) {
+    let slot = changes
+        .iter_mut()
+        .find(|slot| slot.is_none())
+        .expect("A move changes at most two pieces each way");
+
+    *slot = Some(change);
+}
+
+fn get_changes(board: &Board, chess_move: Move) -> Changes {
+    let player = board.current_player;
+    let piece = |piece_kind| Piece { piece_kind, player };
+    let mut changes = Changes::default();
+
+    let (origin, target, moved, placed) = match chess_move {
+        Move::Regular {
+            origin,
+            target,
+            piece_kind,
+            ..
+        } => (origin, target, piece(piece_kind), piece(piece_kind)),
+        Move::Promotion {
+            origin,
+            target,
+            promotion_to,
+        } => (origin, target, piece(PieceKind::Pawn), piece(promotion_to)),
+        Move::EnPassant { origin } => {
+            let target = board.ep_info.capture_point.first_one_square();
+            let captured_square = match player {
+                Player::White => target.move_down(1),
+                Player::Black => target.move_up(1),
+            };
+            let captured = Piece {
+                piece_kind: PieceKind::Pawn,
+                player: !player,
+            };
+
+            push_change(&mut changes.removed, (captured, captured_square));
+
+            (
+                origin,
+                target,
+                piece(PieceKind::Pawn),
+                piece(PieceKind::Pawn),
+            )
+        }
+        Move::CastleKS | Move::CastleQS => {
+            let (king_to, rook_from, rook_to) = match (chess_move, player) {
+                (Move::CastleKS, Player::White) => (Square::G1, Square::H1, Square::F1),
+                (Move::CastleQS, Player::White) => (Square::C1, Square::A1, Square::D1),
+                (Move::CastleKS, Player::Black) => (Square::G8, Square::H8, Square::F8),
+                _ => (Square::C8, Square::A8, Square::D8),
+            };
+            let king_from = match player {
+                Player::White => Square::E1,
+                Player::Black => Square::E8,
+            };
+
+            push_change(&mut changes.removed, (piece(PieceKind::Rook), rook_from));
+            push_change(&mut changes.added, (piece(PieceKind::Rook), rook_to));
+
+            (
+                king_from,
+                king_to,
+                piece(PieceKind::King),
+                piece(PieceKind::King),
+            )
+        }
+    };
+
+    if let Move::Regular { .. } | Move::Promotion { .. } = chess_move {
+        if let Some(captured) = *board.pieces.get_piece(target) {
+            push_change(&mut changes.removed, (captured, target));
+        }
+    }
+
+    push_change(&mut changes.removed, (moved, origin));
+    push_change(&mut changes.added, (placed, target));
+
+    changes
+}
+
+pub struct Network {
+    // The weights of every feature are stored together, since they are added to the accumulator together.
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    // The weights of the accumulator of the moving player come first, and those of the other player after.
+    output_weights: Vec<i8>,
+    output_bias: i32,
+}
+
+impl Network {
+    // The file starts with "FNUE", the version and the hidden size (as little-endian 32-bit integers).
+    // Then come the feature weights (feature by feature) and biases as 16-bit integers, the output weights as 8-bit ones,
+    // and the output bias as a 32-bit one, all in little-endian.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        let header = bytes.get(..12).ok_or("Network file is too short")?;
+
+        if &header[..4] != MAGIC {
+            return Err("Network file must start with \"FNUE\"");
+        }
+
+        if header[4..8] != VERSION.to_le_bytes() {
+            return Err("Network file has an unsupported version");
+        }
+
+        if header[8..12] != (HIDDEN_SIZE as u32).to_le_bytes() {
+            return Err("Network has an unsupported hidden layer size");
+        }
+
+        let weights_end = 12 + 2 * (INPUT_SIZE + 1) * HIDDEN_SIZE;
+        let output_end = weights_end + 2 * HIDDEN_SIZE;
+
+        if bytes.len() != output_end + 4 {
+            return Err("Network file has the wrong size");
+        }
+
+        let read_i16s = |bytes: &[u8]| {
+            bytes
+                .chunks_exact(2)
+                .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>()
+        };
+        let mut feature_weights = read_i16s(&bytes[12..weights_end]);
+        let feature_biases = feature_weights.split_off(INPUT_SIZE * HIDDEN_SIZE);
+
+        Ok(Self {
+            feature_weights,
+            feature_biases,
+            output_weights: bytes[weights_end..output_end]
+                .iter()
+                .map(|&byte| byte as i8)
+                .collect(),
+            output_bias: i32::from_le_bytes(bytes[output_end..].try_into().unwrap()),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend((HIDDEN_SIZE as u32).to_le_bytes());
+
+        for weight in self.feature_weights.iter().chain(&self.feature_biases) {
+            bytes.extend(weight.to_le_bytes());
+        }
+
+        bytes.extend(self.output_weights.iter().map(|&weight| weight as u8));
+        bytes.extend(self.output_bias.to_le_bytes());
+
+        bytes
+    }
+
+    pub fn load(path: &str) -> Result<Self, &'static str> {
+        Self::from_bytes(&fs::read(path).map_err(|_| "Network file couldn't be read")?)
+    }
+
+    fn get_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * HIDDEN_SIZE..(feature + 1) * HIDDEN_SIZE]
+    }
+
+    // Returns the score of the position from the perspective of the moving player.
+    pub fn evaluate(&self, accumulator: &Accumulator, player: Player) -> i32 {
+        let mut sum = Lane::<i32>::splat(0);
+
+        for (values, weights) in [accumulator.values[player], accumulator.values[!player]]
+            .iter()
+            .zip(self.output_weights.chunks_exact(HIDDEN_SIZE))
+        {
+            for (values, weights) in values.chunks_exact(LANES).zip(weights.chunks_exact(LANES)) {
+                // Clipped ReLU, which keeps the products small enough for 32 bits.
+                let activations = Lane::<i16>::from_slice(values)
+                    .simd_clamp(Lane::splat(0), Lane::splat(ACTIVATION_SCALE as i16))
+                    .cast::<i32>();
+
+                sum += activations * Lane::<i8>::from_slice(weights).cast::<i32>();
+            }
+        }
+
+        (sum.reduce_sum() + self.output_bias) * OUTPUT_SCALE / (ACTIVATION_SCALE * WEIGHT_SCALE)
+    }
+}
+
+fn add_weights(values: &mut [i16], weights: &[i16]) {
+    for (values, weights) in values
+        .chunks_exact_mut(LANES)
+        .zip(weights.chunks_exact(LANES))
+    {
+        (Lane::from_slice(values) + Lane::from_slice(weights)).copy_to_slice(values);
+    }
+}
+
+fn subtract_weights(values: &mut [i16], weights: &[i16]) {
+    for (values, weights) in values
+        .chunks_exact_mut(LANES)
+        .zip(weights.chunks_exact(LANES))
+    {
+        (Lane::from_slice(values) - Lane::from_slice(weights)).copy_to_slice(values);
+    }
+}
+
+// The output of the first layer, for the perspective of each player.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Accumulator {
+    values: [[i16; HIDDEN_SIZE]; 2],
+}
+
+impl Accumulator {
+    pub fn new(network: &Network, board: &Board) -> Self {
+        let mut accumulator = Self {
+            values: [[0; HIDDEN_SIZE]; 2],
+        };
+
+        for perspective in [Player::White, Player::Black] {
+            accumulator.refresh(network, board, perspective);
+        }
+
+        accumulator
+    }
+
+    // Computes the accumulator of the perspective from scratch.
+    fn refresh(&mut self, network: &Network, board: &Board, perspective: Player) {
+        let values = &mut self.values[perspective];
+        let king = board.get_player_state(perspective).king.first_one_square();
+
+        values.copy_from_slice(&network.feature_biases);
+
+        for square in (0..64).map(Square) {
+            if let Some(piece) = *board.pieces.get_piece(square) {
+                add_weights(
+                    values,
+                    network.get_weights(get_feature(perspective, king, piece, square)),
+                );
+            }
+        }
+    }
+
+    // Returns the accumulator after the move, which turned the board into "new_board".
+    // Every feature depends on the square of the king, so the perspective of a king which moved is computed from scratch.
+    pub fn after_move(
+        &self,
+        network: &Network,
+        board: &Board,
+        chess_move: Move,
+        new_board: &Board,
+    ) -> Self {
+        let mut accumulator = *self;
+        let changes = get_changes(board, chess_move);
+
+        for perspective in [Player::White, Player::Black] {
+            let king = new_board.get_player_state(perspective).king;
+
+            if king != board.get_player_state(perspective).king {
+                accumulator.refresh(network, new_board, perspective);
+
+                continue;
+            }
+
+            let king = king.first_one_square();
+            let values = &mut accumulator.values[perspective];
+
+            for &(piece, square) in changes.removed.iter().flatten() {
+                subtract_weights(
+                    values,
+                    network.get_weights(get_feature(perspective, king, piece, square)),
+                );
+            }
+
+            for &(piece, square) in changes.added.iter().flatten() {
+                add_weights(
+                    values,
+                    network.get_weights(get_feature(perspective, king, piece, square)),
+                );
+            }
+        }
+
+        accumulator
+    }
+}
+
+#[cfg(test)]
+use crate::game::zobrist::splitmix64;
+
+#[cfg(test)]
+impl Network {
+    // A network with small random weights, so the accumulators can't overflow.
+    pub fn random(seed: u64) -> Self {
+        let mut state = seed;
+        let mut next =
+            |range: i64| (splitmix64(&mut state) % (2 * range as u64 + 1)) as i64 - range;
+
+        Self {
+            feature_weights: (0..INPUT_SIZE * HIDDEN_SIZE)
+                .map(|_| next(32) as i16)
+                .collect(),
+            feature_biases: (0..HIDDEN_SIZE).map(|_| next(64) as i16).collect(),
+            output_weights: (0..2 * HIDDEN_SIZE).map(|_| next(127) as i8).collect(),
+            output_bias: next(1000) as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{game::board::Board, generators::MoveGen, uci::parse_move, Player};
+
+    use super::{Accumulator, Network, ACTIVATION_SCALE, HIDDEN_SIZE, OUTPUT_SCALE, WEIGHT_SCALE};
+
+    // Plays the first, middle and last legal move in turns, updating the accumulator at every move.
+    fn check_line(network: &Network, fen: &str, move_count: usize) {
+        let mut board = Board::from_str(fen).unwrap();
+        let mut accumulator = Accumulator::new(network, &board);
+
+        for index in 0..move_count {
+            let moves = MoveGen::run(board);
+
+            if moves.is_empty() {
+                break;
+            }
+
+            let chess_move = moves[[0, moves.len() / 2, moves.len() - 1][index % 3]];
+            let mut new_board = board;
+            new_board.make_move(chess_move);
+
+            accumulator = accumulator.after_move(network, &board, chess_move, &new_board);
+            board = new_board;
+
+            let refreshed = Accumulator::new(network, &board);
+
+            assert_eq!(accumulator, refreshed, "{} after {}", fen, chess_move);
+            assert_eq!(
+                network.evaluate(&accumulator, board.current_player),
+                network.evaluate(&refreshed, board.current_player)
+            );
+        }
+    }
+
+    #[test]
+    fn incremental_updates_match_refresh() {
+        let network = Network::random(1);
+
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            check_line(&network, fen, 40);
+        }
+
+        // Every kind of special move: castling on both sides, en-passant and promotions with captures.
+        for (fen, text) in [
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"),
+            ("3rk3/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7d8n"),
+        ] {
+            let board = Board::from_str(fen).unwrap();
+            let chess_move = parse_move(&board, text).unwrap();
+            let mut new_board = board;
+            new_board.make_move(chess_move);
+
+            assert_eq!(
+                Accumulator::new(&network, &board)
+                    .after_move(&network, &board, chess_move, &new_board),
+                Accumulator::new(&network, &new_board),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn forward_pass_matches_scalar() {
+        let network = Network::random(2);
+        let board =
+            Board::from_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let accumulator = Accumulator::new(&network, &board);
+
+        for player in [Player::White, Player::Black] {
+            let mut sum = network.output_bias;
+
+            for (index, perspective) in [player, !player].into_iter().enumerate() {
+                for (value, weight) in accumulator.values[perspective]
+                    .iter()
+                    .zip(&network.output_weights[index * HIDDEN_SIZE..])
+                {
+                    sum += (*value as i32).clamp(0, ACTIVATION_SCALE) * *weight as i32;
+                }
+            }
+
+            assert_eq!(
+                network.evaluate(&accumulator, player),
+                sum * OUTPUT_SCALE / (ACTIVATION_SCALE * WEIGHT_SCALE)
+            );
+        }
+    }
+
+    #[test]
+    fn network_files() {
+        let network = Network::random(3);
+        let bytes = network.to_bytes();
+        let loaded = Network::from_bytes(&bytes).unwrap();
+        let board = Board::default();
+
+        assert_eq!(
+            loaded.evaluate(&Accumulator::new(&loaded, &board), Player::White),
+            network.evaluate(&Accumulator::new(&network, &board), Player::White)
+        );
+        assert_eq!(loaded.to_bytes(), bytes);
+
+        assert!(Network::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Network::from_bytes(b"FNUE").is_err());
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+
+        assert!(Network::from_bytes(&wrong_magic).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+// Every weight of the evaluation, so that they can be tuned together and loaded from a file.
+// The weights are also exposed as a flat vector (in a fixed order), which is what the tuner optimizes.
+use std::{fmt::Display, str::FromStr};
+
+use crate::PieceKind;
+
+use super::eval::{BISHOP_VALUE, KNIGHT_VALUE, PAWN_VALUE, QUEEN_VALUE, ROOK_VALUE};
+
+// The pieces which have a value, in the order of the material weights. The other weights per piece leave out the pawn.
+pub const WEIGHTED_PIECES: [PieceKind; 5] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+    PieceKind::Pawn,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EvalParams {
+    pub material: [i32; 5],
+    pub doubled_penalty: i32,
+    pub isolated_penalty: i32,
+    pub backward_penalty: i32,
+    // For every pawn defended by another pawn, or standing next to one.
+    pub connected_bonus: i32,
+    // Indexed by the rank of the passed pawn, from the perspective of its player.
+    pub passed_bonus: [i32; 8],
+    // Added when nothing stands on the path of the passed pawn to its promotion square.
+    pub free_passed_bonus: [i32; 8],
+    // For each pawn of the player in front of its king, by how far ahead it is (one or two ranks).
+    pub shield_bonus: [i32; 2],
+    // For each pawn of the opponent advancing on the king, by how far ahead of the king it is (one to four ranks).
+    pub storm_penalty: [i32; 4],
+    // For each file next to the king (or its own) without pawns of the player, depending on whether the opponent has pawns on it.
+    pub semi_open_file_penalty: i32,
+    pub open_file_penalty: i32,
+    // How dangerous each piece is, for every square of the king zone it attacks.
+    pub attack_weights: [i32; 4],
+    // The bonus of each piece for every safe square it attacks.
+    pub mobility_weights: [i32; 4],
+    pub rook_open_file_bonus: i32,
+    pub rook_semi_open_file_bonus: i32,
+    pub rook_seventh_rank_bonus: i32,
+    pub bishop_pair_bonus: i32,
+    pub knight_outpost_bonus: i32,
+    pub trapped_bishop_penalty: i32,
+    pub trapped_rook_penalty: i32,
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self {
+            material: [
+                QUEEN_VALUE,
+                ROOK_VALUE,
+                BISHOP_VALUE,
+                KNIGHT_VALUE,
+                PAWN_VALUE,
+            ],
+            doubled_penalty: 15,
+            isolated_penalty: 12,
+            backward_penalty: 10,
+            connected_bonus: 8,
+            passed_bonus: [0, 5, 10, 20, 35, 60, 100, 0],
+            free_passed_bonus: [0, 0, 5, 10, 20, 35, 60, 0],
+            shield_bonus: [15, 8],
+            storm_penalty: [5, 20, 12, 5],
+            semi_open_file_penalty: 15,
+            open_file_penalty: 25,
+            attack_weights: [5, 3, 2, 2],
+            mobility_weights: [1, 2, 5, 4],
+            rook_open_file_bonus: 20,
+            rook_semi_open_file_bonus: 10,
+            rook_seventh_rank_bonus: 20,
+            bishop_pair_bonus: 30,
+            knight_outpost_bonus: 20,
+            trapped_bishop_penalty: 100,
+            trapped_rook_penalty: 50,
+        }
+    }
+}
+
+// Adds the values of an array to the entries, named after the array and the index of each value.
+fn add_entries<'a>(entries: &mut Vec<(String, &'a mut i32)>, name: &str, values: &'a mut [i32]) {
+    for (index, value) in values.iter_mut().enumerate() {
+        entries.push((format!("{}_{}", name, index), value));
+    }
+}
+
+// Like "add_entries", for arrays with a value per piece (in the order of "WEIGHTED_PIECES"), which are named after the pieces.
+fn add_piece_entries<'a>(
+    entries: &mut Vec<(String, &'a mut i32)>,
+    name: &str,
+    values: &'a mut [i32],
+) {
+    for (piece_kind, value) in WEIGHTED_PIECES.into_iter().zip(values) {
+        let piece_name = format!("{:?}", piece_kind).to_lowercase();
+
+        entries.push((format!("{}_{}", name, piece_name), value));
+    }
+}
+
+impl EvalParams {
+    // Every weight with its name, in the order of the flat vector.
+    fn get_entries_mut(&mut self) -> Vec<(String, &mut i32)> {
+        // This fails to compile when a weight is added without being listed here.
+        let Self {
+            material,
+            doubled_penalty,
+            isolated_penalty,
+            backward_penalty,
+            connected_bonus,
+            passed_bonus,
+            free_passed_bonus,
+            shield_bonus,
+            storm_penalty,
+            semi_open_file_penalty,
+            open_file_penalty,
+            attack_weights,
+            mobility_weights,
+            rook_open_file_bonus,
+            rook_semi_open_file_bonus,
+            rook_seventh_rank_bonus,
+            bishop_pair_bonus,
+            knight_outpost_bonus,
+            trapped_bishop_penalty,
+            trapped_rook_penalty,
+        } = self;
+        let mut entries = Vec::new();
+
+        add_piece_entries(&mut entries, "material", material);
+
+        for (name, value) in [
+            ("doubled_penalty", doubled_penalty),
+            ("isolated_penalty", isolated_penalty),
+            ("backward_penalty", backward_penalty),
+            ("connected_bonus", connected_bonus),
+        ] {
+            entries.push((name.to_string(), value));
+        }
+
+        add_entries(&mut entries, "passed_bonus", passed_bonus);
+        add_entries(&mut entries, "free_passed_bonus", free_passed_bonus);
+        add_entries(&mut entries, "shield_bonus", shield_bonus);
+        add_entries(&mut entries, "storm_penalty", storm_penalty);
+
+        for (name, value) in [
+            ("semi_open_file_penalty", semi_open_file_penalty),
+            ("open_file_penalty", open_file_penalty),
+        ] {
+            entries.push((name.to_string(), value));
+        }
+
+        add_piece_entries(&mut entries, "attack_weights", attack_weights);
+        add_piece_entries(&mut entries, "mobility_weights", mobility_weights);
+
+        for (name, value) in [
+            ("rook_open_file_bonus", rook_open_file_bonus),
+            ("rook_semi_open_file_bonus", rook_semi_open_file_bonus),
+            ("rook_seventh_rank_bonus", rook_seventh_rank_bonus),
+            ("bishop_pair_bonus", bishop_pair_bonus),
+            ("knight_outpost_bonus", knight_outpost_bonus),
+            ("trapped_bishop_penalty", trapped_bishop_penalty),
+            ("trapped_rook_penalty", trapped_rook_penalty),
+        ] {
+            entries.push((name.to_string(), value));
+        }
+
+        entries
+    }
+
+    pub fn get_names() -> Vec<String> {
+        Self::default()
+            .get_entries_mut()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    pub fn get_material(&self, piece_kind: PieceKind) -> i32 {
+        match piece_kind {
+            PieceKind::King => 0,
+            _ => self.material[piece_kind as usize - 1],
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<i32> {
+        let mut params = *self;
+
+        params
+            .get_entries_mut()
+            .into_iter()
+            .map(|(_, value)| *value)
+            .collect()
+    }
+
+    pub fn from_slice(values: &[i32]) -> Result<Self, &'static str> {
+        let mut params = Self::default();
+        let entries = params.get_entries_mut();
+
+        if entries.len() != values.len() {
+            return Err("Wrong amount of evaluation weights");
+        }
+
+        for ((_, entry), &value) in entries.into_iter().zip(values) {
+            *entry = value;
+        }
+
+        Ok(params)
+    }
+}
+
+// The weights file has a line per weight, with its name and value. Blank lines and comments (starting with "#") are ignored.
+impl Display for EvalParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut params = *self;
+
+        for (name, value) in params.get_entries_mut() {
+            writeln!(f, "{} {}", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Weights missing from the file keep their default value.
+impl FromStr for EvalParams {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params = Self::default();
+        let mut entries = params.get_entries_mut();
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or("Every weight must be given as \"<name> <value>\"")?;
+
+            let (_, entry) = entries
+                .iter_mut()
+                .find(|(entry_name, _)| entry_name == name)
+                .ok_or("Unknown evaluation weight")?;
+
+            **entry = value
+                .trim()
+                .parse()
+                .map_err(|_| "Evaluation weights must be whole numbers")?;
+        }
+
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, str::FromStr};
+
+    use crate::PieceKind;
+
+    use super::EvalParams;
+
+    #[test]
+    fn flat_vector() {
+        let params = EvalParams::default();
+        let values = params.to_vec();
+
+        assert_eq!(values.len(), EvalParams::get_names().len());
+        assert_eq!(EvalParams::from_slice(&values), Ok(params));
+        assert!(EvalParams::from_slice(&values[1..]).is_err());
+
+        let names = EvalParams::get_names();
+
+        assert_eq!(names.iter().collect::<HashSet<_>>().len(), names.len());
+        assert_eq!(names[0], "material_queen");
+        assert!(names.contains(&"passed_bonus_6".to_string()));
+        assert!(names.contains(&"mobility_weights_knight".to_string()));
+
+        let mut values = values;
+        values[0] = 1000;
+
+        assert_eq!(
+            EvalParams::from_slice(&values)
+                .unwrap()
+                .get_material(PieceKind::Queen),
+            1000
+        );
+    }
+
+    #[test]
+    fn weights_file() {
+        let mut params = EvalParams::default();
+        params.material[4] = 90;
+        params.passed_bonus[6] = 120;
+        params.trapped_rook_penalty = -5;
+
+        assert_eq!(EvalParams::from_str(&params.to_string()), Ok(params));
+
+        let partial = "# Tuned\n\nmaterial_pawn 90\n  passed_bonus_6   120\n";
+        let parsed = EvalParams::from_str(partial).unwrap();
+
+        assert_eq!(parsed.get_material(PieceKind::Pawn), 90);
+        assert_eq!(parsed.passed_bonus[6], 120);
+        assert_eq!(parsed.trapped_rook_penalty, 50);
+
+        assert!(EvalParams::from_str("material_king 0").is_err());
+        assert!(EvalParams::from_str("material_pawn ninety").is_err());
+        assert!(EvalParams::from_str("material_pawn").is_err());
+    }
+}
@@ -0,0 +1,320 @@
+// See: https://www.chessprogramming.org/Pawn_Structure
+use crate::{game::board::Board, BitBoard, Player};
+
+use super::params::EvalParams;
+
+// The amount of entries in the pawn table, which must be a power of two.
+const PAWN_TABLE_SIZE: usize = 1 << 14;
+
+// Returns the rank of the square from the perspective of the player, so that pawns always start on the second rank.
+pub fn get_relative_rank(player: Player, square: u32) -> usize {
+    match player {
+        Player::White => (square / 8) as usize,
+        Player::Black => 7 - (square / 8) as usize,
+    }
+}
+
+// These move the pawns of the player towards the promotion rank, or away from it.
+pub fn move_forward(player: Player, pawns: BitBoard) -> BitBoard {
+    match player {
+        Player::White => pawns.move_up(1),
+        Player::Black => pawns.move_down(1),
+    }
+}
+
+fn move_backward(player: Player, pawns: BitBoard) -> BitBoard {
+    match player {
+        Player::White => pawns.move_down(1),
+        Player::Black => pawns.move_up(1),
+    }
+}
+
+// All the squares in front of the pawns, on their files.
+fn get_front_span(player: Player, pawns: BitBoard) -> BitBoard {
+    match player {
+        Player::White => pawns.move_up(1).smear_ones_up(),
+        Player::Black => pawns.move_down(1).smear_ones_down(),
+    }
+}
+
+pub fn get_attacks(player: Player, pawns: BitBoard) -> BitBoard {
+    let forward = move_forward(player, pawns);
+
+    forward.move_left() | forward.move_right()
+}
+
+// All the squares the pawns could ever attack, by pushing forward.
+pub fn get_attack_span(player: Player, pawns: BitBoard) -> BitBoard {
+    match player {
+        Player::White => get_attacks(player, pawns).smear_ones_up(),
+        Player::Black => get_attacks(player, pawns).smear_ones_down(),
+    }
+}
+
+// The pawns of the player which are blocked by another pawn of the player in front of them.
+pub fn get_doubled(player: Player, pawns: BitBoard) -> BitBoard {
+    pawns & get_front_span(!player, pawns)
+}
+
+// The pawns of the player which have no pawn of the player on a neighbouring file.
+pub fn get_isolated(pawns: BitBoard) -> BitBoard {
+    let files = pawns.smear_ones_files();
+
+    pawns - (files.move_left() | files.move_right())
+}
+
+// The pawns of the player which no pawn of the opponent can stop, or capture, on their way to promotion.
+pub fn get_passed(player: Player, pawns: BitBoard, opponent_pawns: BitBoard) -> BitBoard {
+    let front_span = get_front_span(!player, opponent_pawns);
+
+    pawns - (front_span | front_span.move_left() | front_span.move_right())
+}
+
+// The pawns of the player which can't advance safely, and can't be defended by other pawns of the player either.
+// See: https://www.chessprogramming.org/Backward_Pawns_(Bitboards)
+pub fn get_backward(player: Player, pawns: BitBoard, opponent_pawns: BitBoard) -> BitBoard {
+    let stops = move_forward(player, pawns);
+    let unsafe_stops =
+        stops & (get_attacks(!player, opponent_pawns) - get_attack_span(player, pawns));
+
+    move_backward(player, unsafe_stops) & pawns
+}
+
+// The pawns of the player which are defended by another pawn of the player, or stand next to one.
+pub fn get_connected(player: Player, pawns: BitBoard) -> BitBoard {
+    let defended = pawns & get_attacks(player, pawns);
+    let phalanx = pawns & (pawns.move_left() | pawns.move_right());
+
+    defended | phalanx
+}
+
+// Scores the pawn structure of the player, which only depends on the pawns of both players.
+fn evaluate_structure(
+    params: &EvalParams,
+    player: Player,
+    pawns: BitBoard,
+    opponent_pawns: BitBoard,
+) -> (i32, BitBoard) {
+    let passed = get_passed(player, pawns, opponent_pawns);
+    let mut score = params.connected_bonus * get_connected(player, pawns).count_ones() as i32
+        - params.doubled_penalty * get_doubled(player, pawns).count_ones() as i32
+        - params.isolated_penalty * get_isolated(pawns).count_ones() as i32
+        - params.backward_penalty * get_backward(player, pawns, opponent_pawns).count_ones() as i32;
+
+    let mut remaining = passed;
+
+    while remaining.isnt_empty() {
+        score += params.passed_bonus[get_relative_rank(player, remaining.pop_first_one().0)];
+    }
+
+    (score, passed)
+}
+
+// The pawn structure of a position, from the perspective of white.
+#[derive(Clone, Copy)]
+pub struct PawnEntry {
+    key: u64,
+    pub score: i32,
+    // The passed pawns of each player, whose bonus for a free path also depends on the other pieces.
+    pub passed: [BitBoard; 2],
+}
+
+impl PawnEntry {
+    fn new(board: &Board, params: &EvalParams) -> Self {
+        let white_pawns = board.get_player_state(Player::White).pawns;
+        let black_pawns = board.get_player_state(Player::Black).pawns;
+        let (white_score, white_passed) =
+            evaluate_structure(params, Player::White, white_pawns, black_pawns);
+        let (black_score, black_passed) =
+            evaluate_structure(params, Player::Black, black_pawns, white_pawns);
+
+        Self {
+            key: board.pawn_hash,
+            score: white_score - black_score,
+            passed: [white_passed, black_passed],
+        }
+    }
+}
+
+// Caches the evaluation of pawn structures by their pawn hash. Pawns move rarely, so most lookups hit.
+// The table belongs to a single search thread, so unlike the transposition table it needs no synchronization.
+// The scores depend on the evaluation weights, so the table must not be shared between different weights.
+// See: https://www.chessprogramming.org/Pawn_Hash_Table
+pub struct PawnTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PawnTable {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![None; PAWN_TABLE_SIZE],
+        }
+    }
+
+    // Returns the entry of the pawn structure, which is evaluated and stored when it isn't in the table yet.
+    pub fn probe(&mut self, board: &Board, params: &EvalParams) -> PawnEntry {
+        let slot = &mut self.entries[board.pawn_hash as usize & (PAWN_TABLE_SIZE - 1)];
+
+        match slot {
+            Some(entry) if entry.key == board.pawn_hash => *entry,
+            _ => *slot.insert(PawnEntry::new(board, params)),
+        }
+    }
+}
+
+// Scores the pawns of the position from the perspective of white, using the table for the pawn structure.
+pub fn evaluate_pawns(board: &Board, params: &EvalParams, pawn_table: &mut PawnTable) -> i32 {
+    let entry = pawn_table.probe(board, params);
+    let occupancy = board.moving_player.pieces | board.moved_player.pieces;
+    let mut score = entry.score;
+
+    for (player, sign) in [(Player::White, 1), (Player::Black, -1)] {
+        let mut passed = entry.passed[player];
+
+        while passed.isnt_empty() {
+            let (square, pawn) = passed.pfo_with_bitboard();
+
+            if (get_front_span(player, pawn) & occupancy).is_empty() {
+                score += sign * params.free_passed_bonus[get_relative_rank(player, square.0)];
+            }
+        }
+    }
+
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        engine::{params::EvalParams, test_utils::mirror_fen},
+        game::board::Board,
+        generators::Square,
+        BitBoard, Player,
+    };
+
+    use super::{
+        evaluate_pawns, get_backward, get_connected, get_doubled, get_isolated, get_passed,
+        PawnTable,
+    };
+
+    // Builds a bitboard out of squares like "e4".
+    fn squares(names: &[&str]) -> BitBoard {
+        let mut bitboard = BitBoard::empty();
+
+        for name in names {
+            bitboard.turn_on(Square::from_str(name).unwrap());
+        }
+
+        bitboard
+    }
+
+    fn pawns(board: &Board, player: Player) -> BitBoard {
+        board.get_player_state(player).pawns
+    }
+
+    #[test]
+    fn structures() {
+        let board = Board::from_str("4k3/p4p2/1p2p3/1P5p/8/2P1P2P/2P5/4K3 w - - 0 1").unwrap();
+        let white = pawns(&board, Player::White);
+        let black = pawns(&board, Player::Black);
+
+        assert!(get_doubled(Player::White, white) == squares(&["c2"]));
+        assert!(get_doubled(Player::Black, black) == BitBoard::empty());
+        assert!(get_isolated(white) == squares(&["e3", "h3"]));
+        assert!(get_isolated(black) == squares(&["h5"]));
+        assert!(get_connected(Player::Black, black) == squares(&["b6", "e6"]));
+        assert!(get_connected(Player::White, white) == BitBoard::empty());
+        // The a-pawn could still be captured by the b-pawn.
+        assert!(get_passed(Player::White, white, black) == BitBoard::empty());
+        assert!(get_passed(Player::Black, black, white) == BitBoard::empty());
+        assert!(get_passed(Player::Black, black, white - squares(&["b5"])) == squares(&["a7"]));
+
+        // The d-pawn can't advance past the black pawn on e5, nor be defended by the far away pawns.
+        let board = Board::from_str("4k3/8/8/4p3/8/3P4/6P1/4K3 w - - 0 1").unwrap();
+
+        assert!(
+            get_backward(
+                Player::White,
+                pawns(&board, Player::White),
+                pawns(&board, Player::Black)
+            ) == squares(&["d3"])
+        );
+
+        let board = Board::from_str("4k3/8/8/4p3/8/3P4/2P5/4K3 w - - 0 1").unwrap();
+
+        assert!(
+            get_backward(
+                Player::White,
+                pawns(&board, Player::White),
+                pawns(&board, Player::Black)
+            ) == BitBoard::empty()
+        );
+    }
+
+    #[test]
+    fn passed_pawns() {
+        let params = EvalParams::default();
+        let mut table = PawnTable::new();
+
+        // The further a passed pawn is, the more it is worth.
+        let far = Board::from_str("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let near = Board::from_str("4k3/8/8/8/8/1P6/8/4K3 w - - 0 1").unwrap();
+
+        assert!(
+            evaluate_pawns(&far, &params, &mut table) > evaluate_pawns(&near, &params, &mut table)
+        );
+
+        // A blocked passed pawn doesn't get the bonus for a free path.
+        let blocked = Board::from_str("1n2k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+        assert!(
+            evaluate_pawns(&far, &params, &mut table)
+                > evaluate_pawns(&blocked, &params, &mut table)
+        );
+    }
+
+    #[test]
+    fn symmetric() {
+        let params = EvalParams::default();
+        let mut table = PawnTable::new();
+
+        for fen in [
+            "4k3/p4p2/1p2p3/1P5p/8/2P1P2P/2P5/4K3 w - - 0 1",
+            "r1bqkbnr/pp3ppp/2n5/2pp4/3P4/5N2/PPP2PPP/RNBQKB1R w KQkq - 0 5",
+        ] {
+            let board = Board::from_str(fen).unwrap();
+            let mirrored = Board::from_str(&mirror_fen(fen)).unwrap();
+
+            assert_eq!(
+                evaluate_pawns(&board, &params, &mut table),
+                -evaluate_pawns(&mirrored, &params, &mut table)
+            );
+        }
+    }
+
+    #[test]
+    fn table_caches_structures() {
+        let params = EvalParams::default();
+        let mut table = PawnTable::new();
+        let board = Board::from_str("4k3/p4p2/1p2p3/1P5p/8/2P1P2P/2P5/4K3 w - - 0 1").unwrap();
+        let score = evaluate_pawns(&board, &params, &mut table);
+
+        // The same pawns with other pieces share the entry, and the cached score.
+        let other = Board::from_str("r3k3/p4p2/1p2p3/1P5p/8/2P1P2P/2P5/4K2R b - - 0 1").unwrap();
+
+        assert_eq!(board.pawn_hash, other.pawn_hash);
+        assert_eq!(
+            table.probe(&other, &params).score,
+            table.probe(&board, &params).score
+        );
+        assert_eq!(evaluate_pawns(&board, &params, &mut table), score);
+    }
+}
@@ -0,0 +1,1496 @@
+use std::{
+    cmp::Reverse,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    game::board::Board,
+    generators::{Move, MoveGen},
+    PieceKind,
+};
+
+#[cfg(feature = "nnue")]
+use super::nnue::{Accumulator, Network};
+use super::{
+    eval,
+    history::{MoveKey, PreviousMoves, SearchContext},
+    params::EvalParams,
+    pawns::PawnTable,
+    syzygy::{Tablebases, Wdl},
+    time::TimeManager,
+    tt::{Bound, TableEntry, TranspositionTable},
+};
+
+pub const MAX_PLY: usize = 128;
+pub const INFINITY: i32 = 32001;
+pub const MATE: i32 = 32000;
+// Any score beyond this bound is a mate, with the distance to it (in plies) being the difference from "MATE".
+pub const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+// Tablebase wins are scored below the mates, with the ply subtracted so the closer ones are preferred.
+pub const TABLEBASE_WIN: i32 = MATE_BOUND - MAX_PLY as i32;
+// Any score beyond this bound is a tablebase result or a mate, which both depend on the ply they were found at.
+pub const TABLEBASE_BOUND: i32 = TABLEBASE_WIN - MAX_PLY as i32;
+// The move ordering buckets, from the first to be searched to the last.
+const TT_MOVE_SCORE: i32 = i32::MAX;
+const CAPTURE_SCORE: i32 = 1 << 24;
+const KILLER_SCORE: i32 = 1 << 20;
+const COUNTER_MOVE_SCORE: i32 = KILLER_SCORE - 2;
+// A capture is skipped in the quiescence search if even this much on top of the captured piece can't raise alpha.
+const DELTA_MARGIN: i32 = 200;
+// The initial half-width of the aspiration window, which grows every time the search falls outside of it.
+const ASPIRATION_WINDOW: i32 = 25;
+const ASPIRATION_MIN_DEPTH: u32 = 4;
+const REVERSE_FUTILITY_MAX_DEPTH: i32 = 6;
+const REVERSE_FUTILITY_MARGIN: i32 = 80;
+const RAZORING_MAX_DEPTH: i32 = 2;
+const RAZORING_MARGIN: i32 = 300;
+const NULL_MOVE_MIN_DEPTH: i32 = 3;
+const NULL_MOVE_REDUCTION: i32 = 3;
+const FUTILITY_MAX_DEPTH: i32 = 3;
+const FUTILITY_MARGIN: i32 = 120;
+const LMP_MAX_DEPTH: i32 = 3;
+const LMP_BASE: i32 = 3;
+const LMR_MIN_DEPTH: i32 = 3;
+const LMR_MIN_MOVES: i32 = 3;
+// Tablebase scores are stored deeper than the node, since searching deeper can't change them.
+const TABLEBASE_DEPTH_BONUS: i32 = 6;
+// Positions with as many pieces as the largest tables are only probed at this depth, since they are the slowest to probe.
+const TABLEBASE_PROBE_DEPTH: i32 = 4;
+// Checking the clock is slow, so it is only done once every this many nodes.
+const NODES_BETWEEN_CHECKS: u64 = 2048;
+
+// Every selectivity technique of the search can be turned off, so their effect can be measured separately.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchConfig {
+    pub null_move_pruning: bool,
+    pub late_move_reductions: bool,
+    pub futility_pruning: bool,
+    pub reverse_futility_pruning: bool,
+    pub razoring: bool,
+    pub late_move_pruning: bool,
+    pub check_extensions: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            null_move_pruning: true,
+            late_move_reductions: true,
+            futility_pruning: true,
+            reverse_futility_pruning: true,
+            razoring: true,
+            late_move_pruning: true,
+            check_extensions: true,
+        }
+    }
+}
+
+impl SearchConfig {
+    // A plain alpha-beta search, without any pruning, reductions or extensions.
+    pub fn disabled() -> Self {
+        Self {
+            null_move_pruning: false,
+            late_move_reductions: false,
+            futility_pruning: false,
+            reverse_futility_pruning: false,
+            razoring: false,
+            late_move_pruning: false,
+            check_extensions: false,
+        }
+    }
+}
+
+// The reduction grows with both the depth and the amount of moves already searched.
+fn get_reduction(depth: i32, moves_searched: i32) -> i32 {
+    (0.75 + (depth as f64).ln() * (moves_searched as f64).ln() / 2.25) as i32
+}
+
+// Positions where the moving player only has pawns (and a king) are prone to zugzwang.
+fn has_non_pawn_material(board: &Board) -> bool {
+    let player = &board.moving_player;
+
+    (player.queens | player.rooks | player.bishops | player.knights).isnt_empty()
+}
+
+// A root move, its score and the principal variation starting with it.
+pub type Line = (Move, i32, Vec<Move>);
+
+// This is reported for every line of every completed iteration of the iterative deepening.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    // The rank of the line, starting from 1 for the best one.
+    pub multi_pv: usize,
+    // The deepest ply reached, including the quiescence search.
+    pub seldepth: usize,
+    pub score: i32,
+    pub nodes: u64,
+    pub nps: u64,
+    pub time: Duration,
+    pub pv: Vec<Move>,
+}
+
+// The principal variation of every ply is built from the one of the ply after it, so row "ply" only uses the columns from "ply" onwards.
+// See: https://www.chessprogramming.org/Triangular_PV-Table
+struct PvTable {
+    moves: Vec<[Option<Move>; MAX_PLY + 1]>,
+    lengths: [usize; MAX_PLY + 1],
+}
+
+impl PvTable {
+    fn new() -> Self {
+        Self {
+            moves: vec![[None; MAX_PLY + 1]; MAX_PLY + 1],
+            lengths: [0; MAX_PLY + 1],
+        }
+    }
+
+    fn clear_ply(&mut self, ply: usize) {
+        self.lengths[ply] = ply;
+    }
+
+    // Sets the move as the best of the ply, followed by the principal variation of the next ply.
+    fn update(&mut self, ply: usize, chess_move: Move) {
+        let next_length = self.lengths[ply + 1].max(ply + 1);
+
+        self.moves[ply][ply] = Some(chess_move);
+
+        for index in ply + 1..next_length {
+            self.moves[ply][index] = self.moves[ply + 1][index];
+        }
+
+        self.lengths[ply] = next_length;
+    }
+
+    fn get_line(&self) -> Vec<Move> {
+        self.moves[0][..self.lengths[0]]
+            .iter()
+            .map_while(|&chess_move| chess_move)
+            .collect()
+    }
+}
+
+// Captures of valuable pieces by cheap ones come first (MVV-LVA), and promotions are scored like capturing the difference.
+// Returns nothing for quiet moves.
+fn get_capture_score(board: &Board, chess_move: Move) -> Option<i32> {
+    let promotion_gain = match chess_move {
+        Move::Promotion { promotion_to, .. } => {
+            promotion_to.see_value() - PieceKind::Pawn.see_value()
+        }
+        _ => 0,
+    };
+
+    match board.get_captured_piece(chess_move) {
+        Some(captured) => {
+            let attacker = match chess_move {
+                Move::Regular { piece_kind, .. } => piece_kind,
+                _ => PieceKind::Pawn,
+            };
+
+            Some(10 * captured.see_value() - attacker.see_value() + promotion_gain)
+        }
+        None if promotion_gain > 0 => Some(promotion_gain),
+        None => None,
+    }
+}
+
+// Orders the captures (and promotions) of the quiescence search, the best looking first.
+pub fn order_captures(board: &Board, moves: &mut [Move]) {
+    moves.sort_by_cached_key(|&chess_move| {
+        Reverse(get_capture_score(board, chess_move).unwrap_or(0))
+    });
+}
+
+pub struct Searcher<'tt> {
+    tt: &'tt TranspositionTable,
+    pub nodes: u64,
+    seldepth: usize,
+    pv: PvTable,
+    // These root moves are skipped, so the search finds the best of the remaining ones (for MultiPV, and the moves the tablebases show to be worse).
+    excluded_moves: Vec<Move>,
+    context: SearchContext,
+    params: EvalParams,
+    pawn_table: PawnTable,
+    // Positions with few enough pieces are scored by the tablebases, when there are some.
+    tablebases: Option<Arc<Tablebases>>,
+    // When there is a network, it evaluates instead, with the accumulator of every ply kept up to date with the moves.
+    #[cfg(feature = "nnue")]
+    network: Option<Arc<Network>>,
+    #[cfg(feature = "nnue")]
+    accumulators: Vec<Accumulator>,
+    // The move played at every ply of the current line (none for null moves), for the continuation history.
+    move_stack: [Option<MoveKey>; MAX_PLY + 1],
+    // Whether the move played at every ply of the current line was a capture or a pawn move.
+    zeroing_stack: [bool; MAX_PLY + 1],
+    config: SearchConfig,
+    time: TimeManager,
+    // This is set from outside of the search (like by the UCI "stop" command) to end it.
+    stop_signal: Arc<AtomicBool>,
+    // With Lazy SMP, the main thread is the first, and the others are helpers.
+    thread_id: usize,
+    // The helper threads add their nodes here periodically, so the main thread can report the total.
+    helper_nodes: Arc<AtomicU64>,
+    // Once this is set, the search unwinds as fast as possible and its results are thrown away.
+    stopped: bool,
+    // The hashes of the positions of the game before the root, and of the current line, to find repetitions.
+    game_hashes: Vec<u64>,
+    line_hashes: [u64; MAX_PLY + 1],
+}
+
+impl<'tt> Searcher<'tt> {
+    pub fn new(tt: &'tt TranspositionTable) -> Self {
+        Self {
+            tt,
+            nodes: 0,
+            seldepth: 0,
+            pv: PvTable::new(),
+            excluded_moves: Vec::new(),
+            context: SearchContext::new(),
+            params: EvalParams::default(),
+            pawn_table: PawnTable::new(),
+            tablebases: None,
+            #[cfg(feature = "nnue")]
+            network: None,
+            #[cfg(feature = "nnue")]
+            accumulators: Vec::new(),
+            move_stack: [None; MAX_PLY + 1],
+            zeroing_stack: [false; MAX_PLY + 1],
+            config: SearchConfig::default(),
+            time: TimeManager::default(),
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            thread_id: 0,
+            helper_nodes: Arc::new(AtomicU64::new(0)),
+            stopped: false,
+            game_hashes: Vec::new(),
+            line_hashes: [0; MAX_PLY + 1],
+        }
+    }
+
+    // Without a time manager, the search is only limited by the maximum depth and the stop signal.
+    pub fn set_time_manager(&mut self, time: TimeManager) {
+        self.time = time;
+    }
+
+    // The context keeps what was learned for move ordering between searches, so it should be reused for the following searches of a game.
+    pub fn set_context(&mut self, context: SearchContext) {
+        self.context = context;
+    }
+
+    pub fn into_context(self) -> SearchContext {
+        self.context
+    }
+
+    pub fn set_config(&mut self, config: SearchConfig) {
+        self.config = config;
+    }
+
+    // This must be set before searching, since the pawn table keeps the scores computed with the weights.
+    pub fn set_params(&mut self, params: EvalParams) {
+        self.params = params;
+    }
+
+    pub fn set_tablebases(&mut self, tablebases: Option<Arc<Tablebases>>) {
+        self.tablebases = tablebases;
+    }
+
+    // The fifty-move rule isn't tracked, so cursed wins and blessed losses are scored as draws.
+    // A line can only reach a new table through a capture or a pawn move, so probing right after them is enough.
+    fn probe_tablebases(&self, board: &Board, depth: i32, ply: usize) -> Option<(i32, Bound)> {
+        let tablebases = self.tablebases.as_ref()?;
+        let piece_count = (board.moving_player.pieces | board.moved_player.pieces).count_ones();
+
+        if ply == 0
+            || !self.zeroing_stack[ply - 1]
+            || piece_count > tablebases.get_max_pieces()
+            || (piece_count == tablebases.get_max_pieces() && depth < TABLEBASE_PROBE_DEPTH)
+        {
+            return None;
+        }
+
+        let wdl = tablebases.probe_wdl(board)?;
+
+        Some(match wdl {
+            Wdl::Win => (TABLEBASE_WIN - ply as i32, Bound::Lower),
+            Wdl::Loss => (-TABLEBASE_WIN + ply as i32, Bound::Upper),
+            _ => (0, Bound::Exact),
+        })
+    }
+
+    #[cfg(feature = "nnue")]
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.accumulators = match &network {
+            Some(network) => vec![Accumulator::new(network, &Board::default()); MAX_PLY + 1],
+            None => Vec::new(),
+        };
+        self.network = network;
+    }
+
+    // The ply picks the accumulator, which only the network uses.
+    #[cfg_attr(not(feature = "nnue"), allow(unused_variables))]
+    fn evaluate(&mut self, board: &Board, ply: usize) -> i32 {
+        #[cfg(feature = "nnue")]
+        if let Some(network) = &self.network {
+            return network.evaluate(&self.accumulators[ply], board.current_player);
+        }
+
+        eval::evaluate(board, &self.params, &mut self.pawn_table)
+    }
+
+    // The accumulator of the root is computed from scratch, and those of the other plies from the one before.
+    #[cfg(feature = "nnue")]
+    fn refresh_accumulator(&mut self, board: &Board) {
+        if let Some(network) = &self.network {
+            self.accumulators[0] = Accumulator::new(network, board);
+        }
+    }
+
+    // Updates the accumulator of the next ply with the move, or copies it for a null move (which changes no pieces).
+    #[cfg(feature = "nnue")]
+    fn update_accumulator(
+        &mut self,
+        board: &Board,
+        chess_move: Option<Move>,
+        new_board: &Board,
+        ply: usize,
+    ) {
+        if let Some(network) = &self.network {
+            self.accumulators[ply + 1] = match chess_move {
+                Some(chess_move) => {
+                    self.accumulators[ply].after_move(network, board, chess_move, new_board)
+                }
+                None => self.accumulators[ply],
+            };
+        }
+    }
+
+    // Makes this the searcher of the given thread, out of all the threads searching the same position.
+    pub fn set_thread(&mut self, thread_id: usize, helper_nodes: Arc<AtomicU64>) {
+        self.thread_id = thread_id;
+        self.helper_nodes = helper_nodes;
+    }
+
+    // The nodes searched by this searcher, and if it is the main thread, by all of the helper threads as well.
+    pub fn get_total_nodes(&self) -> u64 {
+        if self.thread_id == 0 {
+            self.nodes + self.helper_nodes.load(Ordering::Relaxed)
+        } else {
+            self.nodes
+        }
+    }
+
+    pub fn set_stop_signal(&mut self, stop_signal: Arc<AtomicBool>) {
+        self.stop_signal = stop_signal;
+    }
+
+    // The hashes of the positions played in the game before the one searched, from the first.
+    pub fn set_game_hashes(&mut self, game_hashes: Vec<u64>) {
+        self.game_hashes = game_hashes;
+    }
+
+    // A position which already occurred is scored as a draw, since repeating it once means it can be repeated again.
+    // Hashes only match for the same player to move, and pawn moves and captures make earlier positions unreachable.
+    fn is_repetition(&self, board: &Board, ply: usize) -> bool {
+        ply > 0
+            && (self.line_hashes[..ply].contains(&board.hash)
+                || self.game_hashes.contains(&board.hash))
+    }
+
+    // Searches the position to a fixed depth, returning the best move (if there is one) and its score.
+    pub fn search(&mut self, board: &Board, depth: u32) -> (Option<Move>, i32) {
+        self.stopped = false;
+
+        let score = self.negamax(board, depth as i32, 0, -INFINITY, INFINITY, false);
+
+        (self.pv.get_line().first().copied(), score)
+    }
+
+    // Plays the line of the quiescence search, which ends in the quiet position the score of the position comes from.
+    // The static evaluation is only meaningful in quiet positions, which is why the tuner evaluates these instead.
+    pub fn get_quiet_position(&mut self, board: &Board) -> Board {
+        self.stopped = false;
+        self.quiescence(board, 0, -INFINITY, INFINITY);
+
+        let mut board = *board;
+
+        for chess_move in self.pv.get_line() {
+            board.make_move(chess_move);
+        }
+
+        board
+    }
+
+    // Searches the position at increasing depths until the limits of the time manager are reached or the search is stopped.
+    // After each completed iteration, "on_iteration" is called with its results.
+    // Returns the best move (if there is one) and the score of the last completed iteration.
+    // See: https://www.chessprogramming.org/Iterative_Deepening
+    pub fn iterative_deepening(
+        &mut self,
+        board: &Board,
+        on_iteration: impl FnMut(&SearchInfo),
+    ) -> (Option<Move>, i32) {
+        self.multi_pv(board, 1, on_iteration)
+            .first()
+            .map_or((None, 0), |&(best_move, score, _)| (Some(best_move), score))
+    }
+
+    // Like "iterative_deepening", but finds the best lines of the given amount of different root moves.
+    // Every iteration searches the root once per line, each time without the root moves of the lines found before.
+    // Returns the lines of the last completed iteration, from the best to the worst.
+    pub fn multi_pv(
+        &mut self,
+        board: &Board,
+        line_count: usize,
+        mut on_iteration: impl FnMut(&SearchInfo),
+    ) -> Vec<Line> {
+        let mut root_moves = MoveGen::run(*board);
+        // The tablebases know which root moves keep the best result, so the others are never searched.
+        let mut tablebase_excluded = Vec::new();
+
+        if let Some(best_moves) = self
+            .tablebases
+            .as_ref()
+            .and_then(|tablebases| tablebases.filter_root_moves(board, &root_moves))
+        {
+            (root_moves, tablebase_excluded) = root_moves
+                .into_iter()
+                .partition(|chess_move| best_moves.contains(chess_move));
+        }
+
+        let line_count = line_count.clamp(1, root_moves.len().max(1));
+        // Even if the first iteration doesn't finish, some legal move must be returned.
+        let mut lines: Vec<Line> = root_moves
+            .first()
+            .map(|&chess_move| (chess_move, 0, vec![chess_move]))
+            .into_iter()
+            .collect();
+        let max_depth = self
+            .time
+            .get_max_depth()
+            .unwrap_or(MAX_PLY as u32)
+            .clamp(1, MAX_PLY as u32);
+
+        self.stopped = false;
+        self.context.age();
+
+        if root_moves.is_empty() {
+            return Vec::new();
+        }
+
+        'deepening: for depth in 1..=max_depth {
+            // The first iteration always runs, so there is a move to play.
+            if depth > 1 && !self.time.should_start_iteration() {
+                break;
+            }
+
+            self.seldepth = 0;
+
+            // Half of the helper threads search one ply deeper, so the threads don't all search the same tree in lockstep.
+            let search_depth = (depth + self.thread_id as u32 % 2).min(MAX_PLY as u32);
+            let mut iteration_lines: Vec<Line> = Vec::with_capacity(line_count);
+
+            self.excluded_moves.clone_from(&tablebase_excluded);
+
+            for index in 0..line_count {
+                let previous_score = lines.get(index).map_or(0, |&(_, score, _)| score);
+
+                let Some(score) = self.aspiration_search(board, search_depth, previous_score)
+                else {
+                    break 'deepening;
+                };
+
+                let pv = self.pv.get_line();
+                let Some(&chess_move) = pv.first() else {
+                    break;
+                };
+
+                self.excluded_moves.push(chess_move);
+                iteration_lines.push((chess_move, score, pv));
+            }
+
+            self.excluded_moves.clone_from(&tablebase_excluded);
+
+            // A line searched later can still score better than an earlier one, since they are searched separately.
+            iteration_lines.sort_by_key(|&(_, score, _)| Reverse(score));
+            lines = iteration_lines;
+
+            let (best_move, best_score, _) = lines[0];
+            let time = self.time.get_elapsed();
+            let nodes = self.get_total_nodes();
+
+            self.time.on_iteration(Some(best_move), best_score);
+
+            for (index, (_, score, pv)) in lines.iter().enumerate() {
+                on_iteration(&SearchInfo {
+                    depth,
+                    multi_pv: index + 1,
+                    seldepth: self.seldepth,
+                    score: *score,
+                    nodes,
+                    nps: nodes * 1000 / (time.as_millis() as u64).max(1),
+                    time,
+                    pv: pv.clone(),
+                });
+            }
+
+            // There is no point in searching deeper once a forced mate was found.
+            if line_count == 1
+                && best_score.abs() >= MATE_BOUND
+                && MATE - best_score.abs() <= depth as i32
+            {
+                break;
+            }
+        }
+
+        self.excluded_moves.clear();
+
+        lines
+    }
+
+    // Searches a narrow window around the score of the previous iteration, widening it whenever the score falls outside of it.
+    // Returns nothing if the search was stopped.
+    // See: https://www.chessprogramming.org/Aspiration_Windows
+    fn aspiration_search(&mut self, board: &Board, depth: u32, previous_score: i32) -> Option<i32> {
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = if depth >= ASPIRATION_MIN_DEPTH {
+            (
+                (previous_score - delta).max(-INFINITY),
+                (previous_score + delta).min(INFINITY),
+            )
+        } else {
+            (-INFINITY, INFINITY)
+        };
+
+        loop {
+            let score = self.negamax(board, depth as i32, 0, alpha, beta, false);
+
+            if self.stopped {
+                return None;
+            }
+
+            if score <= alpha {
+                // The window is re-centered around the lower score, keeping some of its upper side.
+                beta = (alpha + beta) / 2;
+                alpha = (score - delta).max(-INFINITY);
+            } else if score >= beta {
+                beta = (score + delta).min(INFINITY);
+            } else {
+                return Some(score);
+            }
+
+            delta += delta / 2;
+        }
+    }
+
+    fn get_previous_moves(&self, ply: usize) -> PreviousMoves {
+        [
+            ply.checked_sub(1).and_then(|ply| self.move_stack[ply]),
+            ply.checked_sub(2).and_then(|ply| self.move_stack[ply]),
+        ]
+    }
+
+    // Orders the moves so the most promising ones are searched first, which makes alpha-beta cut off more often.
+    // The move from the transposition table comes first, then captures, killers, the counter-move, and lastly the other quiet moves by their history.
+    fn order_moves(&self, board: &Board, moves: &mut [Move], tt_move: Option<Move>, ply: usize) {
+        let previous_moves = self.get_previous_moves(ply);
+        let killers = self.context.get_killers(ply);
+        let counter_move = self.context.get_counter_move(&previous_moves);
+
+        moves.sort_by_cached_key(|&chess_move| {
+            Reverse(if Some(chess_move) == tt_move {
+                TT_MOVE_SCORE
+            } else if let Some(score) = get_capture_score(board, chess_move) {
+                CAPTURE_SCORE + score
+            } else if Some(chess_move) == killers[0] {
+                KILLER_SCORE
+            } else if Some(chess_move) == killers[1] {
+                KILLER_SCORE - 1
+            } else if Some(chess_move) == counter_move {
+                COUNTER_MOVE_SCORE
+            } else {
+                self.context.get_quiet_score(
+                    board.current_player,
+                    MoveKey::new(board, chess_move),
+                    &previous_moves,
+                )
+            })
+        });
+    }
+
+    fn count_node(&mut self, ply: usize) {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+
+        if self.time.is_node_limit_reached(self.nodes) {
+            self.stopped = true;
+        }
+
+        if self.nodes.is_multiple_of(NODES_BETWEEN_CHECKS) {
+            if self.thread_id > 0 {
+                self.helper_nodes
+                    .fetch_add(NODES_BETWEEN_CHECKS, Ordering::Relaxed);
+            }
+
+            if self.stop_signal.load(Ordering::Relaxed) || self.time.is_hard_limit_reached() {
+                self.stopped = true;
+            }
+        }
+    }
+
+    fn negamax(
+        &mut self,
+        board: &Board,
+        mut depth: i32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        allow_null_move: bool,
+    ) -> i32 {
+        if depth <= 0 {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+
+        #[cfg(feature = "nnue")]
+        if ply == 0 {
+            self.refresh_accumulator(board);
+        }
+
+        self.pv.clear_ply(ply);
+        self.count_node(ply);
+
+        if self.stopped || self.is_repetition(board, ply) {
+            return 0;
+        }
+
+        self.line_hashes[ply] = board.hash;
+
+        let in_check = board.is_in_check();
+        let mut moves = MoveGen::run(*board);
+
+        if moves.is_empty() {
+            // The side to move is either checkmated or stalemated.
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+
+        if ply >= MAX_PLY {
+            return self.evaluate(board, ply);
+        }
+
+        let entry = self.tt.probe(board.hash, ply);
+
+        if let Some(entry) = entry {
+            // The root always needs a move, so it is never cut off.
+            if ply > 0 && entry.depth as i32 >= depth {
+                match entry.bound {
+                    Bound::Exact => return entry.score,
+                    Bound::Lower if entry.score >= beta => return entry.score,
+                    Bound::Upper if entry.score <= alpha => return entry.score,
+                    _ => {}
+                }
+            }
+        }
+
+        // The tablebases are exact, so they end the search whenever their score is enough for a cutoff.
+        // Otherwise, their score still bounds the result of the node.
+        let tablebase_result = self.probe_tablebases(board, depth, ply);
+
+        if let Some((score, bound)) = tablebase_result {
+            let is_cutoff = match bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+
+            if is_cutoff {
+                self.tt.store(
+                    board.hash,
+                    TableEntry {
+                        best_move: None,
+                        score,
+                        depth: (depth + TABLEBASE_DEPTH_BONUS).min(MAX_PLY as i32) as u8,
+                        bound,
+                    },
+                    ply,
+                );
+
+                return score;
+            }
+        }
+
+        // Checks are searched deeper, since they are forcing and often lead to tactics.
+        if in_check && self.config.check_extensions {
+            depth += 1;
+        }
+
+        // Searching a null window means the node isn't on the principal variation, so it is safer to prune.
+        let pv_node = beta - alpha > 1;
+        let static_eval = if in_check {
+            -INFINITY
+        } else {
+            self.evaluate(board, ply)
+        };
+
+        if !pv_node && !in_check {
+            if let Some(score) =
+                self.prune_node(board, depth, ply, alpha, beta, static_eval, allow_null_move)
+            {
+                return score;
+            }
+
+            if self.stopped {
+                return 0;
+            }
+        }
+
+        // The stored move may come from a different position with the same hash, so it is verified first.
+        let tt_move = entry
+            .and_then(|entry| entry.best_move)
+            .filter(|&chess_move| board.is_legal(chess_move));
+
+        self.order_moves(board, &mut moves, tt_move, ply);
+
+        // Quiet moves are unlikely to raise alpha when the position is far below it.
+        // See: https://www.chessprogramming.org/Futility_Pruning
+        let can_prune_futile = self.config.futility_pruning
+            && !pv_node
+            && !in_check
+            && depth <= FUTILITY_MAX_DEPTH
+            && static_eval + FUTILITY_MARGIN * depth <= alpha;
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        let mut moves_searched = 0;
+        let mut quiets_searched = Vec::new();
+
+        for chess_move in moves {
+            if ply == 0 && self.excluded_moves.contains(&chess_move) {
+                continue;
+            }
+
+            let is_quiet =
+                !board.is_capture(chess_move) && !matches!(chess_move, Move::Promotion { .. });
+            let key = MoveKey::new(board, chess_move);
+
+            let mut board_copy = *board;
+            board_copy.make_move(chess_move);
+
+            let gives_check = board_copy.is_in_check();
+
+            // The first move is always searched, so there is a score to return.
+            if moves_searched > 0 && is_quiet && !in_check && !gives_check {
+                if can_prune_futile {
+                    continue;
+                }
+
+                // Late quiet moves in shallow, non-principal nodes are rarely good, given the ordering.
+                // See: https://www.chessprogramming.org/Futility_Pruning#MoveCountBasedPruning
+                if self.config.late_move_pruning
+                    && !pv_node
+                    && depth <= LMP_MAX_DEPTH
+                    && moves_searched >= LMP_BASE + depth * depth
+                {
+                    continue;
+                }
+            }
+
+            self.move_stack[ply] = Some(key);
+            self.zeroing_stack[ply] = board.is_zeroing(chess_move);
+
+            #[cfg(feature = "nnue")]
+            self.update_accumulator(board, Some(chess_move), &board_copy, ply);
+
+            // The first move is searched with the full window, and the rest with a null window, re-searched only if they turn out better.
+            // See: https://www.chessprogramming.org/Principal_Variation_Search
+            let score = if moves_searched == 0 {
+                -self.negamax(&board_copy, depth - 1, ply + 1, -beta, -alpha, true)
+            } else {
+                // Late quiet moves are searched to a lower depth first.
+                // See: https://www.chessprogramming.org/Late_Move_Reductions
+                let reduction = if self.config.late_move_reductions
+                    && depth >= LMR_MIN_DEPTH
+                    && moves_searched >= LMR_MIN_MOVES
+                    && is_quiet
+                    && !in_check
+                    && !gives_check
+                {
+                    (get_reduction(depth, moves_searched) - pv_node as i32).clamp(0, depth - 2)
+                } else {
+                    0
+                };
+
+                let mut score = -self.negamax(
+                    &board_copy,
+                    depth - 1 - reduction,
+                    ply + 1,
+                    -alpha - 1,
+                    -alpha,
+                    true,
+                );
+
+                if score > alpha && reduction > 0 {
+                    score =
+                        -self.negamax(&board_copy, depth - 1, ply + 1, -alpha - 1, -alpha, true);
+                }
+
+                if score > alpha && score < beta {
+                    score = -self.negamax(&board_copy, depth - 1, ply + 1, -beta, -alpha, true);
+                }
+
+                score
+            };
+
+            moves_searched += 1;
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(chess_move);
+            }
+
+            if score > alpha {
+                alpha = score;
+                self.pv.update(ply, chess_move);
+            }
+
+            if alpha >= beta {
+                if is_quiet {
+                    let previous_moves = self.get_previous_moves(ply);
+
+                    self.context.update_cutoff(
+                        board,
+                        ply,
+                        depth,
+                        chess_move,
+                        &previous_moves,
+                        &quiets_searched,
+                    );
+                }
+
+                break;
+            }
+
+            if is_quiet {
+                quiets_searched.push(key);
+            }
+        }
+
+        // Without some of its moves, the root's result isn't the position's.
+        if ply == 0 && !self.excluded_moves.is_empty() {
+            return best_score;
+        }
+
+        let mut bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        if let Some((score, tablebase_bound)) = tablebase_result {
+            let is_beyond = match tablebase_bound {
+                Bound::Lower => best_score < score,
+                Bound::Upper => best_score > score,
+                Bound::Exact => false,
+            };
+
+            if is_beyond {
+                best_score = score;
+                bound = tablebase_bound;
+            }
+        }
+
+        self.tt.store(
+            board.hash,
+            TableEntry {
+                best_move: if best_score > original_alpha {
+                    best_move
+                } else {
+                    None
+                },
+                score: best_score,
+                depth: depth.clamp(0, u8::MAX as i32) as u8,
+                bound,
+            },
+            ply,
+        );
+
+        best_score
+    }
+
+    // Tries to prove the node isn't worth searching, before any of its moves are searched.
+    // Returns the score to return from the node if it is pruned.
+    #[allow(clippy::too_many_arguments)]
+    fn prune_node(
+        &mut self,
+        board: &Board,
+        depth: i32,
+        ply: usize,
+        alpha: i32,
+        beta: i32,
+        static_eval: i32,
+        allow_null_move: bool,
+    ) -> Option<i32> {
+        // When the position is far above beta, a shallow search will most likely fail high too.
+        // See: https://www.chessprogramming.org/Reverse_Futility_Pruning
+        if self.config.reverse_futility_pruning
+            && depth <= REVERSE_FUTILITY_MAX_DEPTH
+            && beta.abs() < TABLEBASE_BOUND
+            && static_eval - REVERSE_FUTILITY_MARGIN * depth >= beta
+        {
+            return Some(static_eval);
+        }
+
+        // When the position is far below alpha, only captures are likely to help, so the quiescence search decides.
+        // See: https://www.chessprogramming.org/Razoring
+        if self.config.razoring
+            && depth <= RAZORING_MAX_DEPTH
+            && static_eval + RAZORING_MARGIN * depth <= alpha
+        {
+            let score = self.quiescence(board, ply, alpha, alpha + 1);
+
+            if score <= alpha {
+                return Some(score);
+            }
+        }
+
+        // If passing the turn still fails high, then a real move would most likely do so too.
+        // In pawn endings zugzwang is common, so passing might be the best "move", which breaks this assumption.
+        // See: https://www.chessprogramming.org/Null_Move_Pruning
+        if self.config.null_move_pruning
+            && allow_null_move
+            && depth >= NULL_MOVE_MIN_DEPTH
+            && static_eval >= beta
+            && has_non_pawn_material(board)
+        {
+            let reduction = NULL_MOVE_REDUCTION + depth / 6;
+            let mut board_copy = *board;
+            board_copy.make_null_move();
+            self.move_stack[ply] = None;
+            self.zeroing_stack[ply] = false;
+
+            #[cfg(feature = "nnue")]
+            self.update_accumulator(board, None, &board_copy, ply);
+
+            let score = -self.negamax(
+                &board_copy,
+                depth - 1 - reduction,
+                ply + 1,
+                -beta,
+                -beta + 1,
+                false,
+            );
+
+            if !self.stopped && score >= beta {
+                // A mate (or tablebase win) found after passing the turn isn't a real one.
+                return Some(if score >= TABLEBASE_BOUND {
+                    beta
+                } else {
+                    score
+                });
+            }
+        }
+
+        None
+    }
+
+    // Searches captures and promotions until the position is quiet, so the static evaluation isn't taken in the middle of an exchange.
+    // When in check every evasion is searched instead, since standing pat isn't an option.
+    // See: https://www.chessprogramming.org/Quiescence_Search
+    fn quiescence(&mut self, board: &Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        #[cfg(feature = "nnue")]
+        if ply == 0 {
+            self.refresh_accumulator(board);
+        }
+
+        self.pv.clear_ply(ply);
+        self.count_node(ply);
+
+        if self.stopped {
+            return 0;
+        }
+
+        let in_check = board.is_in_check();
+
+        if ply >= MAX_PLY {
+            return self.evaluate(board, ply);
+        }
+
+        let (mut moves, stand_pat) = if in_check {
+            let moves = MoveGen::run(*board);
+
+            if moves.is_empty() {
+                return -MATE + ply as i32;
+            }
+
+            (moves, -INFINITY)
+        } else {
+            let stand_pat = self.evaluate(board, ply);
+
+            // The side to move can usually do at least as well as the static evaluation, by not capturing anything.
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+
+            alpha = alpha.max(stand_pat);
+
+            (MoveGen::run_captures(*board), stand_pat)
+        };
+
+        order_captures(board, &mut moves);
+
+        let mut best_score = stand_pat;
+
+        for chess_move in moves {
+            if !in_check {
+                let gain = board
+                    .get_captured_piece(chess_move)
+                    .map_or(0, PieceKind::see_value);
+
+                let gain = match chess_move {
+                    // Under-promotions are almost never better than promoting to a queen.
+                    Move::Promotion { promotion_to, .. } if promotion_to != PieceKind::Queen => {
+                        continue;
+                    }
+                    Move::Promotion { .. } => {
+                        gain + PieceKind::Queen.see_value() - PieceKind::Pawn.see_value()
+                    }
+                    _ => gain,
+                };
+
+                // Delta pruning: even winning the piece (with a margin) won't raise alpha.
+                // See: https://www.chessprogramming.org/Delta_Pruning
+                if stand_pat + gain + DELTA_MARGIN <= alpha {
+                    continue;
+                }
+
+                // Captures losing material are very unlikely to matter.
+                if !board.see_ge(chess_move, 0) {
+                    continue;
+                }
+            }
+
+            let mut board_copy = *board;
+            board_copy.make_move(chess_move);
+
+            #[cfg(feature = "nnue")]
+            self.update_accumulator(board, Some(chess_move), &board_copy, ply);
+
+            let score = -self.quiescence(&board_copy, ply + 1, -beta, -alpha);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+            }
+
+            if score > alpha {
+                alpha = score;
+                self.pv.update(ply, chess_move);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best_score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc, time::Duration};
+
+    use crate::{
+        engine::{
+            eval,
+            params::EvalParams,
+            pawns::PawnTable,
+            time::{FakeClock, SearchLimits, TimeManager},
+            tt::TranspositionTable,
+        },
+        game::board::Board,
+        generators::{Move, MoveGen},
+    };
+
+    use super::{SearchConfig, Searcher, INFINITY, MATE};
+
+    fn limited_searcher<'tt>(
+        table: &'tt TranspositionTable,
+        board: &Board,
+        limits: SearchLimits,
+    ) -> Searcher<'tt> {
+        let mut searcher = Searcher::new(table);
+
+        searcher.set_time_manager(TimeManager::new(
+            &limits,
+            board.current_player,
+            Arc::new(FakeClock::new()),
+        ));
+
+        searcher
+    }
+
+    #[test]
+    fn finds_back_rank_mate() {
+        let table = TranspositionTable::new(1);
+        let board = Board::from_str("6k1/5ppp/8/8/8/8/5PPP/2R1R1K1 w - - 0 1").unwrap();
+
+        let (best_move, score) = Searcher::new(&table).search(&board, 3);
+
+        let mut board_copy = board;
+        board_copy.make_move(best_move.unwrap());
+
+        assert_eq!(score, MATE - 1);
+        assert!(board_copy.is_in_check() && MoveGen::run(board_copy).is_empty());
+    }
+
+    #[test]
+    fn table_keeps_result() {
+        let table = TranspositionTable::new(1);
+        let board =
+            Board::from_str("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+
+        let (_, score) = Searcher::new(&table).search(&board, 4);
+        let mut searcher = Searcher::new(&table);
+        let (_, second_score) = searcher.search(&board, 4);
+
+        // The second search is answered by the table.
+        assert_eq!(score, second_score);
+        assert!(searcher.nodes < 100);
+    }
+
+    #[test]
+    fn quiescence_resolves_exchanges() {
+        let table = TranspositionTable::new(1);
+        // Taking the pawn on d5 loses the queen to the pawn on e6, which a depth 1 search only sees through the quiescence search.
+        let board = Board::from_str("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+
+        let (best_move, score) = Searcher::new(&table).search(&board, 1);
+
+        assert_ne!(best_move, Some(Move::from_str("qd1d5").unwrap()));
+        // The positional terms move the score a little, but the queen is kept.
+        assert!((score - (eval::QUEEN_VALUE - 2 * eval::PAWN_VALUE)).abs() < eval::PAWN_VALUE);
+
+        // Here the pawn is free, so the score is the one after taking it.
+        let board = Board::from_str("4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let mut after_capture = board;
+        after_capture.make_move(Move::from_str("qd1d5").unwrap());
+
+        assert_eq!(
+            Searcher::new(&table).quiescence(&board, 0, -INFINITY, INFINITY),
+            -eval::evaluate(
+                &after_capture,
+                &EvalParams::default(),
+                &mut PawnTable::new()
+            )
+        );
+
+        // The exchange is played out to reach the quiet position.
+        assert_eq!(
+            Searcher::new(&table).get_quiet_position(&board).hash,
+            after_capture.hash
+        );
+    }
+
+    #[cfg(feature = "nnue")]
+    #[test]
+    fn network_evaluates() {
+        use crate::engine::nnue::{Accumulator, Network};
+
+        let table = TranspositionTable::new(1);
+        let network = Arc::new(Network::random(1));
+        let mut searcher = Searcher::new(&table);
+        searcher.set_network(Some(Arc::clone(&network)));
+
+        // Without any captures, the quiescence search returns the evaluation of the network.
+        let board = Board::default();
+
+        assert_eq!(
+            searcher.quiescence(&board, 0, -INFINITY, INFINITY),
+            network.evaluate(&Accumulator::new(&network, &board), board.current_player)
+        );
+
+        // The accumulators are updated along the search, through every kind of move.
+        let board =
+            Board::from_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let (best_move, _) = searcher.search(&board, 4);
+
+        assert!(MoveGen::run(board).contains(&best_move.unwrap()));
+    }
+
+    #[test]
+    fn repetitions_are_draws() {
+        let table = TranspositionTable::new(1);
+        // White is up a queen, but every move leads back to a position of the game.
+        let board = Board::from_str("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let game_hashes = MoveGen::run(board)
+            .into_iter()
+            .map(|chess_move| {
+                let mut board_copy = board;
+                board_copy.make_move(chess_move);
+
+                board_copy.hash
+            })
+            .collect();
+
+        let (_, score) = Searcher::new(&table).search(&board, 3);
+
+        assert!(score > eval::QUEEN_VALUE / 2);
+
+        // The scores of the first search are kept in the table, so a new one is used.
+        let table = TranspositionTable::new(1);
+        let mut searcher = Searcher::new(&table);
+        searcher.set_game_hashes(game_hashes);
+        let (best_move, score) = searcher.search(&board, 3);
+
+        assert!(best_move.is_some());
+        assert_eq!(score, 0);
+    }
+
+    #[test]
+    fn quiescence_evades_checks() {
+        let table = TranspositionTable::new(1);
+        // Black is checkmated, which the quiescence search must notice instead of standing pat.
+        let board = Board::from_str("R5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1").unwrap();
+
+        assert_eq!(
+            Searcher::new(&table).quiescence(&board, 0, -INFINITY, INFINITY),
+            -MATE
+        );
+
+        // The best evasion is the king capturing the checking rook, even though it isn't defended against.
+        let board = Board::from_str("4k3/8/8/8/8/8/3r4/3K4 w - - 0 1").unwrap();
+
+        assert_eq!(
+            Searcher::new(&table).quiescence(&board, 0, -INFINITY, INFINITY),
+            0
+        );
+    }
+
+    #[test]
+    fn iterations_are_reported() {
+        let table = TranspositionTable::new(1);
+        let board =
+            Board::from_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let mut iterations = Vec::new();
+
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..Default::default()
+        };
+
+        let (best_move, score) = limited_searcher(&table, &board, limits)
+            .iterative_deepening(&board, |info| iterations.push(info.clone()));
+
+        assert_eq!(
+            iterations.iter().map(|info| info.depth).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+
+        let last = iterations.last().unwrap();
+
+        assert_eq!(last.score, score);
+        assert_eq!(last.pv.first().copied(), best_move);
+        assert!(last.seldepth >= 4);
+        assert!(iterations
+            .windows(2)
+            .all(|pair| pair[0].nodes < pair[1].nodes));
+
+        // Every move of the principal variation must be legal in the position it is played in.
+        let mut board_copy = board;
+
+        for &chess_move in &last.pv {
+            assert!(board_copy.is_legal(chess_move));
+
+            board_copy.make_move(chess_move);
+        }
+    }
+
+    #[test]
+    fn mate_ends_deepening() {
+        let table = TranspositionTable::new(1);
+        let board = Board::from_str("6k1/5ppp/8/8/8/8/5PPP/2R1R1K1 w - - 0 1").unwrap();
+        let mut depths = Vec::new();
+
+        let (_, score) =
+            Searcher::new(&table).iterative_deepening(&board, |info| depths.push(info.depth));
+
+        assert_eq!(score, MATE - 1);
+        assert_eq!(depths, [1]);
+    }
+
+    #[test]
+    fn multi_pv_ranks_lines() {
+        let table = TranspositionTable::new(1);
+        // Taking the queen is best, while the other lines have to let it escape.
+        let board = Board::from_str("q7/8/8/4k3/7n/8/7R/RK6 w - - 0 1").unwrap();
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..Default::default()
+        };
+        let mut reported = Vec::new();
+
+        let lines = limited_searcher(&table, &board, limits)
+            .multi_pv(&board, 3, |info| reported.push(info.clone()));
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].0, Move::from_str("ra1a8").unwrap());
+        assert!(lines[0].1 > lines[1].1 && lines[1].1 >= lines[2].1);
+        assert!(lines[1].0 != lines[0].0 && lines[2].0 != lines[0].0 && lines[2].0 != lines[1].0);
+
+        for (chess_move, _, pv) in &lines {
+            assert_eq!(pv.first(), Some(chess_move));
+        }
+
+        // Every iteration reports each line, ranked.
+        assert_eq!(reported.len(), 4 * 3);
+        assert_eq!(
+            reported[9..]
+                .iter()
+                .map(|info| (info.multi_pv, info.score))
+                .collect::<Vec<_>>(),
+            lines
+                .iter()
+                .enumerate()
+                .map(|(index, &(_, score, _))| (index + 1, score))
+                .collect::<Vec<_>>()
+        );
+
+        // There can't be more lines than legal moves.
+        let board = Board::from_str("7k/8/8/8/8/8/6q1/7K w - - 0 1").unwrap();
+
+        assert_eq!(Searcher::new(&table).multi_pv(&board, 5, |_| {}).len(), 1);
+    }
+
+    #[test]
+    fn limits_stop_search() {
+        let table = TranspositionTable::new(1);
+        let board = Board::default();
+
+        let limits = SearchLimits {
+            nodes: Some(5000),
+            ..Default::default()
+        };
+        let mut searcher = limited_searcher(&table, &board, limits);
+
+        let (best_move, _) = searcher.iterative_deepening(&board, |_| {});
+
+        assert!(board.is_legal(best_move.unwrap()));
+        assert_eq!(searcher.nodes, 5000);
+
+        // The time is already up, so only the first iteration runs.
+        let clock = Arc::new(FakeClock::new());
+        let limits = SearchLimits {
+            move_time: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let mut searcher = Searcher::new(&table);
+        let mut depths = Vec::new();
+
+        searcher.set_time_manager(TimeManager::new(
+            &limits,
+            board.current_player,
+            clock.clone(),
+        ));
+        clock.advance(Duration::from_millis(100));
+
+        let (best_move, _) = searcher.iterative_deepening(&board, |info| depths.push(info.depth));
+
+        assert!(board.is_legal(best_move.unwrap()));
+        assert!(depths.len() <= 1);
+    }
+
+    fn get_configs() -> Vec<SearchConfig> {
+        let mut configs = vec![SearchConfig::default(), SearchConfig::disabled()];
+
+        for toggle in [
+            |config: &mut SearchConfig| config.null_move_pruning = false,
+            |config: &mut SearchConfig| config.late_move_reductions = false,
+            |config: &mut SearchConfig| config.futility_pruning = false,
+            |config: &mut SearchConfig| config.reverse_futility_pruning = false,
+            |config: &mut SearchConfig| config.razoring = false,
+            |config: &mut SearchConfig| config.late_move_pruning = false,
+            |config: &mut SearchConfig| config.check_extensions = false,
+        ] {
+            let mut config = SearchConfig::default();
+            toggle(&mut config);
+
+            configs.push(config);
+        }
+
+        configs
+    }
+
+    #[test]
+    fn configs_find_tactics() {
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..Default::default()
+        };
+
+        for config in get_configs() {
+            let table = TranspositionTable::new(1);
+            let board = Board::from_str("6k1/5ppp/8/8/8/8/5PPP/2R1R1K1 w - - 0 1").unwrap();
+            let mut searcher = limited_searcher(&table, &board, limits);
+
+            searcher.set_config(config);
+
+            let (_, score) = searcher.iterative_deepening(&board, |_| {});
+
+            assert_eq!(score, MATE - 1, "{:?}", config);
+
+            // The queen can be taken for free.
+            let table = TranspositionTable::new(1);
+            let board = Board::from_str("4k3/pp6/8/3q4/8/8/PP6/3RK3 w - - 0 1").unwrap();
+            let mut searcher = limited_searcher(&table, &board, limits);
+
+            searcher.set_config(config);
+
+            let (best_move, _) = searcher.iterative_deepening(&board, |_| {});
+
+            assert_eq!(
+                best_move,
+                Some(Move::from_str("rd1d5").unwrap()),
+                "{:?}",
+                config
+            );
+        }
+    }
+
+    #[test]
+    fn pruning_reduces_nodes() {
+        let board =
+            Board::from_str("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4")
+                .unwrap();
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..Default::default()
+        };
+
+        let get_nodes = |config| {
+            let table = TranspositionTable::new(1);
+            let mut searcher = limited_searcher(&table, &board, limits);
+
+            searcher.set_config(config);
+            searcher.iterative_deepening(&board, |_| {});
+
+            searcher.nodes
+        };
+
+        assert!(get_nodes(SearchConfig::default()) < get_nodes(SearchConfig::disabled()));
+    }
+}
@@ -0,0 +1,1868 @@
+// Probing of Syzygy endgame tablebases, which know the result of every position with few enough pieces.
+// The WDL tables (".rtbw") tell whether the side to move wins, draws or loses, and the DTZ tables (".rtbz") how many plies are left until the next capture or pawn move on the way there.
+// The tables are read into memory the first time a position of their material is probed.
+// The decoding follows the probing code of Stockfish, from which most of the names of the format come.
+// See: https://www.chessprogramming.org/Syzygy_Bases
+use std::{
+    collections::HashMap,
+    env, fs,
+    ops::Neg,
+    path::PathBuf,
+    sync::{LazyLock, OnceLock},
+};
+
+use crate::{
+    engine::endgame,
+    game::board::Board,
+    generators::{Move, MoveGen, Square},
+    tables::KING_MOVES,
+    BitBoard, Piece, PieceKind, Player,
+};
+
+const MAX_PIECES: usize = 7;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+// The flags of the whole file.
+const SPLIT: u8 = 1;
+const HAS_PAWNS: u8 = 2;
+
+// The flags of every table of a file.
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+// A leaf of the tree of symbols has no right child.
+const LEAF: u16 = 0xFFF;
+
+// The names of the tables list the pieces of both players in this order, like "KRPvKR".
+const PIECE_CHARS: [(PieceKind, char); 6] = [
+    (PieceKind::King, 'K'),
+    (PieceKind::Queen, 'Q'),
+    (PieceKind::Rook, 'R'),
+    (PieceKind::Bishop, 'B'),
+    (PieceKind::Knight, 'N'),
+    (PieceKind::Pawn, 'P'),
+];
+
+// Cursed wins and blessed losses are wins and losses which the fifty-move rule turns into draws.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Self {
+        match value {
+            ..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+
+    fn get_value(self) -> i32 {
+        self as i32 - 2
+    }
+
+    // The distance to zeroing of the position before a zeroing move which leads to this result.
+    fn get_dtz_before_zeroing(self) -> i32 {
+        match self {
+            Wdl::Loss => -1,
+            Wdl::BlessedLoss => -101,
+            Wdl::Draw => 0,
+            Wdl::CursedWin => 101,
+            Wdl::Win => 1,
+        }
+    }
+}
+
+impl Neg for Wdl {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Wdl::from_value(-self.get_value())
+    }
+}
+
+// The tables which map the squares of the pieces to the index of the position in a table.
+struct Encoding {
+    // Squares below the a1-h8 diagonal, to 0..28.
+    map_b1h1h7: [u64; 64],
+    // Squares of the a1-d1-d4 triangle, to 0..10 (the diagonal last).
+    map_a1d1d4: [usize; 64],
+    // The 462 ways to place two kings, when the first is in the a1-d1-d4 triangle.
+    map_kk: [[u64; 64]; 10],
+    // The ways to choose "k" (the first index) of "n" (the second index).
+    binomial: [[u64; 64]; 6],
+    // Squares from a2 to h7, to 0..48. The leading pawn is the one with the highest value.
+    map_pawns: [usize; 64],
+    lead_pawn_indices: [[u64; 64]; 6],
+    lead_pawn_sizes: [[u64; 4]; 6],
+}
+
+static ENCODING: LazyLock<Encoding> = LazyLock::new(Encoding::new);
+
+fn get_diagonal_offset(square: u32) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+impl Encoding {
+    fn new() -> Self {
+        let mut encoding = Self {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 6],
+            map_pawns: [0; 64],
+            lead_pawn_indices: [[0; 64]; 6],
+            lead_pawn_sizes: [[0; 4]; 6],
+        };
+
+        let mut code = 0;
+
+        for square in 0..64 {
+            if get_diagonal_offset(square) < 0 {
+                encoding.map_b1h1h7[square as usize] = code;
+                code += 1;
+            }
+        }
+
+        let triangle = (0..=27).filter(|square| square % 8 <= 3);
+        let below = triangle
+            .clone()
+            .filter(|&square| get_diagonal_offset(square) < 0);
+        let diagonal = triangle.filter(|&square| get_diagonal_offset(square) == 0);
+
+        for (code, square) in below.chain(diagonal).enumerate() {
+            encoding.map_a1d1d4[square as usize] = code;
+        }
+
+        // The positions with both kings on the diagonal come last.
+        let map_a1d1d4 = encoding.map_a1d1d4;
+        let mut code = 0;
+        let mut both_on_diagonal = Vec::new();
+
+        for (index, first) in (0..10).flat_map(|index| {
+            (0..=27)
+                .filter(move |&square| {
+                    square % 8 <= 3
+                        && get_diagonal_offset(square) <= 0
+                        && map_a1d1d4[square as usize] == index
+                })
+                .map(move |square| (index, square))
+        }) {
+            for second in 0..64 {
+                if (KING_MOVES[first as usize] | BitBoard::from(Square(first)))
+                    & BitBoard::from(Square(second))
+                    != BitBoard(0)
+                {
+                    continue;
+                }
+
+                match (get_diagonal_offset(first), get_diagonal_offset(second)) {
+                    (0, 1..) => {}
+                    (0, 0) => both_on_diagonal.push((index, second)),
+                    _ => {
+                        encoding.map_kk[index][second as usize] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+
+        for (index, second) in both_on_diagonal {
+            encoding.map_kk[index][second as usize] = code;
+            code += 1;
+        }
+
+        encoding.binomial[0][0] = 1;
+
+        for n in 1..64 {
+            for k in 0..6.min(n + 1) {
+                encoding.binomial[k][n] = if k > 0 {
+                    encoding.binomial[k - 1][n - 1]
+                } else {
+                    0
+                } + if k < n {
+                    encoding.binomial[k][n - 1]
+                } else {
+                    0
+                };
+            }
+        }
+
+        // A leading pawn closer to the edge, and lower on its file, leaves fewer squares for the other leading pawns.
+        let mut available_squares = 47;
+
+        for lead_pawn_count in 1..=5 {
+            for file in 0..4 {
+                let mut index = 0;
+
+                for rank in 1..=6 {
+                    let square = rank * 8 + file;
+
+                    if lead_pawn_count == 1 {
+                        encoding.map_pawns[square] = available_squares;
+                        encoding.map_pawns[square ^ 7] = available_squares - 1;
+                        available_squares = available_squares.saturating_sub(2);
+                    }
+
+                    encoding.lead_pawn_indices[lead_pawn_count][square] = index;
+                    index += encoding.binomial[lead_pawn_count - 1][encoding.map_pawns[square]];
+                }
+
+                encoding.lead_pawn_sizes[lead_pawn_count][file] = index;
+            }
+        }
+
+        encoding
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, &'static str> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or("Tablebase file is truncated")
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Result<u8, &'static str> {
+    data.get(offset)
+        .copied()
+        .ok_or("Tablebase file is truncated")
+}
+
+// The compressed data may be read a little past the end of the last block, where there is nothing to decode anymore.
+fn read_u32_be(data: &[u8], offset: usize) -> u32 {
+    data.get(offset..offset + 4)
+        .map_or(0, |bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+// One table of a file, for one player to move (and one file of the leading pawn, when there are pawns).
+// The values are compressed with "Recursive Pairing", where every symbol stands for a pair of symbols, and the symbols are Huffman coded.
+// See: http://www.larsson.dogma.net/dcc99.pdf
+#[derive(Clone, Default)]
+struct PairsData {
+    flags: u8,
+    // The value every position has when the table is a single value.
+    min_symbol_length: u8,
+    block_size: usize,
+    // Every "span" values, the sparse index tells in which block they are.
+    span: u64,
+    block_count: usize,
+    block_length_count: usize,
+    sparse_index_count: usize,
+    // These are offsets into the file.
+    lowest_symbols: usize,
+    symbol_tree: usize,
+    sparse_index: usize,
+    block_lengths: usize,
+    blocks: usize,
+    // The lowest code of every symbol length, padded to 64 bits.
+    bases: Vec<u64>,
+    // How many values every symbol stands for, minus one.
+    symbol_lengths: Vec<u8>,
+    // The pieces in the order of the encoding, as Stockfish numbers them.
+    pieces: [u8; MAX_PIECES],
+    group_indices: [u64; MAX_PIECES + 1],
+    group_lengths: [usize; MAX_PIECES + 1],
+    // Where the values of the DTZ map start, for wins, losses, cursed wins and blessed losses.
+    map_indices: [u16; 4],
+}
+
+fn get_tree_symbols(data: &[u8], tree: usize, symbol: u16) -> (u16, u16) {
+    let offset = tree + 3 * symbol as usize;
+    let (low, middle, high) = (data[offset], data[offset + 1], data[offset + 2]);
+
+    (
+        (middle as u16 & 0xF) << 8 | low as u16,
+        (high as u16) << 4 | (middle as u16) >> 4,
+    )
+}
+
+impl PairsData {
+    // Groups the pieces which are encoded together. The leading group is the leading pawns, the three first pieces when there is a unique piece, or otherwise the kings.
+    // The other groups are the pieces of the same kind and player.
+    fn set_groups(&mut self, material: &Material, order: [u8; 2], file: usize) {
+        let encoding = &*ENCODING;
+        let mut first_length: i32 = if material.has_pawns {
+            0
+        } else if material.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+        let mut group_count = 0;
+
+        self.group_lengths[0] = 1;
+
+        for index in 1..material.piece_count {
+            first_length -= 1;
+
+            if first_length > 0 || self.pieces[index] == self.pieces[index - 1] {
+                self.group_lengths[group_count] += 1;
+            } else {
+                group_count += 1;
+                self.group_lengths[group_count] = 1;
+            }
+        }
+
+        group_count += 1;
+        self.group_lengths[group_count] = 0;
+
+        // The groups are encoded in the order of the file, so some may be multiplied by the sizes of the ones after them.
+        let both_have_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+        let mut next = if both_have_pawns { 2 } else { 1 };
+        let mut free_squares = 64
+            - self.group_lengths[0]
+            - if both_have_pawns {
+                self.group_lengths[1]
+            } else {
+                0
+            };
+        let mut index = 1;
+
+        for k in 0.. {
+            if next >= group_count && k != order[0] as usize && k != order[1] as usize {
+                break;
+            }
+
+            if k == order[0] as usize {
+                self.group_indices[0] = index;
+                index *= if material.has_pawns {
+                    encoding.lead_pawn_sizes[self.group_lengths[0]][file]
+                } else if material.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] as usize {
+                self.group_indices[1] = index;
+                index *= encoding.binomial[self.group_lengths[1]][48 - self.group_lengths[0]];
+            } else {
+                self.group_indices[next] = index;
+                index *= encoding.binomial[self.group_lengths[next]][free_squares];
+                free_squares -= self.group_lengths[next];
+                next += 1;
+            }
+        }
+
+        self.group_indices[group_count] = index;
+    }
+
+    // Reads the sizes and the Huffman code of the table, returning where the data after them starts.
+    fn set_sizes(&mut self, data: &[u8], mut offset: usize) -> Result<usize, &'static str> {
+        self.flags = read_u8(data, offset)?;
+        offset += 1;
+
+        if self.flags & SINGLE_VALUE != 0 {
+            self.min_symbol_length = read_u8(data, offset)?;
+
+            return Ok(offset + 1);
+        }
+
+        let group_count = self.group_lengths.iter().position(|&length| length == 0);
+        let size = self.group_indices[group_count.unwrap_or(MAX_PIECES)];
+
+        self.block_size = 1usize
+            .checked_shl(read_u8(data, offset)? as u32)
+            .ok_or("Tablebase block size is invalid")?;
+        self.span = 1u64
+            .checked_shl(read_u8(data, offset + 1)? as u32)
+            .ok_or("Tablebase span is invalid")?;
+        self.sparse_index_count = size.div_ceil(self.span) as usize;
+
+        let padding = read_u8(data, offset + 2)? as usize;
+
+        self.block_count = data
+            .get(offset + 3..offset + 7)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+            .ok_or("Tablebase file is truncated")?;
+        self.block_length_count = self.block_count + padding;
+
+        let max_symbol_length = read_u8(data, offset + 7)?;
+
+        self.min_symbol_length = read_u8(data, offset + 8)?;
+        self.lowest_symbols = offset + 9;
+
+        if max_symbol_length < self.min_symbol_length || self.min_symbol_length == 0 {
+            return Err("Tablebase symbol lengths are invalid");
+        }
+
+        // The canonical Huffman code gives the longer symbols the lower codes, so the length of a symbol is found by comparing it with the lowest code of every length.
+        // See: https://en.wikipedia.org/wiki/Canonical_Huffman_code
+        let length_count = (max_symbol_length - self.min_symbol_length) as usize + 1;
+
+        self.bases = vec![0; length_count];
+
+        for index in (0..length_count - 1).rev() {
+            let lowest = read_u16(data, self.lowest_symbols + 2 * index)? as u64;
+            let next_lowest = read_u16(data, self.lowest_symbols + 2 * index + 2)? as u64;
+
+            self.bases[index] = self.bases[index + 1]
+                .wrapping_add(lowest)
+                .wrapping_sub(next_lowest)
+                / 2;
+        }
+
+        for (index, base) in self.bases.iter_mut().enumerate() {
+            *base = base
+                .checked_shl(64 - index as u32 - self.min_symbol_length as u32)
+                .unwrap_or(0);
+        }
+
+        offset = self.lowest_symbols + 2 * length_count;
+
+        let symbol_count = read_u16(data, offset)? as usize;
+
+        self.symbol_tree = offset + 2;
+
+        if data.len() < self.symbol_tree + 3 * symbol_count {
+            return Err("Tablebase file is truncated");
+        }
+
+        self.symbol_lengths = vec![0; symbol_count];
+
+        let mut visited = vec![false; symbol_count];
+
+        for symbol in 0..symbol_count {
+            if !visited[symbol] {
+                self.symbol_lengths[symbol] =
+                    self.get_symbol_length(data, symbol as u16, &mut visited)?;
+            }
+        }
+
+        Ok(self.symbol_tree + 3 * symbol_count + (symbol_count & 1))
+    }
+
+    fn get_symbol_length(
+        &mut self,
+        data: &[u8],
+        symbol: u16,
+        visited: &mut [bool],
+    ) -> Result<u8, &'static str> {
+        visited[symbol as usize] = true;
+
+        let (left, right) = get_tree_symbols(data, self.symbol_tree, symbol);
+
+        if right == LEAF {
+            return Ok(0);
+        }
+
+        if left as usize >= visited.len() || right as usize >= visited.len() {
+            return Err("Tablebase symbol tree is invalid");
+        }
+
+        for child in [left, right] {
+            if !visited[child as usize] {
+                self.symbol_lengths[child as usize] =
+                    self.get_symbol_length(data, child, visited)?;
+            }
+        }
+
+        Ok(self.symbol_lengths[left as usize]
+            .wrapping_add(self.symbol_lengths[right as usize])
+            .wrapping_add(1))
+    }
+
+    fn decompress(&self, data: &[u8], index: u64) -> i32 {
+        if self.flags & SINGLE_VALUE != 0 {
+            return self.min_symbol_length as i32;
+        }
+
+        // The sparse index gives the block of the value in the middle of every span, and its offset in the block.
+        // From there, the blocks are walked until the one of the index.
+        let sparse_entry = self.sparse_index + 6 * (index / self.span) as usize;
+        let mut block =
+            u32::from_le_bytes(data[sparse_entry..sparse_entry + 4].try_into().unwrap()) as usize;
+        let mut offset =
+            u16::from_le_bytes([data[sparse_entry + 4], data[sparse_entry + 5]]) as i64;
+
+        offset += (index % self.span) as i64 - (self.span / 2) as i64;
+
+        let get_block_length = |block: usize| {
+            let position = self.block_lengths + 2 * block;
+
+            u16::from_le_bytes([data[position], data[position + 1]]) as i64
+        };
+
+        while offset < 0 {
+            block -= 1;
+            offset += get_block_length(block) + 1;
+        }
+
+        while offset > get_block_length(block) {
+            offset -= get_block_length(block) + 1;
+            block += 1;
+        }
+
+        // The symbols are read from the start of the block, until the one which stands for the value.
+        let mut position = self.blocks + block * self.block_size;
+        let mut buffer =
+            (read_u32_be(data, position) as u64) << 32 | read_u32_be(data, position + 4) as u64;
+        let mut buffer_size = 64;
+        let min_length = self.min_symbol_length as u32;
+        let mut symbol;
+
+        position += 8;
+
+        loop {
+            let mut length = 0;
+
+            while buffer < self.bases[length] {
+                length += 1;
+            }
+
+            symbol = ((buffer - self.bases[length]) >> (64 - length as u32 - min_length)) as u16;
+            symbol = symbol.wrapping_add(u16::from_le_bytes([
+                data[self.lowest_symbols + 2 * length],
+                data[self.lowest_symbols + 2 * length + 1],
+            ]));
+
+            let symbol_length = self.symbol_lengths[symbol as usize] as i64;
+
+            if offset < symbol_length + 1 {
+                break;
+            }
+
+            offset -= symbol_length + 1;
+
+            let bit_count = length as u32 + min_length;
+
+            buffer <<= bit_count;
+            buffer_size -= bit_count;
+
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= (read_u32_be(data, position) as u64) << (64 - buffer_size);
+                position += 4;
+            }
+        }
+
+        // The symbol is expanded into the pair it stands for, until the one of the value is a leaf.
+        while self.symbol_lengths[symbol as usize] != 0 {
+            let (left, right) = get_tree_symbols(data, self.symbol_tree, symbol);
+            let left_length = self.symbol_lengths[left as usize] as i64;
+
+            if offset < left_length + 1 {
+                symbol = left;
+            } else {
+                offset -= left_length + 1;
+                symbol = right;
+            }
+        }
+
+        get_tree_symbols(data, self.symbol_tree, symbol).0 as i32
+    }
+}
+
+// The pieces of a table, from its name.
+struct Material {
+    piece_count: usize,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    // The pawns of the leading player first, which is the one with fewer pawns (when both have some).
+    pawn_counts: [usize; 2],
+    is_symmetric: bool,
+    // The material keys of both sides of the name, like the ones of the endgame recognizers.
+    keys: [u32; 2],
+}
+
+impl Material {
+    fn from_name(name: &str) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+        let mut counts = [[0; 6]; 2];
+
+        for (player, pieces) in [white, black].into_iter().enumerate() {
+            if !pieces.starts_with('K') || pieces[1..].contains('K') {
+                return None;
+            }
+
+            for char in pieces.chars() {
+                let kind = PIECE_CHARS
+                    .iter()
+                    .position(|&(_, piece_char)| piece_char == char)?;
+
+                counts[player][kind] += 1;
+            }
+        }
+
+        let piece_count = counts.iter().flatten().sum();
+        let pawns = [counts[0][5], counts[1][5]];
+        let white_leads = pawns[1] == 0 || (pawns[0] > 0 && pawns[1] >= pawns[0]);
+
+        if piece_count > MAX_PIECES {
+            return None;
+        }
+
+        Some(Self {
+            piece_count,
+            has_pawns: pawns[0] + pawns[1] > 0,
+            has_unique_pieces: counts.iter().any(|counts| counts[1..].contains(&1)),
+            pawn_counts: if white_leads {
+                pawns
+            } else {
+                [pawns[1], pawns[0]]
+            },
+            is_symmetric: counts[0] == counts[1],
+            // Every piece but the king gets four bits, with the pawns (which come last in the names) in the lowest ones.
+            keys: counts.map(|counts| {
+                counts[1..]
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .map(|(index, &count)| (count as u32) << (4 * index))
+                    .sum()
+            }),
+        })
+    }
+}
+
+// The tables are found by the material keys of both players, so nothing is allocated while probing.
+fn get_table_key(first: u32, second: u32) -> u64 {
+    (first as u64) << 32 | second as u64
+}
+
+// Stockfish numbers the pieces from 1 for pawns to 6 for kings, with 8 added for black.
+fn get_piece_code(piece: Piece) -> u8 {
+    let kind = match piece.piece_kind {
+        PieceKind::Pawn => 1,
+        PieceKind::Knight => 2,
+        PieceKind::Bishop => 3,
+        PieceKind::Rook => 4,
+        PieceKind::Queen => 5,
+        PieceKind::King => 6,
+    };
+
+    kind | (piece.player as u8) << 3
+}
+
+enum TableValue {
+    Value(i32),
+    // The DTZ table only has the positions of the other player to move.
+    OtherSide,
+}
+
+struct Table {
+    data: Vec<u8>,
+    is_dtz: bool,
+    material: Material,
+    // Indexed by the player to move (unless there is only one), and then the file of the leading pawn.
+    pairs: Vec<Vec<PairsData>>,
+    dtz_map: usize,
+}
+
+impl Table {
+    fn new(data: Vec<u8>, name: &str, is_dtz: bool) -> Result<Self, &'static str> {
+        let material = Material::from_name(name).ok_or("Tablebase name is invalid")?;
+        let magic = if is_dtz { DTZ_MAGIC } else { WDL_MAGIC };
+
+        if data.get(..4) != Some(&magic) {
+            return Err("Tablebase file has the wrong magic number");
+        }
+
+        let flags = read_u8(&data, 4)?;
+
+        if (flags & HAS_PAWNS != 0) != material.has_pawns
+            || (flags & SPLIT != 0) == material.is_symmetric
+        {
+            return Err("Tablebase file doesn't match its name");
+        }
+
+        let sides = if !is_dtz && !material.is_symmetric {
+            2
+        } else {
+            1
+        };
+        let files = if material.has_pawns { 4 } else { 1 };
+        let both_have_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+        let mut pairs = vec![vec![PairsData::default(); files]; sides];
+        let mut offset = 5;
+
+        for file in 0..files {
+            let first = read_u8(&data, offset)?;
+            let second = if both_have_pawns {
+                read_u8(&data, offset + 1)?
+            } else {
+                0xFF
+            };
+            let orders = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+
+            offset += 1 + both_have_pawns as usize;
+
+            for index in 0..material.piece_count {
+                let pieces = read_u8(&data, offset)?;
+
+                for (side, side_pairs) in pairs.iter_mut().enumerate() {
+                    side_pairs[file].pieces[index] =
+                        if side == 0 { pieces & 0xF } else { pieces >> 4 };
+                }
+
+                offset += 1;
+            }
+
+            for (side_pairs, order) in pairs.iter_mut().zip(orders) {
+                side_pairs[file].set_groups(&material, order, file);
+            }
+        }
+
+        offset += offset & 1;
+
+        for file in 0..files {
+            for side_pairs in &mut pairs {
+                offset = side_pairs[file].set_sizes(&data, offset)?;
+            }
+        }
+
+        let dtz_map = offset;
+
+        if is_dtz {
+            for file_pairs in &mut pairs[0] {
+                if file_pairs.flags & MAPPED == 0 {
+                    continue;
+                }
+
+                if file_pairs.flags & WIDE != 0 {
+                    offset += offset & 1;
+
+                    for map_index in &mut file_pairs.map_indices {
+                        *map_index = ((offset - dtz_map) / 2 + 1) as u16;
+                        offset += 2 * read_u16(&data, offset)? as usize + 2;
+                    }
+                } else {
+                    for map_index in &mut file_pairs.map_indices {
+                        *map_index = (offset - dtz_map + 1) as u16;
+                        offset += read_u8(&data, offset)? as usize + 1;
+                    }
+                }
+            }
+
+            offset += offset & 1;
+        }
+
+        for file in 0..files {
+            for side_pairs in &mut pairs {
+                side_pairs[file].sparse_index = offset;
+                offset += 6 * side_pairs[file].sparse_index_count;
+            }
+        }
+
+        for file in 0..files {
+            for side_pairs in &mut pairs {
+                side_pairs[file].block_lengths = offset;
+                offset += 2 * side_pairs[file].block_length_count;
+            }
+        }
+
+        for file in 0..files {
+            for side_pairs in &mut pairs {
+                let file_pairs = &mut side_pairs[file];
+
+                offset = offset.next_multiple_of(64);
+                file_pairs.blocks = offset;
+                offset += file_pairs.block_count * file_pairs.block_size;
+
+                if file_pairs.block_count > 0 && offset > data.len() {
+                    return Err("Tablebase file is truncated");
+                }
+            }
+        }
+
+        Ok(Self {
+            data,
+            is_dtz,
+            material,
+            pairs,
+            dtz_map,
+        })
+    }
+
+    fn get_pairs(&self, side: usize, file: usize) -> &PairsData {
+        &self.pairs[side % self.pairs.len()][file]
+    }
+
+    // DTZ tables keep their values in plies or moves, and remapped by how often they occur.
+    fn map_value(&self, file: usize, value: i32, wdl: Wdl) -> i32 {
+        if !self.is_dtz {
+            return value - 2;
+        }
+
+        let pairs = self.get_pairs(0, file);
+        let mut value = value as usize;
+
+        if pairs.flags & MAPPED != 0 {
+            let map_index = pairs.map_indices[match wdl {
+                Wdl::Loss => 1,
+                Wdl::CursedWin => 2,
+                Wdl::BlessedLoss => 3,
+                _ => 0,
+            }] as usize;
+
+            value = if pairs.flags & WIDE != 0 {
+                let position = self.dtz_map + 2 * (map_index + value);
+
+                u16::from_le_bytes([self.data[position], self.data[position + 1]]) as usize
+            } else {
+                self.data[self.dtz_map + map_index + value] as usize
+            };
+        }
+
+        let in_moves = match wdl {
+            Wdl::Win => pairs.flags & WIN_PLIES == 0,
+            Wdl::Loss => pairs.flags & LOSS_PLIES == 0,
+            _ => true,
+        };
+
+        if in_moves {
+            value *= 2;
+        }
+
+        value as i32 + 1
+    }
+
+    fn probe(&self, board: &Board, black_stronger: bool, wdl: Wdl) -> TableValue {
+        match self.get_index(board, black_stronger) {
+            Some((side, file, index)) => TableValue::Value(self.map_value(
+                file,
+                self.get_pairs(side, file).decompress(&self.data, index),
+                wdl,
+            )),
+            None => TableValue::OtherSide,
+        }
+    }
+
+    // Returns the player to move and the file of the leading pawn (which pick the table), and the index of the position in that table.
+    // The tables only keep the positions where white is the stronger player, so the colors are swapped when black is.
+    // Symmetric tables also only keep the positions with white to move.
+    fn get_index(&self, board: &Board, black_stronger: bool) -> Option<(usize, usize, u64)> {
+        let encoding = &*ENCODING;
+        let flip =
+            black_stronger || (self.material.is_symmetric && board.current_player == Player::Black);
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let side = flip as usize ^ board.current_player as usize;
+        let mut squares = [0u32; MAX_PIECES];
+        let mut pieces = [0u8; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = 0;
+        let mut file = 0;
+
+        if self.material.has_pawns {
+            let player = if (self.get_pairs(0, 0).pieces[0] ^ flip_color) & 8 == 0 {
+                Player::White
+            } else {
+                Player::Black
+            };
+
+            lead_pawns = board.get_player_state(player).pawns.0;
+
+            let mut bits = lead_pawns;
+
+            while bits != 0 {
+                squares[size] = bits.trailing_zeros() ^ flip_squares;
+                bits &= bits - 1;
+                size += 1;
+            }
+
+            let leading = (0..size)
+                .max_by_key(|&index| encoding.map_pawns[squares[index] as usize])
+                .unwrap_or(0);
+
+            squares.swap(0, leading);
+            file = (squares[0] % 8).min(7 - squares[0] % 8) as usize;
+        }
+
+        let lead_pawn_count = size;
+
+        if self.is_dtz
+            && self.get_pairs(0, file).flags & STM != side as u8
+            && !(self.material.is_symmetric && !self.material.has_pawns)
+        {
+            return None;
+        }
+
+        let mut bits = (board.moving_player.pieces | board.moved_player.pieces).0 ^ lead_pawns;
+
+        while bits != 0 {
+            let square = bits.trailing_zeros();
+            let piece = board
+                .pieces
+                .get_piece(Square(square))
+                .expect("The square is occupied");
+
+            squares[size] = square ^ flip_squares;
+            pieces[size] = get_piece_code(piece) ^ flip_color;
+            bits &= bits - 1;
+            size += 1;
+        }
+
+        let pairs = self.get_pairs(side, file);
+
+        // The pieces are sorted in the order of the table.
+        for index in lead_pawn_count..size.saturating_sub(1) {
+            if let Some(other) =
+                (index + 1..size).find(|&other| pairs.pieces[index] == pieces[other])
+            {
+                pieces.swap(index, other);
+                squares.swap(index, other);
+            }
+        }
+
+        // The board is mirrored so the leading piece is on the files from "a" to "d".
+        if squares[0] % 8 > 3 {
+            for square in &mut squares[..size] {
+                *square ^= 7;
+            }
+        }
+
+        let mut index;
+
+        if self.material.has_pawns {
+            index = encoding.lead_pawn_indices[lead_pawn_count][squares[0] as usize];
+
+            squares[1..lead_pawn_count].sort_by_key(|&square| encoding.map_pawns[square as usize]);
+
+            for (count, &square) in squares[1..lead_pawn_count].iter().enumerate() {
+                index += encoding.binomial[count + 1][encoding.map_pawns[square as usize]];
+            }
+        } else {
+            // Without pawns, the board is also flipped so the leading piece is on the lower half, and then below the a1-h8 diagonal.
+            if squares[0] / 8 > 3 {
+                for square in &mut squares[..size] {
+                    *square ^= 56;
+                }
+            }
+
+            if let Some(first) =
+                (0..pairs.group_lengths[0]).find(|&index| get_diagonal_offset(squares[index]) != 0)
+            {
+                if get_diagonal_offset(squares[first]) > 0 {
+                    for square in &mut squares[first..size] {
+                        *square = ((*square >> 3) | (*square << 3)) & 63;
+                    }
+                }
+            }
+
+            index = if self.material.has_unique_pieces {
+                let [first, second, third] = [squares[0], squares[1], squares[2]];
+                let second_adjust = (second > first) as u64;
+                let third_adjust = (third > first) as u64 + (third > second) as u64;
+                let rank = |square: u32| (square / 8) as u64;
+
+                if get_diagonal_offset(first) != 0 {
+                    (encoding.map_a1d1d4[first as usize] as u64 * 63 + second as u64
+                        - second_adjust)
+                        * 62
+                        + third as u64
+                        - third_adjust
+                } else if get_diagonal_offset(second) != 0 {
+                    (6 * 63 + rank(first) * 28 + encoding.map_b1h1h7[second as usize]) * 62
+                        + third as u64
+                        - third_adjust
+                } else if get_diagonal_offset(third) != 0 {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + rank(first) * 7 * 28
+                        + (rank(second) - second_adjust) * 28
+                        + encoding.map_b1h1h7[third as usize]
+                } else {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + 4 * 7 * 28
+                        + rank(first) * 7 * 6
+                        + (rank(second) - second_adjust) * 6
+                        + (rank(third) - third_adjust)
+                }
+            } else {
+                encoding.map_kk[encoding.map_a1d1d4[squares[0] as usize]][squares[1] as usize]
+            };
+        }
+
+        // The other groups are encoded by the squares left to them, in ascending order.
+        index *= pairs.group_indices[0];
+
+        let mut start = pairs.group_lengths[0];
+        let mut remaining_pawns = self.material.has_pawns && self.material.pawn_counts[1] > 0;
+        let mut next = 1;
+
+        while pairs.group_lengths[next] != 0 {
+            let length = pairs.group_lengths[next];
+            let mut group_index = 0;
+
+            squares[start..start + length].sort_unstable();
+
+            for count in 0..length {
+                let square = squares[start + count];
+                let adjust = squares[..start]
+                    .iter()
+                    .filter(|&&other| square > other)
+                    .count() as u32;
+
+                group_index += encoding.binomial[count + 1]
+                    [(square - adjust - 8 * remaining_pawns as u32) as usize];
+            }
+
+            remaining_pawns = false;
+            index += group_index * pairs.group_indices[next];
+            start += length;
+            next += 1;
+        }
+
+        Some((side, file, index))
+    }
+}
+
+// A table is only read once a position of its material is probed.
+struct LazyTable {
+    path: PathBuf,
+    table: OnceLock<Option<Table>>,
+}
+
+impl LazyTable {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            table: OnceLock::new(),
+        }
+    }
+
+    // A table which can't be read is treated like a missing one.
+    fn get(&self, name: &str, is_dtz: bool) -> Option<&Table> {
+        self.table
+            .get_or_init(|| {
+                fs::read(&self.path)
+                    .ok()
+                    .and_then(|data| Table::new(data, name, is_dtz).ok())
+            })
+            .as_ref()
+    }
+}
+
+#[derive(Default)]
+struct TableFiles {
+    name: String,
+    wdl: Option<LazyTable>,
+    dtz: Option<LazyTable>,
+}
+
+pub struct Tablebases {
+    tables: HashMap<u64, TableFiles>,
+    max_pieces: u32,
+}
+
+impl Tablebases {
+    // The directories are separated like in the "PATH" environment variable.
+    pub fn new(paths: &str) -> Result<Self, &'static str> {
+        let mut tables: HashMap<u64, TableFiles> = HashMap::new();
+
+        for directory in env::split_paths(paths) {
+            let entries =
+                fs::read_dir(&directory).map_err(|_| "Tablebase directory couldn't be read")?;
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let (Some(name), Some(extension)) = (
+                    path.file_stem().and_then(|name| name.to_str()),
+                    path.extension().and_then(|extension| extension.to_str()),
+                ) else {
+                    continue;
+                };
+
+                let Some(material) = Material::from_name(name) else {
+                    continue;
+                };
+
+                let files = tables
+                    .entry(get_table_key(material.keys[0], material.keys[1]))
+                    .or_insert_with(|| TableFiles {
+                        name: name.to_string(),
+                        ..Default::default()
+                    });
+
+                match extension {
+                    "rtbw" => files.wdl = Some(LazyTable::new(path)),
+                    "rtbz" => files.dtz = Some(LazyTable::new(path)),
+                    _ => {}
+                }
+            }
+        }
+
+        tables.retain(|_, files| files.wdl.is_some());
+
+        let max_pieces = tables
+            .values()
+            .filter_map(|files| Material::from_name(&files.name))
+            .map(|material| material.piece_count as u32)
+            .max()
+            .ok_or("No tablebase files were found")?;
+
+        Ok(Self { tables, max_pieces })
+    }
+
+    pub fn get_max_pieces(&self) -> u32 {
+        self.max_pieces
+    }
+
+    // The tables don't know about castling, so positions where it is still possible can't be probed.
+    fn can_probe(&self, board: &Board) -> bool {
+        let castling = [&board.moving_player, &board.moved_player]
+            .iter()
+            .any(|state| state.can_castle_ks || state.can_castle_qs);
+
+        !castling
+            && (board.moving_player.pieces | board.moved_player.pieces).count_ones()
+                <= self.max_pieces
+    }
+
+    fn probe_table(&self, board: &Board, is_dtz: bool, wdl: Wdl) -> Option<TableValue> {
+        if (board.moving_player.pieces | board.moved_player.pieces).count_ones() == 2 {
+            return Some(TableValue::Value(0));
+        }
+
+        let white = endgame::get_material_key(board.get_player_state(Player::White));
+        let black = endgame::get_material_key(board.get_player_state(Player::Black));
+
+        let (files, black_stronger) = match self.tables.get(&get_table_key(white, black)) {
+            Some(files) => (files, false),
+            None => (self.tables.get(&get_table_key(black, white))?, true),
+        };
+
+        let table = if is_dtz { &files.dtz } else { &files.wdl };
+
+        Some(
+            table
+                .as_ref()?
+                .get(&files.name, is_dtz)?
+                .probe(board, black_stronger, wdl),
+        )
+    }
+
+    // The tables don't keep the right value of positions where a capture is best, or which can be captured en passant, so captures are searched first.
+    // With "zeroing_moves", pawn moves are searched too, since DTZ tables don't keep the positions where one of them wins.
+    // Returns the result, and whether a zeroing move is the best move.
+    fn search(&self, board: &Board, zeroing_moves: bool) -> Option<(Wdl, bool)> {
+        let moves = MoveGen::run(*board);
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+
+        for &chess_move in &moves {
+            let searches = if zeroing_moves {
+                board.is_zeroing(chess_move)
+            } else {
+                board.is_capture(chess_move)
+            };
+
+            if !searches {
+                continue;
+            }
+
+            searched += 1;
+
+            let mut board_copy = *board;
+            board_copy.make_move(chess_move);
+
+            let wdl = -self.search(&board_copy, false)?.0;
+
+            if wdl > best {
+                best = wdl;
+
+                if wdl == Wdl::Win {
+                    return Some((wdl, true));
+                }
+            }
+        }
+
+        // When every move was searched, the stored value may be wrong (like with en passant), and isn't needed.
+        let all_searched = searched > 0 && searched == moves.len();
+        let wdl = if all_searched {
+            best
+        } else {
+            match self.probe_table(board, false, Wdl::Draw)? {
+                TableValue::Value(value) => Wdl::from_value(value),
+                TableValue::OtherSide => return None,
+            }
+        };
+
+        if best >= wdl {
+            Some((best, best > Wdl::Draw || all_searched))
+        } else {
+            Some((wdl, false))
+        }
+    }
+
+    // Returns the result of the position for the player to move, if it can be probed.
+    pub fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        if !self.can_probe(board) {
+            return None;
+        }
+
+        self.search(board, false).map(|(wdl, _)| wdl)
+    }
+
+    // Returns how many plies are left until a capture or pawn move, positive when the player to move wins and negative when they lose.
+    // It is 100 more for cursed wins and blessed losses, and 0 for draws.
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.can_probe(board) {
+            return None;
+        }
+
+        let (wdl, zeroing_is_best) = self.search(board, true)?;
+
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+
+        if zeroing_is_best {
+            return Some(wdl.get_dtz_before_zeroing());
+        }
+
+        let sign = wdl.get_value().signum();
+
+        match self.probe_table(board, true, wdl)? {
+            TableValue::Value(dtz) => {
+                let cursed = matches!(wdl, Wdl::BlessedLoss | Wdl::CursedWin);
+
+                Some((dtz + 100 * cursed as i32) * sign)
+            }
+            // The table only has the positions after the moves, so the best of them is found instead.
+            TableValue::OtherSide => {
+                let mut best_dtz = None;
+
+                for chess_move in MoveGen::run(*board) {
+                    let mut board_copy = *board;
+                    board_copy.make_move(chess_move);
+
+                    let dtz = self.get_dtz_after(board, chess_move, &board_copy)?;
+
+                    if dtz.signum() == sign && best_dtz.is_none_or(|best| dtz < best) {
+                        best_dtz = Some(dtz);
+                    }
+                }
+
+                // Without legal moves, the player to move is checkmated.
+                Some(best_dtz.unwrap_or(-1))
+            }
+        }
+    }
+
+    // The distance to zeroing of a position through one of its moves.
+    fn get_dtz_after(&self, board: &Board, chess_move: Move, board_copy: &Board) -> Option<i32> {
+        // After a zeroing move, the distance only depends on the result.
+        if board.is_zeroing(chess_move) {
+            return Some((-self.search(board_copy, false)?.0).get_dtz_before_zeroing());
+        }
+
+        let dtz = -self.probe_dtz(board_copy)?;
+
+        if dtz == 1 && board_copy.is_in_check() && MoveGen::run(*board_copy).is_empty() {
+            return Some(1);
+        }
+
+        Some(dtz + dtz.signum())
+    }
+
+    // Returns the root moves which keep the best result, if the position can be probed.
+    // When the position is decided, only the moves which zero the soonest (or, when losing, the latest) are kept, so a win always makes progress.
+    // Without DTZ tables, all the moves keeping the best result are kept.
+    pub fn filter_root_moves(&self, board: &Board, moves: &[Move]) -> Option<Vec<Move>> {
+        if !self.can_probe(board) || moves.is_empty() {
+            return None;
+        }
+
+        let mut results = Vec::with_capacity(moves.len());
+
+        for &chess_move in moves {
+            let mut board_copy = *board;
+            board_copy.make_move(chess_move);
+
+            let wdl = -self.search(&board_copy, false)?.0;
+
+            results.push((
+                chess_move,
+                wdl,
+                self.get_dtz_after(board, chess_move, &board_copy),
+            ));
+        }
+
+        let best_wdl = results.iter().map(|&(_, wdl, _)| wdl).max()?;
+
+        results.retain(|&(_, wdl, _)| wdl == best_wdl);
+
+        let distances = results
+            .iter()
+            .map(|&(_, _, dtz)| dtz)
+            .collect::<Option<Vec<_>>>();
+
+        if let Some(distances) = distances.filter(|_| best_wdl != Wdl::Draw) {
+            let best_dtz = if best_wdl > Wdl::Draw {
+                distances.iter().filter(|&&dtz| dtz > 0).min()
+            } else {
+                distances.iter().min()
+            }
+            .copied();
+
+            if let Some(best_dtz) = best_dtz {
+                results.retain(|&(_, _, dtz)| dtz == Some(best_dtz));
+            }
+        }
+
+        Some(
+            results
+                .into_iter()
+                .map(|(chess_move, _, _)| chess_move)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, env, fs, path::PathBuf, process, str::FromStr, sync::Arc};
+
+    use crate::{
+        engine::{
+            endgame::get_material_key,
+            kpk,
+            search::{Searcher, MATE, MATE_BOUND, TABLEBASE_BOUND, TABLEBASE_WIN},
+            tt::TranspositionTable,
+        },
+        game::board::Board,
+        generators::{Move, MoveGen, Square},
+        tables::KING_MOVES,
+        BitBoard, Player,
+    };
+
+    use super::{
+        Material, Table, Tablebases, Wdl, DTZ_MAGIC, ENCODING, SINGLE_VALUE, SPLIT, WDL_MAGIC,
+    };
+
+    // The tests decode the real KQvK, KRvK and KPvK tables in "assets/syzygy", and build small KQvK tables in the same format to check the parts on their own.
+    const REAL_TABLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/syzygy");
+    const KQK_SIZE: usize = 31332;
+    const BLOCK_VALUES: usize = 4096;
+
+    struct Side {
+        sizes: Vec<u8>,
+        sparse_index: Vec<u8>,
+        block_lengths: Vec<u8>,
+        blocks: Vec<u8>,
+    }
+
+    fn get_single_value_side(value: u8) -> Side {
+        Side {
+            sizes: vec![SINGLE_VALUE, value],
+            sparse_index: Vec::new(),
+            block_lengths: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    // Every value takes a single bit, which is the first symbol when it is unset and the second when it is set.
+    fn get_bit_side(values: &[bool], symbols: [u8; 2]) -> Side {
+        let block_count = KQK_SIZE.div_ceil(BLOCK_VALUES);
+        let mut sizes = vec![0, 9, 12, 0];
+        let mut sparse_index = Vec::new();
+        let mut block_lengths = Vec::new();
+        let mut blocks = vec![0; block_count * BLOCK_VALUES / 8];
+
+        sizes.extend((block_count as u32).to_le_bytes());
+        sizes.extend([1, 1, 0, 0, 2, 0]);
+
+        for symbol in symbols {
+            sizes.extend([symbol, 0xF0, 0xFF]);
+        }
+
+        for block in 0..block_count {
+            let length = (KQK_SIZE - block * BLOCK_VALUES).min(BLOCK_VALUES);
+
+            sparse_index.extend((block as u32).to_le_bytes());
+            sparse_index.extend((BLOCK_VALUES as u16 / 2).to_le_bytes());
+            block_lengths.extend((length as u16 - 1).to_le_bytes());
+        }
+
+        for (index, &value) in values.iter().enumerate() {
+            if value {
+                blocks[index / 8] |= 0x80 >> (index % 8);
+            }
+        }
+
+        Side {
+            sizes,
+            sparse_index,
+            block_lengths,
+            blocks,
+        }
+    }
+
+    // The pieces are the white king, the white queen and the black king, in this order for both sides.
+    fn build_table(magic: [u8; 4], sides: &[Side]) -> Vec<u8> {
+        let mut data = magic.to_vec();
+
+        data.extend([SPLIT, 0, 0x66, 0x55, 0xEE, 0]);
+
+        for side in sides {
+            data.extend(&side.sizes);
+        }
+
+        for side in sides {
+            data.extend(&side.sparse_index);
+        }
+
+        for side in sides {
+            data.extend(&side.block_lengths);
+        }
+
+        for side in sides {
+            data.resize(data.len().next_multiple_of(64), 0);
+            data.extend(&side.blocks);
+        }
+
+        data
+    }
+
+    fn create_directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("fisher-{}-{}", name, process::id()));
+
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    fn get_board(pieces: &[(u32, char)], side: char) -> Board {
+        let mut rows = [['1'; 8]; 8];
+
+        for &(square, piece) in pieces {
+            rows[7 - square as usize / 8][square as usize % 8] = piece;
+        }
+
+        let rows = rows.map(|row| row.iter().collect::<String>());
+
+        Board::from_str(&format!("{} {} - - 0 1", rows.join("/"), side)).unwrap()
+    }
+
+    // Every KQvK position with black to move is lost, unless black can take the queen or is stalemated.
+    fn get_truth(board: &Board) -> Wdl {
+        let moves = MoveGen::run(*board);
+
+        if moves.is_empty() {
+            if board.is_in_check() {
+                Wdl::Loss
+            } else {
+                Wdl::Draw
+            }
+        } else if moves.iter().any(|&chess_move| board.is_capture(chess_move)) {
+            Wdl::Draw
+        } else {
+            Wdl::Loss
+        }
+    }
+
+    // The positions with black to move and their results, for some squares of the white king (including the ones which need the board to be mirrored).
+    fn get_kqk_positions() -> Vec<([u32; 3], Board, Wdl)> {
+        let mut positions = Vec::new();
+
+        for white_king in [0, 1, 2, 3, 9, 10, 11, 18, 19, 27, 14, 36, 63] {
+            for queen in (0..64).filter(|&queen| queen != white_king) {
+                for black_king in 0..64 {
+                    if black_king == queen
+                        || (KING_MOVES[white_king as usize] | BitBoard(1 << white_king))
+                            & BitBoard(1 << black_king)
+                            != BitBoard(0)
+                    {
+                        continue;
+                    }
+
+                    let board =
+                        get_board(&[(white_king, 'K'), (queen, 'Q'), (black_king, 'k')], 'b');
+                    let truth = get_truth(&board);
+
+                    positions.push(([white_king, queen, black_king], board, truth));
+                }
+            }
+        }
+
+        positions
+    }
+
+    #[test]
+    fn encoding_tables() {
+        let encoding = &*ENCODING;
+        let triangle = [1, 2, 3, 10, 11, 19, 0, 9, 18, 27];
+
+        for (code, square) in triangle.into_iter().enumerate() {
+            assert_eq!(encoding.map_a1d1d4[square], code);
+        }
+
+        let mut king_codes = Vec::new();
+
+        for first in triangle {
+            for second in 0..64 {
+                let adjacent = (KING_MOVES[first] | BitBoard(1 << first)) & BitBoard(1 << second)
+                    != BitBoard(0);
+                let above_diagonal = first / 8 == first % 8 && second / 8 > second % 8;
+
+                if !adjacent && !above_diagonal {
+                    king_codes.push(encoding.map_kk[encoding.map_a1d1d4[first]][second]);
+                }
+            }
+        }
+
+        king_codes.sort_unstable();
+        king_codes.dedup();
+        assert_eq!(king_codes, (0..462).collect::<Vec<_>>());
+
+        let mut pawn_codes = (8..56)
+            .map(|square| encoding.map_pawns[square])
+            .collect::<Vec<_>>();
+
+        pawn_codes.sort_unstable();
+        assert_eq!(pawn_codes, (0..48).collect::<Vec<_>>());
+        assert_eq!(encoding.binomial[2][5], 10);
+        assert_eq!(encoding.binomial[5][63], 7028847);
+        assert_eq!(encoding.lead_pawn_sizes[1], [6; 4]);
+    }
+
+    #[test]
+    fn decompresses_tables() {
+        let positions = get_kqk_positions();
+        let empty = build_table(
+            WDL_MAGIC,
+            &[get_bit_side(&[], [2, 4]), get_bit_side(&[], [2, 0])],
+        );
+        let table = Table::new(empty, "KQvK", false).unwrap();
+        let mut results = HashMap::new();
+        let mut losses = vec![false; KQK_SIZE];
+
+        for (_, board, truth) in &positions {
+            let (side, file, index) = table.get_index(board, false).unwrap();
+
+            assert_eq!((side, file), (1, 0));
+            assert!((index as usize) < KQK_SIZE);
+
+            // The tables don't need to know the positions where the queen can be taken.
+            if MoveGen::run(*board)
+                .iter()
+                .any(|&chess_move| board.is_capture(chess_move))
+            {
+                continue;
+            }
+
+            // The positions which are mirrors of each other have the same index.
+            assert_eq!(*results.entry(index).or_insert(*truth), *truth);
+            losses[index as usize] = *truth == Wdl::Loss;
+        }
+
+        let directory = create_directory("syzygy-kqk");
+        let data = build_table(
+            WDL_MAGIC,
+            &[get_single_value_side(4), get_bit_side(&losses, [2, 0])],
+        );
+
+        fs::write(directory.join("KQvK.rtbw"), data).unwrap();
+
+        let tablebases = Tablebases::new(directory.to_str().unwrap()).unwrap();
+
+        assert_eq!(tablebases.get_max_pieces(), 3);
+
+        for ([white_king, queen, black_king], board, truth) in &positions {
+            assert_eq!(tablebases.probe_wdl(board), Some(*truth));
+
+            // The same position with the colors swapped is found in the same table.
+            let swapped = get_board(
+                &[
+                    (black_king ^ 56, 'K'),
+                    (queen ^ 56, 'q'),
+                    (white_king ^ 56, 'k'),
+                ],
+                'w',
+            );
+
+            assert_eq!(tablebases.probe_wdl(&swapped), Some(*truth));
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    fn create_single_value_tables(name: &str) -> PathBuf {
+        let directory = create_directory(name);
+
+        // White to move always wins, in 3 moves to zeroing.
+        fs::write(
+            directory.join("KQvK.rtbw"),
+            build_table(
+                WDL_MAGIC,
+                &[get_single_value_side(4), get_single_value_side(0)],
+            ),
+        )
+        .unwrap();
+        fs::write(
+            directory.join("KQvK.rtbz"),
+            build_table(DTZ_MAGIC, &[get_single_value_side(3)]),
+        )
+        .unwrap();
+
+        directory
+    }
+
+    #[test]
+    fn distance_to_zeroing() {
+        let directory = create_single_value_tables("syzygy-dtz");
+        let tablebases = Tablebases::new(directory.to_str().unwrap()).unwrap();
+        let dtz = |fen: &str| tablebases.probe_dtz(&Board::from_str(fen).unwrap());
+
+        assert_eq!(dtz("8/8/8/3k4/8/8/8/KQ6 w - - 0 1"), Some(7));
+        // The table only has white to move, so the moves of black are probed instead.
+        assert_eq!(dtz("8/8/8/3k4/8/8/8/KQ6 b - - 0 1"), Some(-8));
+        // Taking the queen draws.
+        assert_eq!(dtz("8/8/8/3k4/3Q4/8/8/K7 b - - 0 1"), Some(0));
+        // Castling isn't in the tables.
+        assert_eq!(dtz("4k3/8/8/8/8/8/8/3QK2R w K - 0 1"), None);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn filters_root_moves() {
+        let directory = create_single_value_tables("syzygy-root");
+        let tablebases = Tablebases::new(directory.to_str().unwrap()).unwrap();
+        let filter = |fen: &str| {
+            let board = Board::from_str(fen).unwrap();
+
+            (
+                board,
+                tablebases
+                    .filter_root_moves(&board, &MoveGen::run(board))
+                    .unwrap(),
+            )
+        };
+
+        // The moves which give the queen away don't keep the win.
+        let (_, moves) = filter("8/8/8/3k4/8/8/8/KQ6 w - - 0 1");
+
+        assert!(moves.contains(&Move::from_str("qb1b2").unwrap()));
+        assert!(!moves.contains(&Move::from_str("qb1e4").unwrap()));
+
+        // Checkmating zeroes the soonest.
+        let (board, moves) = filter("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1");
+
+        assert!(moves.contains(&Move::from_str("qg1g8").unwrap()));
+
+        for chess_move in moves {
+            let mut board = board;
+            board.make_move(chess_move);
+
+            assert!(board.is_in_check() && MoveGen::run(board).is_empty());
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn search_uses_tablebases() {
+        let directory = create_single_value_tables("syzygy-search");
+        let tablebases = Arc::new(Tablebases::new(directory.to_str().unwrap()).unwrap());
+        let table = TranspositionTable::new(1);
+        let mut searcher = Searcher::new(&table);
+
+        searcher.set_tablebases(Some(tablebases));
+
+        // Capturing the rook reaches the tables, which are probed once the search is deep enough.
+        let board = Board::from_str("8/8/8/3k4/8/8/1r6/KQ6 w - - 0 1").unwrap();
+        let (best_move, score) = searcher.search(&board, 5);
+
+        assert!(score > TABLEBASE_WIN - 10 && score < MATE_BOUND);
+        assert!(best_move.is_some_and(|chess_move| board.is_capture(chess_move)));
+
+        // Without a capture or pawn move on the way there, the positions aren't probed.
+        let board = Board::from_str("8/8/8/3k4/8/8/8/KQ6 w - - 0 1").unwrap();
+
+        assert!(searcher.search(&board, 5).1 < TABLEBASE_BOUND);
+
+        // Mates are still found, and preferred over tablebase wins.
+        let board = Board::from_str("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1").unwrap();
+
+        assert_eq!(searcher.search(&board, 2).1, MATE - 1);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    // Every legal position with a white king, a black king and one more piece, with the side to move.
+    fn get_three_piece_positions(piece: char) -> Vec<([u32; 3], Board)> {
+        let mut positions = Vec::new();
+
+        for white_king in 0..64 {
+            for extra in (0..64).filter(|&extra| extra != white_king) {
+                if piece == 'P' && !(8..56).contains(&extra) {
+                    continue;
+                }
+
+                for black_king in (0..64).filter(|&black_king| black_king != extra) {
+                    if (KING_MOVES[white_king as usize] | BitBoard(1 << white_king))
+                        & BitBoard(1 << black_king)
+                        != BitBoard(0)
+                    {
+                        continue;
+                    }
+
+                    let pieces = [(white_king, 'K'), (extra, piece), (black_king, 'k')];
+
+                    // Black can't be in check with white to move.
+                    let black_to_move = get_board(&pieces, 'b');
+
+                    if !black_to_move.is_in_check() {
+                        positions.push(([white_king, extra, black_king], get_board(&pieces, 'w')));
+                    }
+
+                    positions.push(([white_king, extra, black_king], black_to_move));
+                }
+            }
+        }
+
+        positions
+    }
+
+    // The same position with the colors swapped, which is found in the same table.
+    fn swap_colors(board: &Board, [white_king, extra, black_king]: [u32; 3], piece: char) -> Board {
+        let side = match board.current_player {
+            Player::White => 'b',
+            Player::Black => 'w',
+        };
+
+        get_board(
+            &[
+                (black_king ^ 56, 'K'),
+                (extra ^ 56, piece.to_ascii_lowercase()),
+                (white_king ^ 56, 'k'),
+            ],
+            side,
+        )
+    }
+
+    #[test]
+    fn real_kpk_results() {
+        let tablebases = Tablebases::new(REAL_TABLES).unwrap();
+
+        for (squares @ [white_king, pawn, black_king], board) in get_three_piece_positions('P') {
+            let player = board.current_player;
+            let wins = kpk::probe(Square(white_king), Square(pawn), Square(black_king), player);
+            let truth = match (wins, player) {
+                (false, _) => Wdl::Draw,
+                (true, Player::White) => Wdl::Win,
+                (true, Player::Black) => Wdl::Loss,
+            };
+
+            assert_eq!(tablebases.probe_wdl(&board), Some(truth), "{}", board);
+            assert_eq!(
+                tablebases.probe_wdl(&swap_colors(&board, squares, 'P')),
+                Some(truth)
+            );
+        }
+    }
+
+    #[test]
+    fn real_kqk_and_krk_results() {
+        let tablebases = Tablebases::new(REAL_TABLES).unwrap();
+
+        for piece in ['Q', 'R'] {
+            for (squares, board) in get_three_piece_positions(piece) {
+                // White always wins, and black only escapes by taking the piece or being stalemated.
+                let truth = match board.current_player {
+                    Player::White => Wdl::Win,
+                    Player::Black => get_truth(&board),
+                };
+
+                assert_eq!(tablebases.probe_wdl(&board), Some(truth), "{}", board);
+                assert_eq!(
+                    tablebases.probe_wdl(&swap_colors(&board, squares, piece)),
+                    Some(truth)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn real_distances_to_zeroing() {
+        let tablebases = Tablebases::new(REAL_TABLES).unwrap();
+        let dtz = |fen: &str| tablebases.probe_dtz(&Board::from_str(fen).unwrap());
+
+        assert_eq!(tablebases.get_max_pieces(), 3);
+        assert_eq!(dtz("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1"), Some(1));
+        assert_eq!(dtz("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1"), Some(21));
+        assert_eq!(dtz("8/5p2/6k1/K7/8/8/8/8 w - - 0 1"), Some(-2));
+        assert_eq!(dtz("8/8/8/2K5/5kp1/8/8/8 b - - 0 1"), Some(1));
+        assert_eq!(dtz("8/3k4/8/8/8/8/4P3/3K4 w - - 0 1"), Some(0));
+
+        // Every winning move leads to a position which is lost one ply sooner, at best.
+        for fen in [
+            "8/8/8/2R5/1K6/8/5k2/8 w - - 0 1",
+            "8/8/8/4k3/8/8/8/1Q2K3 w - - 0 1",
+            "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1",
+        ] {
+            let board = Board::from_str(fen).unwrap();
+            let best = MoveGen::run(board)
+                .into_iter()
+                .filter(|&chess_move| !board.is_zeroing(chess_move))
+                .filter_map(|chess_move| {
+                    let mut board_copy = board;
+                    board_copy.make_move(chess_move);
+
+                    tablebases.probe_dtz(&board_copy)
+                })
+                .filter(|&dtz| dtz < 0)
+                .max();
+
+            assert!(dtz(fen).is_some_and(|dtz| dtz > 0));
+            assert_eq!(dtz(fen), best.map(|dtz| 1 - dtz));
+        }
+    }
+
+    #[test]
+    fn material_keys() {
+        let board = Board::from_str("8/8/3k4/8/2n5/8/3PR3/4K3 w - - 0 1").unwrap();
+        let material = Material::from_name("KRPvKN").unwrap();
+
+        assert_eq!(
+            material.keys,
+            [
+                get_material_key(&board.moving_player),
+                get_material_key(&board.moved_player)
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_tables() {
+        assert!(Tablebases::new("/nonexistent/fisher-tablebases").is_err());
+
+        let directory = create_directory("syzygy-invalid");
+
+        // A directory without tables is rejected.
+        assert!(Tablebases::new(directory.to_str().unwrap()).is_err());
+
+        // Tables are only read when probed, and broken ones are treated like missing ones.
+        fs::write(directory.join("KQvK.rtbw"), [0; 64]).unwrap();
+        fs::write(directory.join("notes.txt"), "KQvK").unwrap();
+
+        let tablebases = Tablebases::new(directory.to_str().unwrap()).unwrap();
+        let board = Board::from_str("8/8/8/3k4/8/8/8/KQ6 w - - 0 1").unwrap();
+
+        assert_eq!(tablebases.probe_wdl(&board), None);
+        assert_eq!(tablebases.probe_dtz(&board), None);
+
+        // Materials without a table can't be probed.
+        let board = Board::from_str("8/8/8/3k4/8/8/8/KR6 w - - 0 1").unwrap();
+
+        assert_eq!(tablebases.probe_wdl(&board), None);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
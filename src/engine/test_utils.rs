@@ -0,0 +1,70 @@
+// Helpers shared by the tests of the evaluation terms.
+
+// Flips the position vertically and swaps the colors of the pieces, so the evaluation should give the same scores with the players swapped.
+pub fn mirror_fen(fen: &str) -> String {
+    let fields = fen.split_whitespace().collect::<Vec<_>>();
+    let swap_case = |text: &str| {
+        text.chars()
+            .map(|char| {
+                if char.is_ascii_uppercase() {
+                    char.to_ascii_lowercase()
+                } else {
+                    char.to_ascii_uppercase()
+                }
+            })
+            .collect::<String>()
+    };
+
+    let placement = fields[0]
+        .split('/')
+        .rev()
+        .map(swap_case)
+        .collect::<Vec<_>>()
+        .join("/");
+    let player = if fields[1] == "w" { "b" } else { "w" };
+    // The castling rights are written with white's first, so they are reordered after swapping.
+    let castling = match fields[2] {
+        "-" => "-".to_string(),
+        rights => {
+            let swapped = swap_case(rights);
+
+            "KQkq"
+                .chars()
+                .filter(|&char| swapped.contains(char))
+                .collect()
+        }
+    };
+    let en_passant = match fields[3] {
+        "-" => "-".to_string(),
+        square => format!(
+            "{}{}",
+            &square[..1],
+            if &square[1..] == "3" { 6 } else { 3 }
+        ),
+    };
+
+    format!(
+        "{} {} {} {} {}",
+        placement,
+        player,
+        castling,
+        en_passant,
+        fields[4..].join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mirror_fen;
+
+    #[test]
+    fn mirrored_fens() {
+        let fen = "rnbqkb1r/pp3ppp/4pn2/2pp4/3P4/4PN2/PPPN1PPP/R1BQKB1R w Kq c6 0 5";
+
+        assert_eq!(
+            mirror_fen(fen),
+            "r1bqkb1r/pppn1ppp/4pn2/3p4/2PP4/4PN2/PP3PPP/RNBQKB1R b Qk c3 0 5"
+        );
+        assert_eq!(mirror_fen(&mirror_fen(fen)), fen);
+    }
+}
@@ -0,0 +1,297 @@
+// See: https://www.chessprogramming.org/Lazy_SMP
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use crate::game::board::Board;
+
+#[cfg(feature = "nnue")]
+use super::nnue::Network;
+use super::{
+    history::SearchContext,
+    params::EvalParams,
+    search::{Line, SearchConfig, SearchInfo, Searcher},
+    syzygy::Tablebases,
+    time::TimeManager,
+    tt::TranspositionTable,
+};
+
+pub const MAX_THREADS: usize = 256;
+pub const MAX_MULTI_PV: usize = 256;
+
+// Every thread searches the same position, and they only cooperate through the shared transposition table.
+// The main thread manages the time and reports the results, while the helpers search until it is done.
+// With a single thread, the search is deterministic.
+pub struct SearchThreads {
+    // Each thread keeps its own move ordering tables between searches.
+    contexts: Vec<SearchContext>,
+    config: SearchConfig,
+    params: EvalParams,
+    game_hashes: Vec<u64>,
+    tablebases: Option<Arc<Tablebases>>,
+    #[cfg(feature = "nnue")]
+    network: Option<Arc<Network>>,
+    // How many lines the main thread searches. The helpers only search the best one.
+    multi_pv: usize,
+}
+
+impl Default for SearchThreads {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl SearchThreads {
+    pub fn new(count: usize) -> Self {
+        Self {
+            contexts: (0..count.clamp(1, MAX_THREADS))
+                .map(|_| SearchContext::new())
+                .collect(),
+            config: SearchConfig::default(),
+            params: EvalParams::default(),
+            game_hashes: Vec::new(),
+            tablebases: None,
+            #[cfg(feature = "nnue")]
+            network: None,
+            multi_pv: 1,
+        }
+    }
+
+    pub fn get_count(&self) -> usize {
+        self.contexts.len()
+    }
+
+    pub fn resize(&mut self, count: usize) {
+        self.contexts
+            .resize_with(count.clamp(1, MAX_THREADS), SearchContext::new);
+    }
+
+    pub fn set_config(&mut self, config: SearchConfig) {
+        self.config = config;
+    }
+
+    pub fn get_params(&self) -> &EvalParams {
+        &self.params
+    }
+
+    pub fn set_params(&mut self, params: EvalParams) {
+        self.params = params;
+    }
+
+    // The hashes of the positions played in the game before the one searched, so the threads can avoid (or aim for) repetitions.
+    pub fn set_game_hashes(&mut self, game_hashes: Vec<u64>) {
+        self.game_hashes = game_hashes;
+    }
+
+    pub fn get_tablebases(&self) -> Option<&Arc<Tablebases>> {
+        self.tablebases.as_ref()
+    }
+
+    pub fn set_tablebases(&mut self, tablebases: Option<Arc<Tablebases>>) {
+        self.tablebases = tablebases;
+    }
+
+    #[cfg(feature = "nnue")]
+    pub fn get_network(&self) -> Option<&Arc<Network>> {
+        self.network.as_ref()
+    }
+
+    // The network evaluates instead of the weights, when there is one.
+    #[cfg(feature = "nnue")]
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.network = network;
+    }
+
+    pub fn get_multi_pv(&self) -> usize {
+        self.multi_pv
+    }
+
+    pub fn set_multi_pv(&mut self, line_count: usize) {
+        self.multi_pv = line_count.clamp(1, MAX_MULTI_PV);
+    }
+
+    // Forgets everything the threads learned, like when a new game starts.
+    pub fn clear(&mut self) {
+        for context in &mut self.contexts {
+            context.clear();
+        }
+    }
+
+    // Searches the position with all threads, until the limits of the time manager are reached or the stop signal is set.
+    // The iterations of the main thread are reported through "on_iteration", and its lines are the ones returned, best first.
+    pub fn search(
+        &mut self,
+        tt: &TranspositionTable,
+        board: &Board,
+        time: TimeManager,
+        stop_signal: Arc<AtomicBool>,
+        on_iteration: impl FnMut(&SearchInfo),
+    ) -> Vec<Line> {
+        // The helpers aren't given any limits, so they are stopped by the main thread once it is done.
+        let helpers_stop_signal = Arc::new(AtomicBool::new(false));
+        let helper_nodes = Arc::new(AtomicU64::new(0));
+        let config = self.config;
+        let params = self.params;
+        let multi_pv = self.multi_pv;
+        let (main_context, helper_contexts) = self
+            .contexts
+            .split_first_mut()
+            .expect("There is always at least one thread");
+
+        thread::scope(|scope| {
+            let helpers = helper_contexts
+                .iter_mut()
+                .enumerate()
+                .map(|(index, context)| {
+                    let helpers_stop_signal = Arc::clone(&helpers_stop_signal);
+                    let helper_nodes = Arc::clone(&helper_nodes);
+                    let game_hashes = self.game_hashes.clone();
+                    let tablebases = self.tablebases.clone();
+                    #[cfg(feature = "nnue")]
+                    let network = self.network.clone();
+
+                    scope.spawn(move || {
+                        let mut searcher = Searcher::new(tt);
+
+                        searcher.set_config(config);
+                        searcher.set_params(params);
+                        searcher.set_game_hashes(game_hashes);
+                        searcher.set_tablebases(tablebases);
+                        #[cfg(feature = "nnue")]
+                        searcher.set_network(network);
+                        searcher.set_context(mem::take(context));
+                        searcher.set_stop_signal(helpers_stop_signal);
+                        searcher.set_thread(index + 1, helper_nodes);
+                        searcher.iterative_deepening(board, |_| {});
+
+                        *context = searcher.into_context();
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut searcher = Searcher::new(tt);
+
+            searcher.set_config(config);
+            searcher.set_params(params);
+            searcher.set_game_hashes(self.game_hashes.clone());
+            searcher.set_tablebases(self.tablebases.clone());
+            #[cfg(feature = "nnue")]
+            searcher.set_network(self.network.clone());
+            searcher.set_context(mem::take(main_context));
+            searcher.set_time_manager(time);
+            searcher.set_stop_signal(stop_signal);
+            searcher.set_thread(0, helper_nodes);
+
+            let lines = searcher.multi_pv(board, multi_pv, on_iteration);
+
+            *main_context = searcher.into_context();
+            helpers_stop_signal.store(true, Ordering::Relaxed);
+
+            for helper in helpers {
+                helper.join().expect("Helper search thread panicked");
+            }
+
+            lines
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        str::FromStr,
+        sync::{atomic::AtomicBool, Arc},
+    };
+
+    use crate::{
+        engine::{
+            search::{SearchInfo, MATE},
+            time::{FakeClock, SearchLimits, TimeManager},
+            tt::TranspositionTable,
+        },
+        game::board::Board,
+    };
+
+    use super::SearchThreads;
+
+    fn run(threads: &mut SearchThreads, fen: &str, depth: u32) -> Vec<SearchInfo> {
+        let table = TranspositionTable::new(1);
+        let board = Board::from_str(fen).unwrap();
+        let limits = SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        };
+        let time = TimeManager::new(&limits, board.current_player, Arc::new(FakeClock::new()));
+        let mut iterations = Vec::new();
+
+        threads.search(
+            &table,
+            &board,
+            time,
+            Arc::new(AtomicBool::new(false)),
+            |info| iterations.push(info.clone()),
+        );
+
+        iterations
+    }
+
+    #[test]
+    fn single_thread_is_deterministic() {
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+
+        let first = run(&mut SearchThreads::new(1), fen, 5);
+        let second = run(&mut SearchThreads::new(1), fen, 5);
+
+        assert_eq!(first.len(), 5);
+
+        for (first, second) in first.iter().zip(&second) {
+            assert_eq!(first.nodes, second.nodes);
+            assert_eq!(first.score, second.score);
+            assert_eq!(first.pv, second.pv);
+        }
+    }
+
+    #[test]
+    fn helpers_search_along() {
+        let mut threads = SearchThreads::new(3);
+        let iterations = run(&mut threads, "6k1/5ppp/8/8/8/8/5PPP/2R1R1K1 w - - 0 1", 4);
+
+        assert_eq!(iterations.last().unwrap().score, MATE - 1);
+
+        threads.resize(1);
+
+        assert_eq!(threads.get_count(), 1);
+
+        threads.resize(0);
+
+        assert_eq!(threads.get_count(), 1);
+    }
+
+    #[test]
+    fn main_thread_searches_lines() {
+        let mut threads = SearchThreads::new(2);
+
+        threads.set_multi_pv(3);
+
+        let iterations = run(&mut threads, "6k1/5ppp/8/8/8/8/5PPP/2R1R1K1 w - - 0 1", 3);
+
+        assert_eq!(iterations.len(), 3 * 3);
+        assert_eq!(iterations[6].score, MATE - 1);
+        assert_eq!(
+            iterations
+                .iter()
+                .map(|info| info.multi_pv)
+                .collect::<Vec<_>>(),
+            [1, 2, 3].repeat(3)
+        );
+
+        threads.set_multi_pv(0);
+
+        assert_eq!(threads.get_multi_pv(), 1);
+    }
+}
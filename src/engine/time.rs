@@ -0,0 +1,412 @@
+// See: https://www.chessprogramming.org/Time_Management
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{generators::Move, Player};
+
+// Time kept aside for the communication with the GUI, so the engine doesn't lose on time because of it.
+pub const MOVE_OVERHEAD: Duration = Duration::from_millis(10);
+// When the GUI doesn't say how many moves are left until the next time control, this many are assumed.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+// The hard limit lets an unstable search go on for this many times the soft limit.
+const HARD_LIMIT_FACTOR: u32 = 4;
+
+// The time manager reads the time through this, so that it can be tested without waiting.
+pub trait Clock: Send + Sync {
+    fn elapsed(&self) -> Duration;
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// A clock which only moves when told to.
+#[derive(Default)]
+pub struct FakeClock {
+    elapsed_micros: AtomicU64,
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for FakeClock {
+    fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.elapsed_micros.load(Ordering::Relaxed))
+    }
+}
+
+// These mirror the parameters of the UCI "go" command. Without any of them, the search only ends when stopped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchLimits {
+    pub white_time: Option<Duration>,
+    pub black_time: Option<Duration>,
+    pub white_increment: Duration,
+    pub black_increment: Duration,
+    pub moves_to_go: Option<u32>,
+    pub move_time: Option<Duration>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    // Look for a mate in this many moves. It isn't a limit of the regular search.
+    pub mate: Option<u32>,
+    pub infinite: bool,
+}
+
+pub struct TimeManager {
+    clock: Arc<dyn Clock>,
+    // Once the soft limit passes, no new iteration is started.
+    soft_limit: Option<Duration>,
+    // Once the hard limit passes, the search is stopped right away.
+    hard_limit: Option<Duration>,
+    max_depth: Option<u32>,
+    max_nodes: Option<u64>,
+    // While set, the search is pondering on the time of the opponent, so the time limits don't apply yet.
+    // See: https://www.chessprogramming.org/Pondering
+    ponder_signal: Option<Arc<AtomicBool>>,
+    previous_best_move: Option<Move>,
+    previous_score: Option<i32>,
+    // A decaying count of how often the best move changed in the recent iterations.
+    best_move_changes: f64,
+    // How much the soft limit is currently stretched, because of an unstable best move or a dropping score.
+    scale: f64,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        Self::new(
+            &SearchLimits::default(),
+            Player::White,
+            Arc::new(SystemClock::new()),
+        )
+    }
+}
+
+impl TimeManager {
+    // The clock should start at the moment the "go" command was received.
+    pub fn new(limits: &SearchLimits, player: Player, clock: Arc<dyn Clock>) -> Self {
+        let (soft_limit, hard_limit) = Self::allocate(limits, player);
+
+        Self {
+            clock,
+            soft_limit,
+            hard_limit,
+            max_depth: limits.depth,
+            max_nodes: limits.nodes,
+            ponder_signal: None,
+            previous_best_move: None,
+            previous_score: None,
+            best_move_changes: 0.0,
+            scale: 1.0,
+        }
+    }
+
+    // Converts the limits into the soft and hard time budgets of the search.
+    fn allocate(limits: &SearchLimits, player: Player) -> (Option<Duration>, Option<Duration>) {
+        if limits.infinite {
+            return (None, None);
+        }
+
+        if let Some(move_time) = limits.move_time {
+            let budget = move_time.saturating_sub(MOVE_OVERHEAD);
+
+            return (Some(budget), Some(budget));
+        }
+
+        let (time_left, increment) = match player {
+            Player::White => (limits.white_time, limits.white_increment),
+            Player::Black => (limits.black_time, limits.black_increment),
+        };
+
+        let Some(time_left) = time_left else {
+            return (None, None);
+        };
+
+        let available = time_left.saturating_sub(MOVE_OVERHEAD);
+        let moves_to_go = limits.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+
+        let soft_limit = (available / moves_to_go + increment * 3 / 4).min(available);
+        let hard_limit = (soft_limit * HARD_LIMIT_FACTOR).min(available);
+
+        (Some(soft_limit), Some(hard_limit))
+    }
+
+    // The time limits start applying once the signal is cleared, which is when the opponent played the expected move.
+    // The time spent pondering still counts, so a long ponder lets the search end soon after.
+    pub fn set_ponder_signal(&mut self, ponder_signal: Arc<AtomicBool>) {
+        self.ponder_signal = Some(ponder_signal);
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.ponder_signal
+            .as_ref()
+            .is_some_and(|ponder_signal| ponder_signal.load(Ordering::Relaxed))
+    }
+
+    pub fn get_elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    pub fn get_max_depth(&self) -> Option<u32> {
+        self.max_depth
+    }
+
+    pub fn get_soft_limit(&self) -> Option<Duration> {
+        self.soft_limit
+    }
+
+    pub fn get_hard_limit(&self) -> Option<Duration> {
+        self.hard_limit
+    }
+
+    pub fn is_node_limit_reached(&self, nodes: u64) -> bool {
+        self.max_nodes.is_some_and(|max_nodes| nodes >= max_nodes)
+    }
+
+    // Reading the clock is slow, so the search only checks this periodically.
+    pub fn is_hard_limit_reached(&self) -> bool {
+        !self.is_pondering()
+            && self
+                .hard_limit
+                .is_some_and(|hard_limit| self.clock.elapsed() >= hard_limit)
+    }
+
+    // Updates the stretch of the soft limit with the results of a completed iteration.
+    pub fn on_iteration(&mut self, best_move: Option<Move>, score: i32) {
+        self.best_move_changes /= 2.0;
+
+        if self.previous_best_move.is_some() && best_move != self.previous_best_move {
+            self.best_move_changes += 1.0;
+        }
+
+        // A score which drops suggests there is a problem the search has only started to see.
+        let score_drop = self
+            .previous_score
+            .map_or(0, |previous_score| (previous_score - score).clamp(0, 100));
+
+        self.scale = (1.0 + self.best_move_changes * 0.5) * (1.0 + score_drop as f64 / 200.0);
+        self.previous_best_move = best_move;
+        self.previous_score = Some(score);
+    }
+
+    // This is checked between iterations, since an iteration which starts after it is unlikely to finish.
+    pub fn should_start_iteration(&self) -> bool {
+        self.is_pondering()
+            || self.soft_limit.is_none_or(|soft_limit| {
+                let soft_limit = soft_limit.mul_f64(self.scale);
+
+                self.clock.elapsed()
+                    < self
+                        .hard_limit
+                        .map_or(soft_limit, |hard_limit| soft_limit.min(hard_limit))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        str::FromStr,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use crate::{generators::Move, Player};
+
+    use super::{FakeClock, SearchLimits, TimeManager, MOVE_OVERHEAD};
+
+    fn millis(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn move_time() {
+        let limits = SearchLimits {
+            move_time: Some(millis(1000)),
+            ..Default::default()
+        };
+        let manager = TimeManager::new(&limits, Player::Black, Arc::new(FakeClock::new()));
+
+        assert_eq!(manager.get_soft_limit(), Some(millis(1000) - MOVE_OVERHEAD));
+        assert_eq!(manager.get_hard_limit(), Some(millis(1000) - MOVE_OVERHEAD));
+    }
+
+    #[test]
+    fn clock_time() {
+        let limits = SearchLimits {
+            white_time: Some(millis(60_010)),
+            black_time: Some(millis(1_010)),
+            white_increment: millis(1000),
+            ..Default::default()
+        };
+
+        let white = TimeManager::new(&limits, Player::White, Arc::new(FakeClock::new()));
+
+        assert_eq!(white.get_soft_limit(), Some(millis(2000 + 750)));
+        assert_eq!(white.get_hard_limit(), Some(millis(11_000)));
+
+        // Black has no increment and little time, so the hard limit is bounded by the time left.
+        let black = TimeManager::new(&limits, Player::Black, Arc::new(FakeClock::new()));
+
+        assert!(black.get_soft_limit().unwrap() < millis(50));
+        assert!(black.get_hard_limit().unwrap() <= millis(1000));
+
+        // With one move to go, all the time can be used.
+        let last_move = TimeManager::new(
+            &SearchLimits {
+                moves_to_go: Some(1),
+                ..limits
+            },
+            Player::Black,
+            Arc::new(FakeClock::new()),
+        );
+
+        assert_eq!(last_move.get_soft_limit(), Some(millis(1000)));
+        assert_eq!(last_move.get_hard_limit(), Some(millis(1000)));
+    }
+
+    #[test]
+    fn no_time_limits() {
+        for limits in [
+            SearchLimits::default(),
+            SearchLimits {
+                depth: Some(5),
+                nodes: Some(1000),
+                ..Default::default()
+            },
+            SearchLimits {
+                white_time: Some(millis(1000)),
+                infinite: true,
+                ..Default::default()
+            },
+        ] {
+            let manager = TimeManager::new(&limits, Player::White, Arc::new(FakeClock::new()));
+
+            assert_eq!(manager.get_soft_limit(), None);
+            assert_eq!(manager.get_hard_limit(), None);
+        }
+    }
+
+    #[test]
+    fn stops_at_limits() {
+        let clock = Arc::new(FakeClock::new());
+        let limits = SearchLimits {
+            move_time: Some(millis(110)),
+            nodes: Some(5000),
+            ..Default::default()
+        };
+        let manager = TimeManager::new(&limits, Player::White, clock.clone());
+
+        assert!(!manager.is_node_limit_reached(4999));
+        assert!(manager.is_node_limit_reached(5000));
+
+        clock.advance(millis(99));
+        assert!(!manager.is_hard_limit_reached());
+        assert!(manager.should_start_iteration());
+
+        clock.advance(millis(1));
+        assert!(manager.is_hard_limit_reached());
+        assert!(!manager.should_start_iteration());
+    }
+
+    #[test]
+    fn pondering_suspends_limits() {
+        let clock = Arc::new(FakeClock::new());
+        let ponder_signal = Arc::new(AtomicBool::new(true));
+        let limits = SearchLimits {
+            move_time: Some(millis(110)),
+            ..Default::default()
+        };
+        let mut manager = TimeManager::new(&limits, Player::White, clock.clone());
+
+        manager.set_ponder_signal(ponder_signal.clone());
+        clock.advance(millis(1000));
+
+        assert!(manager.is_pondering());
+        assert!(!manager.is_hard_limit_reached());
+        assert!(manager.should_start_iteration());
+
+        // The opponent played the expected move, and the time spent pondering has already used up the budget.
+        ponder_signal.store(false, Ordering::Relaxed);
+
+        assert!(!manager.is_pondering());
+        assert!(manager.is_hard_limit_reached());
+        assert!(!manager.should_start_iteration());
+    }
+
+    #[test]
+    fn instability_extends_time() {
+        let first = Move::from_str("pe2e4").unwrap();
+        let second = Move::from_str("pd2d4").unwrap();
+        let limits = SearchLimits {
+            white_time: Some(millis(30_010)),
+            ..Default::default()
+        };
+
+        let stable_clock = Arc::new(FakeClock::new());
+        let mut stable = TimeManager::new(&limits, Player::White, stable_clock.clone());
+
+        let unstable_clock = Arc::new(FakeClock::new());
+        let mut unstable = TimeManager::new(&limits, Player::White, unstable_clock.clone());
+
+        let dropping_clock = Arc::new(FakeClock::new());
+        let mut dropping = TimeManager::new(&limits, Player::White, dropping_clock.clone());
+
+        for (iteration, score) in [(0, 50), (1, 40), (2, -50)] {
+            stable.on_iteration(Some(first), 50);
+            unstable.on_iteration(Some([first, second][iteration % 2]), 50);
+            dropping.on_iteration(Some(first), score);
+        }
+
+        // The soft limit is a second, which the stable search respects while the others go on.
+        for clock in [&stable_clock, &unstable_clock, &dropping_clock] {
+            clock.advance(millis(1200));
+        }
+
+        assert!(!stable.should_start_iteration());
+        assert!(unstable.should_start_iteration());
+        assert!(dropping.should_start_iteration());
+
+        // Still, the hard limit is never exceeded.
+        for clock in [&stable_clock, &unstable_clock, &dropping_clock] {
+            clock.advance(millis(10_000));
+        }
+
+        assert!(!unstable.should_start_iteration());
+        assert!(!dropping.should_start_iteration());
+    }
+}
@@ -0,0 +1,337 @@
+// See: https://www.chessprogramming.org/Transposition_Table
+use std::{
+    mem,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
+
+use crate::generators::Move;
+
+use super::search::TABLEBASE_BOUND;
+
+pub const DEFAULT_SIZE_MB: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    // The score is at least as good as the stored one (the search failed high).
+    Lower,
+    // The score is at most as good as the stored one (the search failed low).
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TableEntry {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+// The data of an entry is packed into a single word: 16 bits for the move, 16 for the score, 8 for the depth, 2 for the bound and 6 for the age.
+// The bound is never zero, so a zeroed slot is always empty.
+fn pack(entry: TableEntry, age: u8) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+
+    entry.best_move.map_or(0, Move::pack) as u64
+        | (entry.score as i16 as u16 as u64) << 16
+        | (entry.depth as u64) << 32
+        | bound << 40
+        | ((age & 0b111111) as u64) << 42
+}
+
+fn unpack(data: u64) -> (TableEntry, u8) {
+    (
+        TableEntry {
+            best_move: Move::unpack(data as u16),
+            score: (data >> 16) as u16 as i16 as i32,
+            depth: (data >> 32) as u8,
+            bound: match (data >> 40) & 0b11 {
+                1 => Bound::Exact,
+                2 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+        },
+        ((data >> 42) & 0b111111) as u8,
+    )
+}
+
+// Mate and tablebase scores are relative to the root, but a position can be reached at many plies.
+// So, they are stored relative to the position itself, and converted back when probed.
+pub fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= TABLEBASE_BOUND {
+        score + ply as i32
+    } else if score <= -TABLEBASE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+pub fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= TABLEBASE_BOUND {
+        score - ply as i32
+    } else if score <= -TABLEBASE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+// The key is stored XORed with the data, so an entry torn by two threads writing at the same time fails verification instead of being misread.
+// See: https://www.chessprogramming.org/Shared_Hash_Table#Lock-less
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    fn load(&self) -> (u64, u64) {
+        let data = self.data.load(Ordering::Relaxed);
+
+        (self.key.load(Ordering::Relaxed) ^ data, data)
+    }
+
+    fn store(&self, hash: u64, data: u64) {
+        self.key.store(hash ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.key.store(0, Ordering::Relaxed);
+        self.data.store(0, Ordering::Relaxed);
+    }
+}
+
+// The first slot of a bucket keeps the deepest (and thus most expensive) result, while the second slot is always replaced.
+#[derive(Default)]
+struct Bucket {
+    depth_preferred: Slot,
+    always_replace: Slot,
+}
+
+// The table is shared between every search thread, so all of its methods only need a shared reference.
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let bucket_count = (size_mb * 1024 * 1024 / mem::size_of::<Bucket>()).max(1);
+
+        Self {
+            buckets: (0..bucket_count).map(|_| Bucket::default()).collect(),
+            age: AtomicU8::new(0),
+        }
+    }
+
+    pub fn clear(&self) {
+        for bucket in &self.buckets {
+            bucket.depth_preferred.clear();
+            bucket.always_replace.clear();
+        }
+
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    // This should be called once before every search, so entries from older searches are replaced first.
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_age(&self) -> u8 {
+        self.age.load(Ordering::Relaxed) & 0b111111
+    }
+
+    fn get_bucket(&self, hash: u64) -> &Bucket {
+        // This maps the hash onto the buckets evenly, without requiring the amount of buckets to be a power of two.
+        &self.buckets[((hash as u128 * self.buckets.len() as u128) >> 64) as usize]
+    }
+
+    pub fn probe(&self, hash: u64, ply: usize) -> Option<TableEntry> {
+        let bucket = self.get_bucket(hash);
+
+        [&bucket.depth_preferred, &bucket.always_replace]
+            .into_iter()
+            .find_map(|slot| {
+                let (key, data) = slot.load();
+
+                (key == hash && data != 0).then(|| {
+                    let (mut entry, _) = unpack(data);
+                    entry.score = score_from_tt(entry.score, ply);
+
+                    entry
+                })
+            })
+    }
+
+    pub fn store(&self, hash: u64, mut entry: TableEntry, ply: usize) {
+        let bucket = self.get_bucket(hash);
+        let age = self.get_age();
+
+        // A search that failed low doesn't know the best move, so the one from a previous search of the position is kept.
+        if entry.best_move.is_none() {
+            entry.best_move = [&bucket.depth_preferred, &bucket.always_replace]
+                .into_iter()
+                .find_map(|slot| {
+                    let (key, data) = slot.load();
+
+                    (key == hash && data != 0).then(|| unpack(data).0.best_move)
+                })
+                .flatten();
+        }
+
+        let (key, data) = bucket.depth_preferred.load();
+        let (old_entry, old_age) = unpack(data);
+
+        entry.score = score_to_tt(entry.score, ply);
+
+        let data = pack(entry, age);
+
+        if key == hash || old_age != age || entry.depth >= old_entry.depth {
+            bucket.depth_preferred.store(hash, data);
+        } else {
+            bucket.always_replace.store(hash, data);
+        }
+    }
+
+    // Estimates how full the table is, in permille, by sampling the first buckets.
+    pub fn hashfull(&self) -> usize {
+        let sampled = &self.buckets[..self.buckets.len().min(500)];
+        let age = self.get_age();
+
+        let used = sampled
+            .iter()
+            .flat_map(|bucket| [&bucket.depth_preferred, &bucket.always_replace])
+            .filter(|slot| {
+                let (_, data) = slot.load();
+
+                data != 0 && unpack(data).1 == age
+            })
+            .count();
+
+        used * 1000 / (sampled.len() * 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        engine::search::{MATE, TABLEBASE_WIN},
+        generators::Move,
+    };
+
+    use super::{Bound, TableEntry, TranspositionTable};
+
+    fn entry(best_move: Option<Move>, score: i32, depth: u8) -> TableEntry {
+        TableEntry {
+            best_move,
+            score,
+            depth,
+            bound: Bound::Exact,
+        }
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let table = TranspositionTable::new(1);
+        let chess_move = Move::from_str("pe2e4").unwrap();
+
+        assert!(table.probe(1234, 0).is_none());
+
+        table.store(1234, entry(Some(chess_move), -57, 6), 0);
+
+        let probed = table.probe(1234, 0).unwrap();
+
+        assert_eq!(probed.best_move, Some(chess_move));
+        assert_eq!(probed.score, -57);
+        assert_eq!(probed.depth, 6);
+        assert_eq!(probed.bound, Bound::Exact);
+        assert!(table.probe(4321, 0).is_none());
+
+        table.clear();
+
+        assert!(table.probe(1234, 0).is_none());
+    }
+
+    #[test]
+    fn mate_scores_are_relative_to_position() {
+        let table = TranspositionTable::new(1);
+
+        // A mate in 3 plies found at ply 5 is a mate in 8 plies from the root.
+        table.store(99, entry(None, MATE - 8, 4), 5);
+
+        assert_eq!(table.probe(99, 5).unwrap().score, MATE - 8);
+        assert_eq!(table.probe(99, 1).unwrap().score, MATE - 4);
+    }
+
+    #[test]
+    fn tablebase_scores_are_relative_to_position() {
+        let table = TranspositionTable::new(1);
+
+        // A tablebase win found at ply 7 is closer when the position is reached at ply 3.
+        table.store(42, entry(None, TABLEBASE_WIN - 7, 4), 7);
+
+        assert_eq!(table.probe(42, 7).unwrap().score, TABLEBASE_WIN - 7);
+        assert_eq!(table.probe(42, 3).unwrap().score, TABLEBASE_WIN - 3);
+
+        table.store(42, entry(None, -TABLEBASE_WIN + 7, 4), 7);
+
+        assert_eq!(table.probe(42, 3).unwrap().score, -TABLEBASE_WIN + 3);
+    }
+
+    #[test]
+    fn replacement() {
+        let table = TranspositionTable::new(1);
+        let first = Move::from_str("pe2e4").unwrap();
+
+        // These hashes map to the same bucket, but are distinct.
+        table.store(0, entry(Some(first), 10, 8), 0);
+        table.store(1, entry(None, 20, 2), 0);
+
+        // The deeper entry stays, while the shallower one goes to the always-replace slot.
+        assert_eq!(table.probe(0, 0).unwrap().depth, 8);
+        assert_eq!(table.probe(1, 0).unwrap().depth, 2);
+
+        // Storing the same position without a move keeps the previous move.
+        table.store(0, entry(None, 15, 9), 0);
+        assert_eq!(table.probe(0, 0).unwrap().best_move, Some(first));
+
+        // The same goes for the position in the always-replace slot.
+        let second = Move::from_str("pd2d4").unwrap();
+
+        table.store(1, entry(Some(second), 20, 2), 0);
+        table.store(1, entry(None, 25, 3), 0);
+        assert_eq!(table.probe(1, 0).unwrap().best_move, Some(second));
+
+        // Once a new search starts, old entries are replaced regardless of depth.
+        table.new_search();
+        table.store(2, entry(None, 30, 1), 0);
+        assert!(table.probe(0, 0).is_none());
+        assert_eq!(table.probe(2, 0).unwrap().score, 30);
+    }
+
+    #[test]
+    fn hashfull() {
+        let table = TranspositionTable::new(1);
+
+        assert_eq!(table.hashfull(), 0);
+
+        for hash in 0..u16::MAX as u64 {
+            table.store(hash.wrapping_mul(0x9E3779B97F4A7C15), entry(None, 0, 1), 0);
+        }
+
+        assert!(table.hashfull() > 300);
+
+        table.new_search();
+
+        assert_eq!(table.hashfull(), 0);
+    }
+}
@@ -1,17 +1,20 @@
 use crate::{
     generators::{
         slides::{
-            get_down_attacks, get_down_left_attacks, get_down_right_attacks, get_left_attacks,
-            get_right_attacks, get_up_attacks, get_up_left_attacks, get_up_right_attacks,
+            get_cross_attacks, get_diagonal_attacks, get_down_attacks, get_down_left_attacks,
+            get_down_right_attacks, get_left_attacks, get_right_attacks, get_up_attacks,
+            get_up_left_attacks, get_up_right_attacks,
         },
         AttackGen, Move, Square,
     },
-    tables::KNIGHT_MOVES,
+    tables::{KING_MOVES, KNIGHT_MOVES, PAWN_ATTACKS},
     BitBoard, Piece, PieceKind, Pins, Player, BLACK_LEFT_ROOK_ORIGIN, BLACK_RIGHT_ROOK_ORIGIN,
     WHITE_LEFT_ROOK_ORIGIN, WHITE_RIGHT_ROOK_ORIGIN,
 };
 use std::{mem, str::FromStr};
 
+use super::zobrist;
+
 #[derive(Clone, Copy)]
 pub struct PlayerState {
     pub king: BitBoard,
@@ -68,6 +71,17 @@ impl PlayerState {
         self.check_mask.is_full()
     }
 
+    pub fn get_piece_bitboard(&self, piece_kind: PieceKind) -> BitBoard {
+        match piece_kind {
+            PieceKind::King => self.king,
+            PieceKind::Queen => self.queens,
+            PieceKind::Rook => self.rooks,
+            PieceKind::Bishop => self.bishops,
+            PieceKind::Knight => self.knights,
+            PieceKind::Pawn => self.pawns,
+        }
+    }
+
     fn get_mut_piece_bitboard(&mut self, piece_kind: PieceKind) -> &mut BitBoard {
         match piece_kind {
             PieceKind::King => &mut self.king,
@@ -114,6 +128,12 @@ pub struct EnPassant {
     pub pawn: BitBoard,
 }
 
+impl Default for EnPassant {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EnPassant {
     pub fn new() -> Self {
         Self {
@@ -130,6 +150,10 @@ pub struct Board {
     pub current_player: Player,
     pub ep_info: EnPassant,
     pub pieces: BoardPieces,
+    // This is the Zobrist hash of the position, which is kept up to date by "make_move".
+    pub hash: u64,
+    // The same, but only of the pawns. The pawn structure is evaluated once per key, since it rarely changes.
+    pub pawn_hash: u64,
 }
 
 impl Default for Board {
@@ -176,7 +200,8 @@ impl Board {
                         }
                     } else { // "does_contain_ep_pawn" needs to be false if we go here.
                         if is_single_blocker {
-                            $pin_mask = $ray;
+                            // The king can be pinned along the same line from both sides, and a piece can't slide past the king, so the rays are joined.
+                            $pin_mask |= $ray;
                         } else if is_empty_of_blockers {
                             if self.moving_player.isnt_in_check() {
                                 self.moving_player.check_mask = $ray;
@@ -289,6 +314,42 @@ impl Board {
         }
     }
 
+    pub fn get_player_state(&self, player: Player) -> &PlayerState {
+        if player == self.current_player {
+            &self.moving_player
+        } else {
+            &self.moved_player
+        }
+    }
+
+    // Returns all pieces of the player attacking the square, where only the pieces in "occupancy" are able to block sliding pieces.
+    // The occupancy doesn't have to match the board, which allows looking "through" pieces that are about to move.
+    pub fn player_attackers_to(
+        &self,
+        square: Square,
+        occupancy: BitBoard,
+        player: Player,
+    ) -> BitBoard {
+        let target = BitBoard::from(square);
+        let empty_squares = !occupancy;
+        let attacking_player = self.get_player_state(player);
+
+        // Pawns attack in reverse to the direction we look from the target square.
+        (PAWN_ATTACKS[!player][square] & attacking_player.pawns)
+            | (KNIGHT_MOVES[square] & attacking_player.knights)
+            | (KING_MOVES[square] & attacking_player.king)
+            | (get_diagonal_attacks(target, empty_squares)
+                & (attacking_player.bishops | attacking_player.queens))
+            | (get_cross_attacks(target, empty_squares)
+                & (attacking_player.rooks | attacking_player.queens))
+    }
+
+    // Same as above, but for the pieces of both players.
+    pub fn attackers_to(&self, square: Square, occupancy: BitBoard) -> BitBoard {
+        self.player_attackers_to(square, occupancy, Player::White)
+            | self.player_attackers_to(square, occupancy, Player::Black)
+    }
+
     // This function will update the pins and check mask of the current side.
     // It will also compute the attacks against the current side.
     // All of these are needed to ensure moves generated later are indeed legal.
@@ -323,6 +384,10 @@ impl Board {
                 WHITE_RIGHT_ROOK_ORIGIN,
             ),
         };
+        let current_player = self.current_player;
+
+        // The castling rights and en-passant file are removed from the hash now, and added back once the move is done.
+        self.hash ^= self.get_state_hash();
 
         match chess_move {
             Move::EnPassant { origin } => {
@@ -341,6 +406,10 @@ impl Board {
                 self.pieces.move_piece(origin, move_to);
                 self.pieces.remove_piece(captured_square);
 
+                self.toggle_piece_hash(current_player, PieceKind::Pawn, origin);
+                self.toggle_piece_hash(current_player, PieceKind::Pawn, move_to);
+                self.toggle_piece_hash(!current_player, PieceKind::Pawn, captured_square);
+
                 self.ep_info = EnPassant::new(); // The en-passant square must be reset (or set again) after each move, since it has a single move timeframe.
             }
             Move::Regular {
@@ -361,7 +430,10 @@ impl Board {
                 } else if moving_rro == origin {
                     // We must have moved a rook!
                     self.moving_player.can_castle_ks = false;
-                } else if moved_lro == target {
+                }
+
+                // This is checked separately, since a piece leaving a rook's origin can capture the other rook (like a rook on a8 taking one on a1).
+                if moved_lro == target {
                     // We must have captured a rook!
                     self.moved_player.can_castle_qs = false;
                 } else if moved_rro == target {
@@ -370,9 +442,12 @@ impl Board {
                 }
 
                 self.moving_player.move_piece(piece_kind, origin, target);
+                self.toggle_piece_hash(current_player, piece_kind, origin);
+                self.toggle_piece_hash(current_player, piece_kind, target);
 
                 if let &Some(Piece { piece_kind, .. }) = self.pieces.get_piece(target) {
-                    self.moved_player.remove_piece(piece_kind, target)
+                    self.moved_player.remove_piece(piece_kind, target);
+                    self.toggle_piece_hash(!current_player, piece_kind, target);
                 }
 
                 self.pieces.move_piece(origin, target); // We must keep the board pieces in-sync with the actual board representation.
@@ -407,9 +482,12 @@ impl Board {
 
                 self.moving_player.remove_piece(PieceKind::Pawn, origin);
                 self.moving_player.place_piece(promotion_to, target);
+                self.toggle_piece_hash(current_player, PieceKind::Pawn, origin);
+                self.toggle_piece_hash(current_player, promotion_to, target);
 
                 if let &Some(Piece { piece_kind, .. }) = self.pieces.get_piece(target) {
-                    self.moved_player.remove_piece(piece_kind, target)
+                    self.moved_player.remove_piece(piece_kind, target);
+                    self.toggle_piece_hash(!current_player, piece_kind, target);
                 }
 
                 // We must keep the board pieces in-sync with the actual board representation.
@@ -431,10 +509,14 @@ impl Board {
                         self.moving_player
                             .move_piece(PieceKind::King, Square::E1, king_to);
                         self.pieces.move_piece(Square::E1, king_to);
+                        self.toggle_piece_hash(current_player, PieceKind::King, Square::E1);
+                        self.toggle_piece_hash(current_player, PieceKind::King, king_to);
 
                         self.moving_player
                             .move_piece(PieceKind::Rook, Square::H1, rook_to);
                         self.pieces.move_piece(Square::H1, rook_to);
+                        self.toggle_piece_hash(current_player, PieceKind::Rook, Square::H1);
+                        self.toggle_piece_hash(current_player, PieceKind::Rook, rook_to);
                     }
                     Player::Black => {
                         let king_to = Square::G8;
@@ -443,10 +525,14 @@ impl Board {
                         self.moving_player
                             .move_piece(PieceKind::King, Square::E8, king_to);
                         self.pieces.move_piece(Square::E8, king_to);
+                        self.toggle_piece_hash(current_player, PieceKind::King, Square::E8);
+                        self.toggle_piece_hash(current_player, PieceKind::King, king_to);
 
                         self.moving_player
                             .move_piece(PieceKind::Rook, Square::H8, rook_to);
                         self.pieces.move_piece(Square::H8, rook_to);
+                        self.toggle_piece_hash(current_player, PieceKind::Rook, Square::H8);
+                        self.toggle_piece_hash(current_player, PieceKind::Rook, rook_to);
                     }
                 }
 
@@ -465,10 +551,14 @@ impl Board {
                         self.moving_player
                             .move_piece(PieceKind::King, Square::E1, king_to);
                         self.pieces.move_piece(Square::E1, king_to);
+                        self.toggle_piece_hash(current_player, PieceKind::King, Square::E1);
+                        self.toggle_piece_hash(current_player, PieceKind::King, king_to);
 
                         self.moving_player
                             .move_piece(PieceKind::Rook, Square::A1, rook_to);
                         self.pieces.move_piece(Square::A1, rook_to);
+                        self.toggle_piece_hash(current_player, PieceKind::Rook, Square::A1);
+                        self.toggle_piece_hash(current_player, PieceKind::Rook, rook_to);
                     }
                     Player::Black => {
                         let king_to = Square::C8;
@@ -477,10 +567,14 @@ impl Board {
                         self.moving_player
                             .move_piece(PieceKind::King, Square::E8, king_to);
                         self.pieces.move_piece(Square::E8, king_to);
+                        self.toggle_piece_hash(current_player, PieceKind::King, Square::E8);
+                        self.toggle_piece_hash(current_player, PieceKind::King, king_to);
 
                         self.moving_player
                             .move_piece(PieceKind::Rook, Square::A8, rook_to);
                         self.pieces.move_piece(Square::A8, rook_to);
+                        self.toggle_piece_hash(current_player, PieceKind::Rook, Square::A8);
+                        self.toggle_piece_hash(current_player, PieceKind::Rook, rook_to);
                     }
                 }
                 // Once a player castles, he loses the right to do so again, on either side.
@@ -496,5 +590,163 @@ impl Board {
         // which could have changed during this move, but now that is no longer the case, as that data is useless there.
         self.switch_sides();
         self.update_move_constraints();
+
+        // This is done after updating the move constraints, since they can invalidate the en-passant square.
+        self.hash ^= self.get_state_hash() ^ zobrist::get_side_key();
+    }
+
+    // Passes the turn without moving, which the search uses to check if a position is good enough to not need a move.
+    // This mustn't be done while in check, since the position would then be illegal.
+    pub fn make_null_move(&mut self) {
+        self.hash ^= self.get_state_hash();
+
+        self.ep_info = EnPassant::new();
+        self.switch_sides();
+        self.update_move_constraints();
+
+        self.hash ^= self.get_state_hash() ^ zobrist::get_side_key();
+    }
+
+    // Returns the kind of piece the move captures, if it captures anything.
+    pub fn get_captured_piece(&self, chess_move: Move) -> Option<PieceKind> {
+        match chess_move {
+            Move::Regular { target, .. } | Move::Promotion { target, .. } => self
+                .pieces
+                .get_piece(target)
+                .map(|Piece { piece_kind, .. }| piece_kind),
+            Move::EnPassant { .. } => Some(PieceKind::Pawn),
+            Move::CastleKS | Move::CastleQS => None,
+        }
+    }
+
+    pub fn is_capture(&self, chess_move: Move) -> bool {
+        self.get_captured_piece(chess_move).is_some()
+    }
+
+    // Pawn moves and captures can't be undone, so they reset the fifty-move counter.
+    pub fn is_zeroing(&self, chess_move: Move) -> bool {
+        match chess_move {
+            Move::Regular { piece_kind, .. } => {
+                piece_kind == PieceKind::Pawn || self.is_capture(chess_move)
+            }
+            Move::EnPassant { .. } | Move::Promotion { .. } => true,
+            Move::CastleKS | Move::CastleQS => false,
+        }
+    }
+
+    pub fn is_in_check(&self) -> bool {
+        !self.moving_player.isnt_in_check() || self.moving_player.king_must_move
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        generators::{Move, MoveGen, Square},
+        BitBoard, Player,
+    };
+
+    use super::Board;
+
+    fn squares(squares: &[Square]) -> BitBoard {
+        squares.iter().fold(BitBoard::empty(), |bitboard, &square| {
+            bitboard | BitBoard::from(square)
+        })
+    }
+
+    #[test]
+    fn attackers_to_both_players() {
+        let board = Board::from_str("4k3/8/2n5/3p4/4P3/5B2/8/4R1K1 w - - 0 1").unwrap();
+        let occupancy = board.moving_player.pieces | board.moved_player.pieces;
+
+        assert!(
+            board.attackers_to(Square::E4, occupancy)
+                == squares(&[Square::D5, Square::F3, Square::E1])
+        );
+        assert!(board.attackers_to(Square::D5, occupancy) == squares(&[Square::E4]));
+        assert!(
+            board.player_attackers_to(Square::E4, occupancy, Player::Black)
+                == squares(&[Square::D5])
+        );
+        assert!(
+            board.player_attackers_to(Square::B4, occupancy, Player::Black)
+                == squares(&[Square::C6])
+        );
+    }
+
+    #[test]
+    fn attackers_to_custom_occupancy() {
+        let board = Board::from_str("k7/8/8/8/8/8/4R3/4R1K1 w - - 0 1").unwrap();
+        let occupancy = board.moving_player.pieces | board.moved_player.pieces;
+
+        // The rook on e1 is only revealed once the rook on e2 is lifted.
+        assert!(board.attackers_to(Square::E5, occupancy) == squares(&[Square::E2]));
+        assert!(
+            board.attackers_to(Square::E5, occupancy - BitBoard::from(Square::E2))
+                == squares(&[Square::E1, Square::E2])
+        );
+    }
+
+    #[test]
+    fn null_move() {
+        let mut board =
+            Board::from_str("rnbqkbnr/ppp1pppp/8/8/3pP3/5N2/PPPP1PPP/RNBQKB1R b KQkq e3 0 3")
+                .unwrap();
+        let passed =
+            Board::from_str("rnbqkbnr/ppp1pppp/8/8/3pP3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3")
+                .unwrap();
+
+        board.make_null_move();
+
+        // The en-passant capture is no longer possible after passing.
+        assert_eq!(board.hash, passed.hash);
+        assert_eq!(board.hash, board.compute_hash());
+        assert_eq!(MoveGen::run(board), MoveGen::run(passed));
+
+        board.make_null_move();
+
+        assert!(board.current_player == Player::Black);
+        assert_eq!(board.hash, board.compute_hash());
+    }
+
+    #[test]
+    fn pins_from_both_sides() {
+        // The rook on c8 and the knight on f8 are both pinned along the eighth rank.
+        let board = Board::from_str("1Rrk1n1R/8/2p5/P2Br3/1p2p2P/5p1n/5P2/K7 b - - 0 1").unwrap();
+        let moves = MoveGen::run(board);
+
+        assert!(!moves.contains(&Move::from_str("nf8e6").unwrap()));
+        assert!(moves.contains(&Move::from_str("rc8b8").unwrap()));
+        assert!(!moves.contains(&Move::from_str("rc8c7").unwrap()));
+    }
+
+    #[test]
+    fn capturing_from_rook_origin() {
+        // The bishop leaves h8 while capturing the rook on a1, which must still revoke the castling of white.
+        let mut board = Board::from_str("4k2b/8/8/8/8/8/8/R3K3 b Q - 0 1").unwrap();
+
+        board.make_move(Move::from_str("bh8a1").unwrap());
+
+        assert!(!board.moved_player.can_castle_qs);
+        assert!(!MoveGen::run(board).contains(&Move::CastleQS));
+    }
+
+    #[test]
+    fn zeroing_moves() {
+        let board = Board::from_str("r3k3/8/8/3pP3/8/2n5/P7/R3K2R w KQq d6 0 1").unwrap();
+
+        for (text, is_zeroing) in [
+            ("pa2a4", true),
+            ("pe5d6", true),
+            ("ra1a8", true),
+            ("ke1d1", false),
+            ("rh1h7", false),
+        ] {
+            assert_eq!(board.is_zeroing(Move::from_str(text).unwrap()), is_zeroing);
+        }
+
+        assert!(!board.is_zeroing(Move::CastleKS));
     }
 }
@@ -0,0 +1,237 @@
+use crate::{
+    generators::{slides, Move, Square},
+    tables::{BETWEEN, KNIGHT_MOVES, LINE, PAWN_ATTACKS},
+    BitBoard, PieceKind, Player,
+};
+
+use super::board::Board;
+
+// This holds everything about the opponent's king that is needed to find out whether a move gives check, without making the move.
+// It is the same for every move in a position, so it should be computed once and reused.
+#[derive(Clone, Copy)]
+pub struct CheckInfo {
+    pub king: Square,
+    pub pawn_checks: BitBoard,
+    pub knight_checks: BitBoard,
+    pub bishop_checks: BitBoard,
+    pub rook_checks: BitBoard,
+    // Pieces of the moving player which are the only blocker between one of their sliders and the opponent's king.
+    // Moving one of them off the line of the slider is a discovered check.
+    pub discoverers: BitBoard,
+}
+
+impl CheckInfo {
+    pub fn new(board: &Board) -> Self {
+        let king_bitboard = board.moved_player.king;
+        let king = king_bitboard.first_one_square();
+        let occupancy = board.moving_player.pieces | board.moved_player.pieces;
+        let empty_squares = !occupancy;
+
+        let diagonal_sliders = board.moving_player.bishops | board.moving_player.queens;
+        let cross_sliders = board.moving_player.rooks | board.moving_player.queens;
+
+        // These are the sliders that would attack the king, if nothing stood in their way.
+        let mut snipers = (slides::get_diagonal_attacks(king_bitboard, BitBoard::full())
+            & diagonal_sliders)
+            | (slides::get_cross_attacks(king_bitboard, BitBoard::full()) & cross_sliders);
+
+        let mut discoverers = BitBoard::empty();
+
+        while snipers.isnt_empty() {
+            let blockers = BETWEEN[king][snipers.pop_first_one()] & occupancy;
+
+            if blockers.is_single_1() {
+                discoverers |= blockers & board.moving_player.pieces;
+            }
+        }
+
+        Self {
+            king,
+            // Pawns attack in reverse to the direction we look from the king.
+            pawn_checks: PAWN_ATTACKS[!board.current_player][king],
+            knight_checks: KNIGHT_MOVES[king],
+            bishop_checks: slides::get_diagonal_attacks(king_bitboard, empty_squares),
+            rook_checks: slides::get_cross_attacks(king_bitboard, empty_squares),
+            discoverers,
+        }
+    }
+
+    // Returns the squares a piece of the given kind would give check from. A king can never give check by itself.
+    pub fn get_check_squares(&self, piece_kind: PieceKind) -> BitBoard {
+        match piece_kind {
+            PieceKind::King => BitBoard::empty(),
+            PieceKind::Queen => self.bishop_checks | self.rook_checks,
+            PieceKind::Rook => self.rook_checks,
+            PieceKind::Bishop => self.bishop_checks,
+            PieceKind::Knight => self.knight_checks,
+            PieceKind::Pawn => self.pawn_checks,
+        }
+    }
+
+    // A discoverer only reveals its slider if it leaves the line between that slider and the king.
+    pub fn is_discovered_check(&self, origin: Square, target: Square) -> bool {
+        self.discoverers.get_bit(origin) && !LINE[origin][self.king].get_bit(target)
+    }
+}
+
+impl Board {
+    // Returns whether one of the moving player's sliders would attack the opponent's king, if the board had the given occupancy.
+    // The piece on "moved_to" is ignored, since it isn't the one that was there before the move.
+    fn do_sliders_see_king(&self, info: &CheckInfo, occupancy: BitBoard, moved_to: Square) -> bool {
+        let king = BitBoard::from(info.king);
+        let empty_squares = !occupancy;
+
+        let diagonal_sliders =
+            (self.moving_player.bishops | self.moving_player.queens) - BitBoard::from(moved_to);
+        let cross_sliders =
+            (self.moving_player.rooks | self.moving_player.queens) - BitBoard::from(moved_to);
+
+        (slides::get_diagonal_attacks(king, empty_squares) & diagonal_sliders).isnt_empty()
+            || (slides::get_cross_attacks(king, empty_squares) & cross_sliders).isnt_empty()
+    }
+
+    // This version of "gives_check" should be used when checking many moves, as it avoids recomputing the check information.
+    pub fn gives_check_with(&self, chess_move: Move, info: &CheckInfo) -> bool {
+        let occupancy = self.moving_player.pieces | self.moved_player.pieces;
+
+        match chess_move {
+            Move::Regular {
+                origin,
+                target,
+                piece_kind,
+                ..
+            } => {
+                info.get_check_squares(piece_kind).get_bit(target)
+                    || info.is_discovered_check(origin, target)
+            }
+            Move::Promotion {
+                origin,
+                target,
+                promotion_to,
+            } => {
+                let moved_occupancy = (occupancy - BitBoard::from(origin)) | BitBoard::from(target);
+                let promoted = BitBoard::from(target);
+                // The promoted piece can slide through the square the pawn has left.
+                let empty_squares = !moved_occupancy;
+
+                let direct = match promotion_to {
+                    PieceKind::Queen => {
+                        slides::get_diagonal_attacks(promoted, empty_squares)
+                            | slides::get_cross_attacks(promoted, empty_squares)
+                    }
+                    PieceKind::Rook => slides::get_cross_attacks(promoted, empty_squares),
+                    PieceKind::Bishop => slides::get_diagonal_attacks(promoted, empty_squares),
+                    PieceKind::Knight => KNIGHT_MOVES[target],
+                    PieceKind::King | PieceKind::Pawn => unreachable!(),
+                };
+
+                direct.get_bit(info.king) || info.is_discovered_check(origin, target)
+            }
+            Move::EnPassant { origin } => {
+                let target = self.ep_info.capture_point.first_one_square();
+
+                // Both the moving pawn and the captured pawn leave their squares, so either of them could discover a check.
+                let moved_occupancy = (occupancy - BitBoard::from(origin) - self.ep_info.pawn)
+                    | BitBoard::from(target);
+
+                info.pawn_checks.get_bit(target)
+                    || self.do_sliders_see_king(info, moved_occupancy, target)
+            }
+            Move::CastleKS | Move::CastleQS => {
+                let (king_origin, king_target, rook_origin, rook_target) =
+                    match (chess_move, self.current_player) {
+                        (Move::CastleKS, Player::White) => {
+                            (Square::E1, Square::G1, Square::H1, Square::F1)
+                        }
+                        (Move::CastleQS, Player::White) => {
+                            (Square::E1, Square::C1, Square::A1, Square::D1)
+                        }
+                        (Move::CastleKS, Player::Black) => {
+                            (Square::E8, Square::G8, Square::H8, Square::F8)
+                        }
+                        (Move::CastleQS, Player::Black) => {
+                            (Square::E8, Square::C8, Square::A8, Square::D8)
+                        }
+                        _ => unreachable!(),
+                    };
+
+                let moved_occupancy =
+                    (occupancy - BitBoard::from(king_origin) - BitBoard::from(rook_origin))
+                        | BitBoard::from(king_target)
+                        | BitBoard::from(rook_target);
+
+                // Only the rook can give check after castling.
+                slides::get_cross_attacks(BitBoard::from(rook_target), !moved_occupancy)
+                    .get_bit(info.king)
+            }
+        }
+    }
+
+    pub fn gives_check(&self, chess_move: Move) -> bool {
+        self.gives_check_with(chess_move, &CheckInfo::new(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        game::board::Board,
+        generators::{Move, MoveGen},
+    };
+
+    use super::CheckInfo;
+
+    // Compares "gives_check" against actually making every move, for every position up to the given depth.
+    fn verify(board: Board, depth: u32) {
+        let info = CheckInfo::new(&board);
+
+        for chess_move in MoveGen::run(board) {
+            let mut board_copy = board;
+            board_copy.make_move(chess_move);
+
+            assert_eq!(
+                board.gives_check_with(chess_move, &info),
+                board_copy.is_in_check(),
+                "{:?}",
+                chess_move
+            );
+
+            if depth > 1 {
+                verify(board_copy, depth - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_make_move() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        ] {
+            verify(Board::from_str(fen).unwrap(), 3);
+        }
+    }
+
+    #[test]
+    fn special_checks() {
+        // A promotion checking through the square the pawn has left.
+        let board = Board::from_str("8/3P4/8/8/8/8/8/3k2K1 w - - 0 1").unwrap();
+        assert!(board.gives_check(Move::from_str("d7d8=q").unwrap()));
+        assert!(!board.gives_check(Move::from_str("d7d8=b").unwrap()));
+
+        // A castling rook checking the king.
+        let board = Board::from_str("5k2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert!(board.gives_check(Move::CastleKS));
+
+        // An en-passant capture that discovers a check along the rank of the captured pawn.
+        let board = Board::from_str("8/8/8/K2pP2k/8/8/8/8 w - d6 0 1").unwrap();
+        assert!(!board.gives_check(Move::from_str("e5").unwrap()));
+        let board = Board::from_str("8/8/8/R2pP2k/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert!(board.gives_check(Move::from_str("e5").unwrap()));
+    }
+}
@@ -142,10 +142,10 @@ impl FromStr for Board {
             };
 
             // These values currently aren't needed anywhere.
-            if let Err(_) = parts[4].parse::<u32>() {
+            if parts[4].parse::<u32>().is_err() {
                 return Err("Input contains invalid number for half-moves");
             }
-            if let Err(_) = parts[5].parse::<u32>() {
+            if parts[5].parse::<u32>().is_err() {
                 return Err("Input contains invalid number for full-moves");
             }
 
@@ -202,11 +202,106 @@ impl FromStr for Board {
                     pawn: ep_pawn,
                 },
                 pieces: board_pieces,
+                hash: 0,
+                pawn_hash: 0,
             };
 
             board.update_move_constraints();
+            board.hash = board.compute_hash();
+            board.pawn_hash = board.compute_pawn_hash();
 
             Ok(board)
         }
     }
 }
+
+impl Board {
+    // The move counters aren't kept by the board, so they are always written as "0 1".
+    pub fn to_fen(&self) -> String {
+        let mut rows = Vec::new();
+
+        for rank in (0..8).rev() {
+            let mut row = String::new();
+            let mut empty_squares = 0;
+
+            for file in 0..8 {
+                match *self.pieces.get_piece(Square(rank * 8 + file)) {
+                    Some(Piece { piece_kind, player }) => {
+                        if empty_squares > 0 {
+                            row.push_str(&empty_squares.to_string());
+                            empty_squares = 0;
+                        }
+
+                        row.push(match player {
+                            Player::White => piece_kind.into_piece_char().to_ascii_uppercase(),
+                            Player::Black => piece_kind.into_piece_char(),
+                        });
+                    }
+                    None => empty_squares += 1,
+                }
+            }
+
+            if empty_squares > 0 {
+                row.push_str(&empty_squares.to_string());
+            }
+
+            rows.push(row);
+        }
+
+        let white = self.get_player_state(Player::White);
+        let black = self.get_player_state(Player::Black);
+        let castling = [
+            (white.can_castle_ks, 'K'),
+            (white.can_castle_qs, 'Q'),
+            (black.can_castle_ks, 'k'),
+            (black.can_castle_qs, 'q'),
+        ]
+        .into_iter()
+        .filter_map(|(can_castle, character)| can_castle.then_some(character))
+        .collect::<String>();
+
+        format!(
+            "{} {} {} {} 0 1",
+            rows.join("/"),
+            match self.current_player {
+                Player::White => 'w',
+                Player::Black => 'b',
+            },
+            if castling.is_empty() { "-" } else { &castling },
+            if self.ep_info.capture_point.isnt_empty() {
+                self.ep_info.capture_point.first_one_square().to_string()
+            } else {
+                "-".to_string()
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{game::board::Board, generators::Move};
+
+    #[test]
+    fn fen_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            "r3k3/8/8/8/8/8/8/4K2R b Kq - 0 1",
+        ] {
+            assert_eq!(Board::from_str(fen).unwrap().to_fen(), fen);
+        }
+
+        let mut board = Board::default();
+        board.make_move(Move::from_str("pe2e4").unwrap());
+
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+    }
+}
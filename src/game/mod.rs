@@ -1,3 +1,7 @@
 pub mod board; // The module defines basic structures to manage the game-state and board.
+pub mod check;
 pub mod fen;
+pub mod pgn;
+pub mod see;
 pub mod ui;
+pub mod zobrist;
@@ -0,0 +1,318 @@
+// Reading of games in Portable Game Notation, with their moves in Standard Algebraic Notation.
+// Comments, variations and annotations are skipped, since only the moves and the results are needed.
+// See: https://www.chessprogramming.org/Portable_Game_Notation
+use std::str::FromStr;
+
+use crate::{
+    generators::{Move, MoveGen, Square},
+    PieceKind, Player,
+};
+
+use super::board::Board;
+
+pub struct Game {
+    // The position the game starts from, which is only different from the standard one with a "FEN" tag.
+    pub board: Board,
+    pub moves: Vec<Move>,
+    // The result from the perspective of white: one for a win, a half for a draw and zero for a loss. Unfinished games have none.
+    pub result: Option<f64>,
+}
+
+fn parse_result(text: &str) -> Option<f64> {
+    match text {
+        "1-0" => Some(1.0),
+        "1/2-1/2" => Some(0.5),
+        "0-1" => Some(0.0),
+        _ => None,
+    }
+}
+
+// Returns the kind of the moved piece, and its origin and target squares.
+fn get_move_squares(board: &Board, chess_move: Move) -> (PieceKind, Square, Square) {
+    let back_row = match board.current_player {
+        Player::White => 0,
+        Player::Black => 56,
+    };
+
+    match chess_move {
+        Move::Regular {
+            origin,
+            target,
+            piece_kind,
+            ..
+        } => (piece_kind, origin, target),
+        Move::EnPassant { origin } => (
+            PieceKind::Pawn,
+            origin,
+            board.ep_info.capture_point.first_one_square(),
+        ),
+        Move::Promotion { origin, target, .. } => (PieceKind::Pawn, origin, target),
+        Move::CastleKS => (PieceKind::King, Square(back_row + 4), Square(back_row + 6)),
+        Move::CastleQS => (PieceKind::King, Square(back_row + 4), Square(back_row + 2)),
+    }
+}
+
+// Finds the legal move the text stands for, like "Nbd2", "exd5", "e8=Q+" or "O-O".
+pub fn parse_san(board: &Board, text: &str) -> Option<Move> {
+    let text = text.trim_end_matches(['+', '#', '!', '?']);
+    let moves = MoveGen::run(*board);
+
+    let castling = match text {
+        "O-O" | "0-0" => Some(Move::CastleKS),
+        "O-O-O" | "0-0-0" => Some(Move::CastleQS),
+        _ => None,
+    };
+
+    if let Some(castling) = castling {
+        return moves.contains(&castling).then_some(castling);
+    }
+
+    if !text.is_ascii() || text.len() < 2 {
+        return None;
+    }
+
+    // The promotion may also be written without the "=", like "e8Q".
+    let (text, promotion) = match text.split_once('=') {
+        Some((text, piece)) => (text, Some(PieceKind::from_str(&piece.to_lowercase()).ok()?)),
+        None if text.ends_with(['Q', 'R', 'B', 'N']) && !text[..1].contains(char::is_uppercase) => {
+            (
+                &text[..text.len() - 1],
+                Some(PieceKind::from_str(&text[text.len() - 1..].to_lowercase()).ok()?),
+            )
+        }
+        None => (text, None),
+    };
+
+    let (piece_kind, text) = if text.starts_with(['K', 'Q', 'R', 'B', 'N']) {
+        (
+            PieceKind::from_str(&text[..1].to_lowercase()).ok()?,
+            &text[1..],
+        )
+    } else {
+        (PieceKind::Pawn, text)
+    };
+
+    let text = text.replace(['x', '-', ':'], "");
+
+    if text.len() < 2 {
+        return None;
+    }
+
+    let target = Square::from_str(&text[text.len() - 2..]).ok()?;
+    // The origin is only given as much as needed to tell the moves apart, by its file, rank, or both.
+    let disambiguation = &text[..text.len() - 2];
+
+    let mut matching = moves.into_iter().filter(|&chess_move| {
+        let (kind, origin, move_target) = get_move_squares(board, chess_move);
+        let move_promotion = match chess_move {
+            Move::Promotion { promotion_to, .. } => Some(promotion_to),
+            _ => None,
+        };
+        let origin_text = origin.to_string();
+
+        kind == piece_kind
+            && move_target == target
+            && move_promotion == promotion
+            && !matches!(chess_move, Move::CastleKS | Move::CastleQS)
+            && disambiguation
+                .chars()
+                .all(|char| origin_text.contains(char))
+    });
+
+    let chess_move = matching.next()?;
+
+    matching.next().is_none().then_some(chess_move)
+}
+
+// Reads the moves of the movetext of a game, until its end or the first move which can't be read.
+fn read_game(fen: Option<&str>, result: Option<f64>, movetext: &str) -> Option<Game> {
+    let mut board = match fen {
+        Some(fen) => Board::from_str(fen).ok()?,
+        None => Board::default(),
+    };
+    let mut game = Game {
+        board,
+        moves: Vec::new(),
+        result,
+    };
+
+    // Comments are in braces, and variations in parentheses (which can be nested).
+    let mut in_comment = false;
+    let mut variation_depth = 0;
+    let mut main_line = String::new();
+
+    for char in movetext.chars() {
+        match char {
+            '{' if !in_comment => in_comment = true,
+            '}' if in_comment => in_comment = false,
+            '(' if !in_comment => variation_depth += 1,
+            ')' if !in_comment => variation_depth -= 1,
+            _ if !in_comment && variation_depth == 0 => main_line.push(char),
+            _ => {}
+        }
+    }
+
+    // After a move which can't be read, the rest of the moves are skipped but the result is still looked for.
+    let mut is_reading = true;
+
+    for token in main_line.split_whitespace() {
+        if let Some(result) = parse_result(token) {
+            game.result = game.result.or(Some(result));
+            break;
+        }
+
+        if token == "*" {
+            break;
+        }
+
+        // Move numbers may be attached to the moves, like in "1.e4" or "3...Nf6".
+        let token = token.rsplit('.').next().unwrap_or(token);
+
+        if !is_reading || token.is_empty() || token.starts_with('$') {
+            continue;
+        }
+
+        match parse_san(&board, token) {
+            Some(chess_move) => {
+                board.make_move(chess_move);
+                game.moves.push(chess_move);
+            }
+            None => is_reading = false,
+        }
+    }
+
+    Some(game)
+}
+
+// Reads every game of the text. A game is cut short at a move which can't be read, and skipped if its starting position can't be.
+pub fn parse_games(text: &str) -> Vec<Game> {
+    let mut games = Vec::new();
+    let mut fen = None;
+    let mut result = None;
+    let mut movetext = String::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        // Lines starting with "%" are escaped, and ";" comments out the rest of the line.
+        if line.starts_with('%') {
+            continue;
+        }
+
+        if let Some(tag) = line.strip_prefix('[').and_then(|tag| tag.strip_suffix(']')) {
+            // The tags of the next game start once the moves of the previous one were read.
+            if !movetext.trim().is_empty() {
+                games.extend(read_game(fen.as_deref(), result, &movetext));
+                fen = None;
+                result = None;
+                movetext.clear();
+            }
+
+            if let Some((name, value)) = tag.split_once(char::is_whitespace) {
+                let value = value.trim().trim_matches('"');
+
+                match name {
+                    "FEN" => fen = Some(value.to_string()),
+                    "Result" => result = parse_result(value),
+                    _ => {}
+                }
+            }
+
+            continue;
+        }
+
+        movetext.push_str(line.split(';').next().unwrap_or(line));
+        movetext.push(' ');
+    }
+
+    if !movetext.trim().is_empty() {
+        games.extend(read_game(fen.as_deref(), result, &movetext));
+    }
+
+    games
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::generators::Move;
+
+    use super::{parse_games, parse_san, Board};
+
+    fn san(fen: &str, text: &str) -> Option<Move> {
+        parse_san(&Board::from_str(fen).unwrap(), text)
+    }
+
+    #[test]
+    fn standard_algebraic_notation() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+        assert_eq!(san(start, "e4"), Some(Move::from_str("pe2e4").unwrap()));
+        assert_eq!(san(start, "Nf3"), Some(Move::from_str("ng1f3").unwrap()));
+        assert_eq!(san(start, "Nf3+!?"), Some(Move::from_str("ng1f3").unwrap()));
+        assert_eq!(san(start, "e5"), None);
+        assert_eq!(san(start, "Ke2"), None);
+
+        // Both knights can go to d2, so the origin must be told.
+        let knights = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+
+        assert_eq!(san(knights, "Nd2"), None);
+        assert_eq!(san(knights, "Nbd2"), Some(Move::from_str("nb1d2").unwrap()));
+        assert_eq!(san(knights, "Nfd2"), Some(Move::from_str("nf1d2").unwrap()));
+        assert_eq!(
+            san(knights, "Nf1d2"),
+            Some(Move::from_str("nf1d2").unwrap())
+        );
+
+        let pawns = "4k3/1P6/8/3p4/4P3/8/8/R3K2R w KQ - 0 1";
+
+        assert_eq!(san(pawns, "exd5"), Some(Move::from_str("pe4d5").unwrap()));
+        assert_eq!(san(pawns, "b8=Q"), Some(Move::from_str("b7b8=q").unwrap()));
+        assert_eq!(san(pawns, "b8N+"), Some(Move::from_str("b7b8=n").unwrap()));
+        assert_eq!(san(pawns, "b8"), None);
+        assert_eq!(san(pawns, "O-O"), Some(Move::CastleKS));
+        assert_eq!(san(pawns, "0-0-0"), Some(Move::CastleQS));
+
+        let en_passant = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+
+        assert!(matches!(
+            san(en_passant, "exd6"),
+            Some(Move::EnPassant { .. })
+        ));
+    }
+
+    #[test]
+    fn games() {
+        let text = r#"
+[Event "First"]
+[Result "1-0"]
+
+1. e4 {The best by test} e5 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3 $1 Nc6 3.Bb5 a6 ; The Ruy Lopez
+4. Ba4 1-0
+
+[Event "Second"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+1. e4 Kd7 2. e5 Zz9 3. e6 1/2-1/2
+
+[Event "Unfinished"]
+
+1. d4 d5 *
+"#;
+        let games = parse_games(text);
+
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].moves.len(), 7);
+        assert_eq!(games[0].result, Some(1.0));
+        assert_eq!(games[0].moves[6], Move::from_str("bb5a4").unwrap());
+
+        // The game is cut short at the move which can't be read, but keeps its result.
+        assert_eq!(games[1].board.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        assert_eq!(games[1].moves.len(), 3);
+        assert_eq!(games[1].result, Some(0.5));
+
+        assert_eq!(games[2].moves.len(), 2);
+        assert_eq!(games[2].result, None);
+    }
+}
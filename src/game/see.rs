@@ -0,0 +1,293 @@
+// See: https://www.chessprogramming.org/Static_Exchange_Evaluation
+use crate::{
+    generators::{slides, Move, Square},
+    BitBoard, Piece, PieceKind,
+};
+
+use super::board::{Board, PlayerState};
+
+impl PieceKind {
+    // The king's value is high enough that no exchange can ever justify giving it up.
+    pub fn see_value(self) -> i32 {
+        match self {
+            PieceKind::King => 20000,
+            PieceKind::Queen => 900,
+            PieceKind::Rook => 500,
+            PieceKind::Bishop => 300,
+            PieceKind::Knight => 300,
+            PieceKind::Pawn => 100,
+        }
+    }
+}
+
+// This holds the state of the capture sequence on a single square, as pieces are removed from the board one by one.
+struct Exchange<'brd> {
+    board: &'brd Board,
+    target: Square,
+    occupancy: BitBoard,
+    attackers: BitBoard,
+}
+
+impl<'brd> Exchange<'brd> {
+    // Returns the exchange after the initial move, the value of the piece it captured and the value of the piece now standing on the target.
+    // Castling can never capture anything, so it has no exchange.
+    fn new(board: &'brd Board, chess_move: Move) -> Option<(Self, i32, i32)> {
+        let mut occupancy = board.moving_player.pieces | board.moved_player.pieces;
+
+        let (origin, target, captured_value, moved_value) = match chess_move {
+            Move::Regular {
+                origin,
+                target,
+                piece_kind,
+                ..
+            } => (
+                origin,
+                target,
+                board.get_captured_value(target),
+                piece_kind.see_value(),
+            ),
+            Move::Promotion {
+                origin,
+                target,
+                promotion_to,
+            } => (
+                origin,
+                target,
+                // The promoting side also gains the difference between the new piece and the pawn.
+                board.get_captured_value(target) + promotion_to.see_value()
+                    - PieceKind::Pawn.see_value(),
+                promotion_to.see_value(),
+            ),
+            Move::EnPassant { origin } => {
+                // The captured pawn isn't on the target square, so it must be removed separately.
+                occupancy &= !board.ep_info.pawn;
+
+                (
+                    origin,
+                    board.ep_info.capture_point.first_one_square(),
+                    PieceKind::Pawn.see_value(),
+                    PieceKind::Pawn.see_value(),
+                )
+            }
+            Move::CastleKS | Move::CastleQS => return None,
+        };
+
+        occupancy.turn_off(origin);
+
+        let exchange = Self {
+            board,
+            target,
+            occupancy,
+            attackers: board.attackers_to(target, occupancy) & occupancy,
+        };
+        Some((exchange, captured_value, moved_value))
+    }
+
+    fn get_diagonal_attackers(&self) -> BitBoard {
+        slides::get_diagonal_attacks(BitBoard::from(self.target), !self.occupancy)
+            & (self.board.moving_player.bishops
+                | self.board.moving_player.queens
+                | self.board.moved_player.bishops
+                | self.board.moved_player.queens)
+    }
+
+    fn get_cross_attackers(&self) -> BitBoard {
+        slides::get_cross_attacks(BitBoard::from(self.target), !self.occupancy)
+            & (self.board.moving_player.rooks
+                | self.board.moving_player.queens
+                | self.board.moved_player.rooks
+                | self.board.moved_player.queens)
+    }
+
+    // Removes the least valuable attacker of the given player from the board and returns its kind.
+    // Removing a piece can reveal a slider behind it (an X-ray attacker), so those are added back to the attackers.
+    fn pop_least_valuable(&mut self, player: &PlayerState) -> Option<PieceKind> {
+        let player_attackers = self.attackers & player.pieces;
+
+        let (piece_kind, pieces) = [
+            (PieceKind::Pawn, player.pawns),
+            (PieceKind::Knight, player.knights),
+            (PieceKind::Bishop, player.bishops),
+            (PieceKind::Rook, player.rooks),
+            (PieceKind::Queen, player.queens),
+            (PieceKind::King, player.king),
+        ]
+        .into_iter()
+        .find(|(_, pieces)| (*pieces & player_attackers).isnt_empty())?;
+
+        self.occupancy &= !(pieces & player_attackers).pfo_as_bitboard();
+
+        match piece_kind {
+            PieceKind::Pawn | PieceKind::Bishop => {
+                self.attackers |= self.get_diagonal_attackers();
+            }
+            PieceKind::Rook => {
+                self.attackers |= self.get_cross_attackers();
+            }
+            PieceKind::Queen => {
+                self.attackers |= self.get_diagonal_attackers() | self.get_cross_attackers();
+            }
+            PieceKind::Knight | PieceKind::King => {}
+        }
+
+        self.attackers &= self.occupancy;
+
+        Some(piece_kind)
+    }
+}
+
+impl Board {
+    fn get_captured_value(&self, target: Square) -> i32 {
+        match self.pieces.get_piece(target) {
+            Some(Piece { piece_kind, .. }) => piece_kind.see_value(),
+            None => 0,
+        }
+    }
+
+    // Returns the material balance of the capture sequence started by the move, from the perspective of the moving player.
+    // Each player captures with their least valuable attacker, and may stop capturing once continuing would lose material.
+    pub fn see(&self, chess_move: Move) -> i32 {
+        let Some((mut exchange, captured_value, mut on_target)) = Exchange::new(self, chess_move)
+        else {
+            return 0;
+        };
+
+        // The longest possible capture sequence involves every piece on the board.
+        let mut gains = [0; 32];
+        let mut depth = 0;
+
+        gains[0] = captured_value;
+
+        let players = [&self.moved_player, &self.moving_player];
+
+        loop {
+            let player = players[depth % 2];
+
+            let Some(piece_kind) = exchange.pop_least_valuable(player) else {
+                break;
+            };
+
+            // A king can only capture if the opponent has nothing left to recapture with.
+            if piece_kind == PieceKind::King
+                && (exchange.attackers & players[(depth + 1) % 2].pieces).isnt_empty()
+            {
+                break;
+            }
+
+            depth += 1;
+            gains[depth] = on_target - gains[depth - 1];
+            on_target = piece_kind.see_value();
+        }
+
+        // Each player only captures if it is favorable for them, so the gains are resolved from the end of the sequence.
+        while depth > 0 {
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+            depth -= 1;
+        }
+
+        gains[0]
+    }
+
+    // This is equivalent to "self.see(chess_move) >= threshold", but stops as soon as the result is known.
+    pub fn see_ge(&self, chess_move: Move, threshold: i32) -> bool {
+        let Some((mut exchange, captured_value, on_target)) = Exchange::new(self, chess_move)
+        else {
+            return 0 >= threshold;
+        };
+
+        // This is the balance the moving player would be left with if the opponent captured (or didn't capture) next.
+        let mut swap = captured_value - threshold;
+
+        if swap < 0 {
+            return false;
+        }
+
+        swap = on_target - swap;
+
+        if swap <= 0 {
+            return true;
+        }
+
+        let players = [&self.moved_player, &self.moving_player];
+        let mut result = true;
+        let mut depth = 0;
+
+        while let Some(piece_kind) = exchange.pop_least_valuable(players[depth % 2]) {
+            // A king can only capture if the opponent has nothing left to recapture with.
+            if piece_kind == PieceKind::King {
+                return if (exchange.attackers & players[(depth + 1) % 2].pieces).isnt_empty() {
+                    result
+                } else {
+                    !result
+                };
+            }
+
+            result = !result;
+            swap = piece_kind.see_value() - swap;
+
+            if swap < result as i32 {
+                break;
+            }
+
+            depth += 1;
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{game::board::Board, generators::Move};
+
+    fn see(fen: &str, chess_move: &str) -> i32 {
+        let board = Board::from_str(fen).unwrap();
+        let chess_move = Move::from_str(chess_move).unwrap();
+        let value = board.see(chess_move);
+
+        // Both functions must always agree.
+        assert!(board.see_ge(chess_move, value));
+        assert!(!board.see_ge(chess_move, value + 1));
+
+        value
+    }
+
+    #[test]
+    fn undefended_capture() {
+        assert_eq!(
+            see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "re1e5"),
+            100
+        )
+    }
+
+    #[test]
+    fn defended_capture() {
+        assert_eq!(see("4k3/8/3p4/4p3/8/8/8/4QK2 w - - 0 1", "qe1e5"), -800)
+    }
+
+    #[test]
+    fn quiet_move() {
+        assert_eq!(see("4k3/8/3p4/8/8/8/8/4QK2 w - - 0 1", "qe1e4"), 0);
+        assert_eq!(see("4k3/8/3p4/8/8/8/8/4QK2 w - - 0 1", "qe1e5"), -900)
+    }
+
+    #[test]
+    fn x_ray_recapture() {
+        // Without the rook behind, black would recapture and win the exchange.
+        assert_eq!(see("4r1k1/8/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "re2e5"), 100);
+        assert_eq!(see("4r1k1/8/8/4p3/8/8/4R3/6K1 w - - 0 1", "re2e5"), -400)
+    }
+
+    #[test]
+    fn king_cannot_recapture_defended_piece() {
+        assert_eq!(see("8/8/8/8/5k2/8/3Q4/2K1R3 w - - 0 1", "qd2e3"), 0);
+        assert_eq!(see("8/8/8/8/3p1k2/8/3Q4/2K1R3 w - - 0 1", "qd2e3"), -900)
+    }
+
+    #[test]
+    fn en_passant() {
+        assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5"), 100)
+    }
+}
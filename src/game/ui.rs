@@ -28,7 +28,7 @@ impl Display for Board {
                         Color::Black
                     },
                 )
-                .bg(if (square.0 + row % 2) % 2 == 0 {
+                .bg(if (square.0 + row % 2).is_multiple_of(2) {
                     Color::Blue
                 } else {
                     Color::Cyan
@@ -88,7 +88,7 @@ impl Debug for Board {
                     Color::Green
                 } else if current_attacks.get_bit(square) {
                     Color::Red
-                } else if (square.0 + row % 2) % 2 == 0 {
+                } else if (square.0 + row % 2).is_multiple_of(2) {
                     Color::Blue
                 } else {
                     Color::Cyan
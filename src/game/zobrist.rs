@@ -0,0 +1,179 @@
+// See: https://www.chessprogramming.org/Zobrist_Hashing
+use array_const_fn_init::array_const_fn_init as initialize;
+
+use crate::{generators::Square, Piece, PieceKind, Player};
+
+use super::board::Board;
+
+const CASTLING_OFFSET: usize = 768;
+const EP_OFFSET: usize = CASTLING_OFFSET + 4;
+const SIDE_OFFSET: usize = EP_OFFSET + 8;
+
+const SPLITMIX64_INCREMENT: u64 = 0x9E3779B97F4A7C15;
+
+// Advances the state and returns the next number. This is also used wherever else reproducible random numbers are needed.
+// See: https://prng.di.unimi.it/splitmix64.c
+pub const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(SPLITMIX64_INCREMENT);
+
+    let mut value = *state;
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+    value ^ (value >> 31)
+}
+
+// The keys are generated with SplitMix64, so they are the same on every run (and thus usable for storing positions).
+const fn gen_key(index: usize) -> u64 {
+    let mut state = (index as u64).wrapping_mul(SPLITMIX64_INCREMENT);
+
+    splitmix64(&mut state)
+}
+
+// There is a key for every piece on every square, for each castling right, for each en-passant file and for the side to move.
+pub static KEYS: [u64; 781] = initialize![gen_key; 781];
+
+pub fn get_piece_key(player: Player, piece_kind: PieceKind, square: Square) -> u64 {
+    KEYS[(player as usize * 6 + piece_kind as usize) * 64 + square.0 as usize]
+}
+
+pub fn get_side_key() -> u64 {
+    KEYS[SIDE_OFFSET]
+}
+
+impl Board {
+    // This includes everything in the hash which isn't a piece: the castling rights and the en-passant file.
+    // These are simpler to remove and add back wholesale during a move, than to update one by one.
+    pub fn get_state_hash(&self) -> u64 {
+        let white = self.get_player_state(Player::White);
+        let black = self.get_player_state(Player::Black);
+        let mut hash = 0;
+
+        for (index, can_castle) in [
+            white.can_castle_ks,
+            white.can_castle_qs,
+            black.can_castle_ks,
+            black.can_castle_qs,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if can_castle {
+                hash ^= KEYS[CASTLING_OFFSET + index];
+            }
+        }
+
+        if self.ep_info.capture_point.isnt_empty() {
+            hash ^=
+                KEYS[EP_OFFSET + (self.ep_info.capture_point.first_one_square().0 % 8) as usize];
+        }
+
+        hash
+    }
+
+    // Computes the hash from scratch. During the game it is updated incrementally instead, in "make_move".
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = self.get_state_hash();
+
+        for square in (0..64).map(Square) {
+            if let Some(Piece { piece_kind, player }) = *self.pieces.get_piece(square) {
+                hash ^= get_piece_key(player, piece_kind, square);
+            }
+        }
+
+        if self.current_player == Player::Black {
+            hash ^= get_side_key();
+        }
+
+        hash
+    }
+
+    // Computes the pawn hash from scratch, which only includes the pawn keys of "compute_hash".
+    pub fn compute_pawn_hash(&self) -> u64 {
+        let mut hash = 0;
+
+        for player in [Player::White, Player::Black] {
+            let mut pawns = self.get_player_state(player).pawns;
+
+            while pawns.isnt_empty() {
+                hash ^= get_piece_key(player, PieceKind::Pawn, pawns.pop_first_one());
+            }
+        }
+
+        hash
+    }
+
+    pub fn toggle_piece_hash(&mut self, player: Player, piece_kind: PieceKind, square: Square) {
+        let key = get_piece_key(player, piece_kind, square);
+
+        self.hash ^= key;
+
+        if piece_kind == PieceKind::Pawn {
+            self.pawn_hash ^= key;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        game::board::Board,
+        generators::{Move, MoveGen},
+    };
+
+    fn verify(board: Board, depth: u32) {
+        assert_eq!(board.hash, board.compute_hash());
+        assert_eq!(board.pawn_hash, board.compute_pawn_hash());
+
+        if depth > 0 {
+            for chess_move in MoveGen::run(board) {
+                let mut board_copy = board;
+                board_copy.make_move(chess_move);
+
+                verify(board_copy, depth - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_hash_matches() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            verify(Board::from_str(fen).unwrap(), 3);
+        }
+    }
+
+    #[test]
+    fn transpositions_match() {
+        let play = |moves: &[&str]| {
+            let mut board = Board::default();
+
+            for chess_move in moves {
+                board.make_move(Move::from_str(chess_move).unwrap());
+            }
+
+            board.hash
+        };
+
+        assert_eq!(
+            play(&["ng1f3", "ng8f6", "nb1c3"]),
+            play(&["nb1c3", "ng8f6", "ng1f3"])
+        );
+        assert_ne!(play(&["ng1f3", "ng8f6"]), play(&["nb1c3", "ng8f6"]));
+        // The same pieces with a different player to move must not collide.
+        assert_ne!(
+            play(&["ng1f3", "ng8f6", "nf3g1", "nf6g8"]),
+            play(&["ng1f3", "ng8f6", "nf3g1"])
+        );
+        assert_eq!(
+            play(&["ng1f3", "ng8f6", "nf3g1", "nf6g8"]),
+            Board::default().hash
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{
+    game::board::Board,
+    tables::{KING_MOVES, KNIGHT_MOVES, PAWN_ATTACKS},
+    BitBoard, Piece, PieceKind, Player, B_CASTLE_KS_SPACE, B_CASTLE_QS_KING_PASS,
+    B_CASTLE_QS_SPACE, PROMOTION_PIECES, W_CASTLE_KS_SPACE, W_CASTLE_QS_KING_PASS,
+    W_CASTLE_QS_SPACE,
+};
+
+use super::{slides, Move, Square};
+
+// These functions validate a single move (for example, one taken from a transposition table or a killer slot) without generating the full move list.
+impl Board {
+    fn is_own_piece(&self, square: Square, piece_kind: PieceKind) -> bool {
+        matches!(
+            self.pieces.get_piece(square),
+            Some(Piece { piece_kind: kind, player }) if *kind == piece_kind && *player == self.current_player
+        )
+    }
+
+    // Returns whether a pawn on the origin square can reach the target with a push or a capture.
+    fn can_pawn_reach(&self, origin: Square, target: Square, double_push: bool) -> bool {
+        let empty_squares = !(self.moving_player.pieces | self.moved_player.pieces);
+        let pawn = BitBoard::from(origin);
+
+        let (single_push, starting_rank) = match self.current_player {
+            Player::White => (pawn.move_up(1), 1),
+            Player::Black => (pawn.move_down(1), 6),
+        };
+        let single_push = single_push & empty_squares;
+
+        if double_push {
+            let double_push = match self.current_player {
+                Player::White => single_push.move_up(1),
+                Player::Black => single_push.move_down(1),
+            } & empty_squares;
+
+            origin.get_row() == starting_rank && double_push.get_bit(target)
+        } else {
+            single_push.get_bit(target)
+                || (PAWN_ATTACKS[self.current_player][origin] & self.moved_player.pieces)
+                    .get_bit(target)
+        }
+    }
+
+    // A pseudo-legal move follows the movement rules of its piece in the current position, but may still leave the king in check.
+    pub fn is_pseudo_legal(&self, chess_move: Move) -> bool {
+        let occupancy = self.moving_player.pieces | self.moved_player.pieces;
+        let promotion_rank = match self.current_player {
+            Player::White => 7,
+            Player::Black => 0,
+        };
+
+        match chess_move {
+            Move::Regular {
+                origin,
+                target,
+                piece_kind,
+                double_push,
+            } => {
+                if !self.is_own_piece(origin, piece_kind)
+                    || self.moving_player.pieces.get_bit(target)
+                    || (double_push && piece_kind != PieceKind::Pawn)
+                {
+                    return false;
+                }
+
+                let origin_bitboard = BitBoard::from(origin);
+
+                match piece_kind {
+                    PieceKind::Pawn => {
+                        // Pawns reaching the last rank must promote.
+                        target.get_row() != promotion_rank
+                            && self.can_pawn_reach(origin, target, double_push)
+                    }
+                    PieceKind::Knight => KNIGHT_MOVES[origin].get_bit(target),
+                    PieceKind::King => KING_MOVES[origin].get_bit(target),
+                    PieceKind::Bishop => {
+                        slides::get_diagonal_attacks(origin_bitboard, !occupancy).get_bit(target)
+                    }
+                    PieceKind::Rook => {
+                        slides::get_cross_attacks(origin_bitboard, !occupancy).get_bit(target)
+                    }
+                    PieceKind::Queen => (slides::get_diagonal_attacks(origin_bitboard, !occupancy)
+                        | slides::get_cross_attacks(origin_bitboard, !occupancy))
+                    .get_bit(target),
+                }
+            }
+            Move::Promotion {
+                origin,
+                target,
+                promotion_to,
+            } => {
+                self.is_own_piece(origin, PieceKind::Pawn)
+                    && PROMOTION_PIECES.contains(&promotion_to)
+                    && target.get_row() == promotion_rank
+                    && self.can_pawn_reach(origin, target, false)
+            }
+            Move::EnPassant { origin } => {
+                self.ep_info.capture_point.isnt_empty()
+                    && self.is_own_piece(origin, PieceKind::Pawn)
+                    && (PAWN_ATTACKS[self.current_player][origin] & self.ep_info.capture_point)
+                        .isnt_empty()
+            }
+            Move::CastleKS => {
+                self.moving_player.can_castle_ks
+                    && match self.current_player {
+                        Player::White => W_CASTLE_KS_SPACE,
+                        Player::Black => B_CASTLE_KS_SPACE,
+                    }
+                    .does_contain_none(occupancy)
+            }
+            Move::CastleQS => {
+                self.moving_player.can_castle_qs
+                    && match self.current_player {
+                        Player::White => W_CASTLE_QS_SPACE,
+                        Player::Black => B_CASTLE_QS_SPACE,
+                    }
+                    .does_contain_none(occupancy)
+            }
+        }
+    }
+
+    // A legal move is a pseudo-legal move that also respects the pins, the check mask and the "king must move" flag of the moving player.
+    // These are the same constraints the move generator uses, so this agrees with "MoveGen::run".
+    pub fn is_legal(&self, chess_move: Move) -> bool {
+        if !self.is_pseudo_legal(chess_move) {
+            return false;
+        }
+
+        let player = &self.moving_player;
+
+        match chess_move {
+            Move::Regular {
+                target,
+                piece_kind: PieceKind::King,
+                ..
+            } => !self.moved_player.attacks.get_bit(target),
+            _ if player.king_must_move => false,
+            Move::Regular { origin, target, .. } | Move::Promotion { origin, target, .. } => {
+                player.check_mask.get_bit(target)
+                    && player
+                        .pins
+                        .get_pin_mask(BitBoard::from(origin))
+                        .get_bit(target)
+            }
+            Move::EnPassant { origin } => {
+                // There are scenarios where the capture point is not in the check mask but the en-passant pawn is (like when it threatens mate).
+                ((self.ep_info.capture_point | self.ep_info.pawn) & player.check_mask).isnt_empty()
+                    && (player.pins.get_pin_mask(BitBoard::from(origin))
+                        & self.ep_info.capture_point)
+                        .isnt_empty()
+            }
+            Move::CastleKS => {
+                player.isnt_in_check()
+                    && match self.current_player {
+                        Player::White => W_CASTLE_KS_SPACE,
+                        Player::Black => B_CASTLE_KS_SPACE,
+                    }
+                    .does_contain_none(self.moved_player.attacks)
+            }
+            Move::CastleQS => {
+                player.isnt_in_check()
+                    && match self.current_player {
+                        Player::White => W_CASTLE_QS_KING_PASS,
+                        Player::Black => B_CASTLE_QS_KING_PASS,
+                    }
+                    .does_contain_none(self.moved_player.attacks)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        game::board::Board,
+        generators::{Move, MoveGen, Square},
+        PieceKind, PROMOTION_PIECES,
+    };
+
+    // Produces every move of every shape starting from an occupied square, most of which are illegal.
+    fn get_candidates(board: &Board) -> Vec<Move> {
+        let mut candidates = vec![Move::CastleKS, Move::CastleQS];
+
+        for origin in (0..64).map(Square) {
+            if board.pieces.get_piece(origin).is_none() {
+                continue;
+            }
+
+            candidates.push(Move::EnPassant { origin });
+
+            for target in (0..64).map(Square) {
+                for piece_kind in [
+                    PieceKind::King,
+                    PieceKind::Queen,
+                    PieceKind::Rook,
+                    PieceKind::Bishop,
+                    PieceKind::Knight,
+                    PieceKind::Pawn,
+                ] {
+                    candidates.push(Move::Regular {
+                        origin,
+                        target,
+                        piece_kind,
+                        double_push: false,
+                    });
+                }
+
+                candidates.push(Move::Regular {
+                    origin,
+                    target,
+                    piece_kind: PieceKind::Pawn,
+                    double_push: true,
+                });
+
+                for promotion_to in PROMOTION_PIECES {
+                    candidates.push(Move::Promotion {
+                        origin,
+                        target,
+                        promotion_to,
+                    });
+                }
+            }
+        }
+
+        candidates
+    }
+
+    fn verify(board: Board, depth: u32) {
+        let moves = MoveGen::run(board);
+
+        for candidate in get_candidates(&board) {
+            let is_legal = board.is_legal(candidate);
+
+            assert_eq!(is_legal, moves.contains(&candidate), "{:?}", candidate);
+            assert!(!is_legal || board.is_pseudo_legal(candidate));
+        }
+
+        if depth > 1 {
+            for chess_move in moves {
+                let mut board_copy = board;
+                board_copy.make_move(chess_move);
+
+                verify(board_copy, depth - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_move_gen() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ] {
+            verify(Board::from_str(fen).unwrap(), 2);
+        }
+    }
+}
@@ -10,6 +10,7 @@ use crate::{
 };
 
 pub mod attacks;
+pub mod legality;
 pub mod moves;
 pub mod slides;
 
@@ -157,7 +158,7 @@ impl TryFrom<&str> for Square {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Move {
     Regular {
         origin: Square,
@@ -177,6 +178,79 @@ pub enum Move {
     CastleQS,
 }
 
+impl Move {
+    const EN_PASSANT: u16 = 6;
+    const PROMOTION: u16 = 7;
+    const CASTLE_KS: u16 = 11;
+    const CASTLE_QS: u16 = 12;
+
+    // Packs the move into 16 bits (6 for the origin, 6 for the target and 4 for the kind of move), for compact storage.
+    // A packed value of 0 is never produced, so it can be used to represent "no move".
+    pub fn pack(self) -> u16 {
+        let (origin, target, kind) = match self {
+            Move::Regular {
+                origin,
+                target,
+                piece_kind,
+                ..
+            } => (origin.0, target.0, piece_kind as u16),
+            Move::EnPassant { origin } => (origin.0, 0, Self::EN_PASSANT),
+            Move::Promotion {
+                origin,
+                target,
+                promotion_to,
+            } => (
+                origin.0,
+                target.0,
+                Self::PROMOTION
+                    + PROMOTION_PIECES
+                        .iter()
+                        .position(|&piece_kind| piece_kind == promotion_to)
+                        .unwrap() as u16,
+            ),
+            Move::CastleKS => (0, 0, Self::CASTLE_KS),
+            Move::CastleQS => (0, 0, Self::CASTLE_QS),
+        };
+
+        origin as u16 | (target as u16) << 6 | kind << 12
+    }
+
+    pub fn unpack(packed: u16) -> Option<Self> {
+        let origin = Square((packed & 0b111111) as u32);
+        let target = Square(((packed >> 6) & 0b111111) as u32);
+
+        match packed >> 12 {
+            _ if packed == 0 => None,
+            kind @ 0..=5 => {
+                let piece_kind = [
+                    PieceKind::King,
+                    PieceKind::Queen,
+                    PieceKind::Rook,
+                    PieceKind::Bishop,
+                    PieceKind::Knight,
+                    PieceKind::Pawn,
+                ][kind as usize];
+
+                Some(Move::Regular {
+                    origin,
+                    target,
+                    piece_kind,
+                    double_push: piece_kind == PieceKind::Pawn && origin.0.abs_diff(target.0) == 16,
+                })
+            }
+            Self::EN_PASSANT => Some(Move::EnPassant { origin }),
+            kind @ Self::PROMOTION..=10 => Some(Move::Promotion {
+                origin,
+                target,
+                promotion_to: PROMOTION_PIECES[(kind - Self::PROMOTION) as usize],
+            }),
+            Self::CASTLE_KS => Some(Move::CastleKS),
+            Self::CASTLE_QS => Some(Move::CastleQS),
+            _ => None,
+        }
+    }
+}
+
 impl FromStr for Move {
     type Err = &'static str;
 
@@ -258,18 +332,30 @@ pub struct MoveGen {
     moving_player: PlayerState,
     moved_player: PlayerState,
     empty_squares: BitBoard,
+    // The squares pieces are allowed to move to (besides promoting pawns and en-passant captures).
+    target_mask: BitBoard,
     ep_info: EnPassant,
     moves: Vec<Move>,
 }
 
 impl MoveGen {
     pub fn run(board: Board) -> Vec<Move> {
+        Self::run_with_mask(board, BitBoard::full(), 31) // Chess has a branching factor of 31 on average.
+    }
+
+    // Generates only the legal captures and promotions, which are the moves the quiescence search follows.
+    pub fn run_captures(board: Board) -> Vec<Move> {
+        Self::run_with_mask(board, board.moved_player.pieces, 8)
+    }
+
+    fn run_with_mask(board: Board, target_mask: BitBoard, capacity: usize) -> Vec<Move> {
         let mut move_gen = Self {
             moving_player: board.moving_player,
             moved_player: board.moved_player,
             empty_squares: !(board.moving_player.pieces | board.moved_player.pieces),
+            target_mask,
             ep_info: board.ep_info,
-            moves: Vec::with_capacity(31), // Chess has a branching factor of 31 on average.
+            moves: Vec::with_capacity(capacity),
         };
 
         move_gen.gen_moves(board.current_player);
@@ -296,7 +382,8 @@ impl MoveGen {
                     self.gen_white_pawn_en_passants();
 
                     // Even though castling is a king move, it cannot happen during check (The above conditional checks if the king is in double check).
-                    if self.moving_player.isnt_in_check() {
+                    // It also isn't a capture, so it is skipped when the empty squares are masked out.
+                    if self.moving_player.isnt_in_check() && self.target_mask.is_full() {
                         self.white_castle_king_side();
                         self.white_castle_queen_side();
                     }
@@ -307,7 +394,7 @@ impl MoveGen {
                     self.gen_black_pawn_en_passants();
 
                     // Even though castling is a king move, it cannot happen during check (The above conditional checks if the king is in double check).
-                    if self.moving_player.isnt_in_check() {
+                    if self.moving_player.isnt_in_check() && self.target_mask.is_full() {
                         self.black_castle_king_side();
                         self.black_castle_queen_side();
                     }
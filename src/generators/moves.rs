@@ -99,7 +99,9 @@ impl MoveGen {
                 .move_down(2);
 
         let mut promotions = pushes & FIRST_RANK;
-        pushes &= !FIRST_RANK;
+        // Promotions are kept even when only captures are generated, since they change the material balance too.
+        pushes &= !FIRST_RANK & self.target_mask;
+        double_pushes &= self.target_mask;
 
         while promotions.isnt_empty() {
             let target = promotions.pop_first_one();
@@ -215,7 +217,9 @@ impl MoveGen {
             & (self.moving_player.pawns & SECOND_RANK & unpinned_locations).move_up(2);
 
         let mut promotions = pushes & EIGHTH_RANK;
-        pushes &= !EIGHTH_RANK;
+        // Promotions are kept even when only captures are generated, since they change the material balance too.
+        pushes &= !EIGHTH_RANK & self.target_mask;
+        double_pushes &= self.target_mask;
 
         while promotions.isnt_empty() {
             let target = promotions.pop_first_one();
@@ -258,6 +262,7 @@ impl MoveGen {
                 | slides::get_down_right_attacks(bishop, self.empty_squares))
                 & self.moving_player.check_mask
                 & !self.moving_player.pieces
+                & self.target_mask
                 & self.moving_player.pins.get_pin_mask(bishop);
 
             while moves.isnt_empty() {
@@ -275,7 +280,9 @@ impl MoveGen {
 
     pub fn gen_king_moves(&mut self) {
         let origin = self.moving_player.king.pop_first_one(); // There's only one king.
-        let mut moves = KING_MOVES[origin] - self.moving_player.pieces - self.moved_player.attacks;
+        let mut moves = (KING_MOVES[origin] & self.target_mask)
+            - self.moving_player.pieces
+            - self.moved_player.attacks;
 
         while moves.isnt_empty() {
             let target = moves.pop_first_one();
@@ -296,8 +303,10 @@ impl MoveGen {
         while knights.isnt_empty() {
             let origin = knights.pop_first_one();
 
-            let mut moves =
-                self.moving_player.check_mask & !self.moving_player.pieces & KNIGHT_MOVES[origin];
+            let mut moves = self.moving_player.check_mask
+                & !self.moving_player.pieces
+                & self.target_mask
+                & KNIGHT_MOVES[origin];
 
             while moves.isnt_empty() {
                 let target = moves.pop_first_one();
@@ -325,6 +334,7 @@ impl MoveGen {
                 | slides::get_down_left_attacks(queen, self.empty_squares)
                 | slides::get_down_right_attacks(queen, self.empty_squares))
                 & !self.moving_player.pieces
+                & self.target_mask
                 & self.moving_player.check_mask
                 & self.moving_player.pins.get_pin_mask(queen);
 
@@ -356,6 +366,7 @@ impl MoveGen {
                 | slides::get_left_attacks(rook, self.empty_squares)
                 | slides::get_down_attacks(rook, self.empty_squares))
                 & !self.moving_player.pieces
+                & self.target_mask
                 & self.moving_player.check_mask
                 & self.moving_player.pins.get_pin_mask(rook);
 
@@ -412,3 +423,49 @@ impl MoveGen {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        game::board::Board,
+        generators::{Move, MoveGen},
+    };
+
+    fn verify(board: Board, depth: u32) {
+        let moves = MoveGen::run(board);
+        let captures = MoveGen::run_captures(board);
+        let expected = moves
+            .iter()
+            .filter(|&&chess_move| {
+                board.is_capture(chess_move) || matches!(chess_move, Move::Promotion { .. })
+            })
+            .count();
+
+        assert_eq!(captures.len(), expected);
+        assert!(captures.iter().all(|chess_move| moves.contains(chess_move)));
+
+        if depth > 1 {
+            for chess_move in moves {
+                let mut board_copy = board;
+                board_copy.make_move(chess_move);
+
+                verify(board_copy, depth - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn captures_match_move_gen() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ] {
+            verify(Board::from_str(fen).unwrap(), 3);
+        }
+    }
+}
@@ -86,3 +86,18 @@ pub fn get_down_left_attacks(mut pieces: BitBoard, mut empty: BitBoard) -> BitBo
 
     pieces.move_down_left()
 }
+
+// These combine the rays of each direction a bishop or a rook can slide in.
+pub fn get_diagonal_attacks(pieces: BitBoard, empty: BitBoard) -> BitBoard {
+    get_up_right_attacks(pieces, empty)
+        | get_up_left_attacks(pieces, empty)
+        | get_down_left_attacks(pieces, empty)
+        | get_down_right_attacks(pieces, empty)
+}
+
+pub fn get_cross_attacks(pieces: BitBoard, empty: BitBoard) -> BitBoard {
+    get_up_attacks(pieces, empty)
+        | get_right_attacks(pieces, empty)
+        | get_down_attacks(pieces, empty)
+        | get_left_attacks(pieces, empty)
+}
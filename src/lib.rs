@@ -1,9 +1,10 @@
-#![feature(const_trait_impl, const_for, const_mut_refs, test)]
+#![feature(const_trait_impl, const_ops, test)]
+#![cfg_attr(feature = "nnue", feature(portable_simd))]
 
 extern crate test;
 
 use std::{
-    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Not, Shl, Shr, Sub},
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Index, IndexMut, Not, Shl, Shr, Sub},
     str::FromStr,
 };
 
@@ -13,10 +14,13 @@ use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 use crate::generators::MoveGen;
 
+pub mod book;
+pub mod datagen;
 pub mod engine;
 pub mod game;
 pub mod generators;
 pub mod tables;
+pub mod tuner;
 pub mod uci;
 
 pub const PROMOTION_PIECES: [PieceKind; 4] = [
@@ -107,6 +111,27 @@ impl BitBoard {
         self & self.move_down(1)
     }
 
+    // Every one is copied to all the squares above it, which fills the files upwards.
+    // See: https://www.chessprogramming.org/Pawn_Fills
+    pub fn smear_ones_up(self) -> Self {
+        let bitboard = self | self.move_up(1);
+        let bitboard = bitboard | bitboard.move_up(2);
+
+        bitboard | bitboard.move_up(4)
+    }
+
+    pub fn smear_ones_down(self) -> Self {
+        let bitboard = self | self.move_down(1);
+        let bitboard = bitboard | bitboard.move_down(2);
+
+        bitboard | bitboard.move_down(4)
+    }
+
+    // Every file with a one becomes full.
+    pub fn smear_ones_files(self) -> Self {
+        self.smear_ones_up() | self.smear_ones_down()
+    }
+
     // This function will make sure all of the switched bits in "self" have a switched-off bit in "mask".
     pub fn does_contain_none(&self, mask: Self) -> bool {
         (*self - mask) == *self
@@ -299,6 +324,12 @@ pub struct Pins {
     pub anti_diagonal: BitBoard,
 }
 
+impl Default for Pins {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Pins {
     pub fn new() -> Self {
         Self {
@@ -386,7 +417,7 @@ impl FromStr for PieceKind {
 }
 
 impl PieceKind {
-    fn into_piece_char(&self) -> char {
+    fn into_piece_char(self) -> char {
         match self {
             PieceKind::King => 'k',
             PieceKind::Queen => 'q',
@@ -415,6 +446,21 @@ impl Not for Player {
     }
 }
 
+// This allows tables with a value per player to be indexed directly. White's value comes first.
+impl<T> Index<Player> for [T; 2] {
+    type Output = T;
+
+    fn index(&self, index: Player) -> &Self::Output {
+        &self[index as usize]
+    }
+}
+
+impl<T> IndexMut<Player> for [T; 2] {
+    fn index_mut(&mut self, index: Player) -> &mut Self::Output {
+        &mut self[index as usize]
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Piece {
     pub piece_kind: PieceKind,
@@ -501,7 +547,7 @@ pub fn search(board: Board, depth: u32) -> u32 {
     } else if depth == 1 {
         // At a depth of one we know all next moves will reach depth zero. Thus, we can know they are all leaves and add one each to the nodes searched.
         moves.len() as u32
-    } else if moves.len() == 0 {
+    } else if moves.is_empty() {
         0
     } else {
         moves
@@ -0,0 +1,21 @@
+use std::{env, process};
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let result = match args.first().map(String::as_str) {
+        Some("book") => fisher::book::run(&args[1..]),
+        Some("datagen") => fisher::datagen::run(&args[1..]),
+        Some("tune") => fisher::tuner::run(&args[1..]),
+        _ => {
+            fisher::uci::run();
+
+            Ok(())
+        }
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
@@ -1,6 +1,18 @@
 use crate::BitBoard;
 use array_const_fn_init::array_const_fn_init as initialize;
 
+// These are the (file, rank) steps of every direction a sliding piece can move in.
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
 const fn gen_knight_moves(square: usize) -> BitBoard {
     let piece = BitBoard(1 << square);
 
@@ -24,8 +36,166 @@ const fn gen_king_moves(square: usize) -> BitBoard {
     horizontal.move_up(1) | middle | horizontal.move_down(1)
 }
 
+const fn gen_white_pawn_attacks(square: usize) -> BitBoard {
+    let piece = BitBoard(1 << square);
+
+    piece.move_up_left() | piece.move_up_right()
+}
+
+const fn gen_black_pawn_attacks(square: usize) -> BitBoard {
+    let piece = BitBoard(1 << square);
+
+    piece.move_down_left() | piece.move_down_right()
+}
+
+// Returns every square from the origin (exclusive) to the edge of the board in the given direction.
+const fn gen_ray(square: usize, (file_step, rank_step): (i32, i32)) -> BitBoard {
+    let mut ray = BitBoard::empty();
+    let mut file = (square % 8) as i32 + file_step;
+    let mut rank = (square / 8) as i32 + rank_step;
+
+    while 0 <= file && file < 8 && 0 <= rank && rank < 8 {
+        ray = ray | BitBoard(1 << (rank * 8 + file));
+
+        file += file_step;
+        rank += rank_step;
+    }
+
+    ray
+}
+
+const fn gen_between(origin: usize) -> [BitBoard; 64] {
+    let mut between = [BitBoard::empty(); 64];
+    let mut direction = 0;
+
+    while direction < DIRECTIONS.len() {
+        let (file_step, rank_step) = DIRECTIONS[direction];
+        let mut passed = BitBoard::empty();
+        let mut file = (origin % 8) as i32 + file_step;
+        let mut rank = (origin / 8) as i32 + rank_step;
+
+        while 0 <= file && file < 8 && 0 <= rank && rank < 8 {
+            let square = (rank * 8 + file) as usize;
+
+            between[square] = passed;
+            passed = passed | BitBoard(1 << square);
+
+            file += file_step;
+            rank += rank_step;
+        }
+
+        direction += 1;
+    }
+
+    between
+}
+
+const fn gen_line(origin: usize) -> [BitBoard; 64] {
+    let mut line = [BitBoard::empty(); 64];
+    let mut direction = 0;
+
+    while direction < DIRECTIONS.len() {
+        let (file_step, rank_step) = DIRECTIONS[direction];
+        let ray = gen_ray(origin, (file_step, rank_step));
+        let full_line = ray | gen_ray(origin, (-file_step, -rank_step)) | BitBoard(1 << origin);
+
+        let mut remaining = ray.0;
+
+        while remaining != 0 {
+            line[remaining.trailing_zeros() as usize] = full_line;
+            remaining &= remaining - 1;
+        }
+
+        direction += 1;
+    }
+
+    line
+}
+
+const fn gen_file_mask(file: usize) -> BitBoard {
+    BitBoard(0x0101010101010101 << file)
+}
+
+const fn gen_rank_mask(rank: usize) -> BitBoard {
+    BitBoard(0xFF << (rank * 8))
+}
+
+const fn gen_diagonal_mask(square: usize) -> BitBoard {
+    gen_ray(square, (1, 1)) | gen_ray(square, (-1, -1)) | BitBoard(1 << square)
+}
+
+const fn gen_anti_diagonal_mask(square: usize) -> BitBoard {
+    gen_ray(square, (-1, 1)) | gen_ray(square, (1, -1)) | BitBoard(1 << square)
+}
+
 // This is a look-up table for each move a knight could make in a given square. The index of the move bitboard is the origin square of the knight.
 pub const KNIGHT_MOVES: [BitBoard; 64] = initialize![gen_knight_moves; 64];
 
 // Same deal, but for the king.
 pub const KING_MOVES: [BitBoard; 64] = initialize![gen_king_moves; 64];
+
+// Same deal, but for the squares a pawn of each player attacks.
+pub const PAWN_ATTACKS: [[BitBoard; 64]; 2] = [
+    initialize![gen_white_pawn_attacks; 64],
+    initialize![gen_black_pawn_attacks; 64],
+];
+
+// The squares strictly between two squares on a shared line, or nothing if the squares aren't on a shared line.
+pub static BETWEEN: [[BitBoard; 64]; 64] = initialize![gen_between; 64];
+
+// The entire line (from edge to edge) passing through two squares, or nothing if the squares aren't on a shared line.
+pub static LINE: [[BitBoard; 64]; 64] = initialize![gen_line; 64];
+
+pub const FILE_MASKS: [BitBoard; 8] = initialize![gen_file_mask; 8];
+pub const RANK_MASKS: [BitBoard; 8] = initialize![gen_rank_mask; 8];
+
+// The diagonal (going up and to the right) and anti-diagonal (going up and to the left) passing through each square.
+pub const DIAGONAL_MASKS: [BitBoard; 64] = initialize![gen_diagonal_mask; 64];
+pub const ANTI_DIAGONAL_MASKS: [BitBoard; 64] = initialize![gen_anti_diagonal_mask; 64];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generators::Square, Player};
+
+    #[test]
+    fn between_squares() {
+        assert!(BETWEEN[Square::A1][Square::A1] == BitBoard::empty());
+        assert!(BETWEEN[Square::A1][Square::B3] == BitBoard::empty());
+        assert!(BETWEEN[Square::A1][Square::B2] == BitBoard::empty());
+        assert!(
+            BETWEEN[Square::A1][Square::D4]
+                == BitBoard::from(Square::B2) | BitBoard::from(Square::C3)
+        );
+        assert!(
+            BETWEEN[Square::H1][Square::E1]
+                == BitBoard::from(Square::F1) | BitBoard::from(Square::G1)
+        );
+
+        for origin in 0..64 {
+            for target in 0..64 {
+                assert!(BETWEEN[origin][target] == BETWEEN[target][origin]);
+                assert!(BETWEEN[origin][target].does_contain_none(!LINE[origin][target]));
+            }
+        }
+    }
+
+    #[test]
+    fn lines() {
+        assert!(LINE[Square::A1][Square::B3] == BitBoard::empty());
+        assert!(LINE[Square::A1][Square::A1] == BitBoard::empty());
+        assert!(LINE[Square::C3][Square::F6] == DIAGONAL_MASKS[Square::A1]);
+        assert!(LINE[Square::B2][Square::B7] == FILE_MASKS[1]);
+        assert!(LINE[Square::H4][Square::A4] == RANK_MASKS[3]);
+        assert!(LINE[Square::H1][Square::A8] == ANTI_DIAGONAL_MASKS[Square::D5]);
+    }
+
+    #[test]
+    fn pawn_attacks() {
+        assert!(
+            PAWN_ATTACKS[Player::White][Square::E4]
+                == BitBoard::from(Square::D5) | BitBoard::from(Square::F5)
+        );
+        assert!(PAWN_ATTACKS[Player::Black][Square::A5] == BitBoard::from(Square::B4));
+    }
+}
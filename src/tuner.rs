@@ -0,0 +1,358 @@
+// Tunes the weights of the evaluation, so that it predicts the results of the games its positions come from.
+// The error is the mean squared difference between each result and the score mapped to an expected result by a sigmoid.
+// See: https://www.chessprogramming.org/Texel%27s_Tuning_Method
+use std::{fs, str::FromStr};
+
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    engine::{
+        eval, params::EvalParams, pawns::PawnTable, search::Searcher, tt::TranspositionTable,
+    },
+    game::board::Board,
+    Player,
+};
+
+const DEFAULT_EPOCHS: usize = 100;
+// The positions are split into batches of at least this size between the threads, which each need their own tables.
+const MIN_BATCH_SIZE: usize = 4096;
+// See: https://arxiv.org/abs/1412.6980
+const FIRST_MOMENT_DECAY: f64 = 0.9;
+const SECOND_MOMENT_DECAY: f64 = 0.999;
+const EPSILON: f64 = 1e-8;
+
+pub struct TuningPosition {
+    pub board: Board,
+    // The result of the game from the perspective of white: one for a win, a half for a draw and zero for a loss.
+    pub result: f64,
+}
+
+fn parse_result(text: &str) -> Result<f64, &'static str> {
+    match text.trim().trim_matches('"') {
+        "1-0" | "1.0" | "1" => Ok(1.0),
+        "1/2-1/2" | "0.5" => Ok(0.5),
+        "0-1" | "0.0" | "0" => Ok(0.0),
+        _ => Err("Result must be \"1-0\", \"1/2-1/2\" or \"0-1\" (or 1.0, 0.5 and 0.0)"),
+    }
+}
+
+impl FromStr for TuningPosition {
+    type Err = &'static str;
+
+    // Accepts "<fen> [<result>]" and "<fen>; <result>". Any fields between the FEN and the result (like a score) are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (fen, result) = if let Some((fen, result)) = s.split_once('[') {
+            (fen, result.trim_end().trim_end_matches(']'))
+        } else {
+            let fen = s.split(';').next().unwrap_or_default();
+            let result = s
+                .rsplit_once(';')
+                .ok_or("Position must be followed by the result of its game")?
+                .1;
+
+            (fen, result)
+        };
+
+        Ok(Self {
+            board: Board::from_str(fen.trim())?,
+            result: parse_result(result)?,
+        })
+    }
+}
+
+// Parses a position per line, skipping the blank ones.
+pub fn parse_positions(data: &str) -> Result<Vec<TuningPosition>, &'static str> {
+    data.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(TuningPosition::from_str)
+        .collect()
+}
+
+// The expected result for white, given the score from the perspective of white.
+// The scaling fits the sigmoid to the scores, so that the weights keep their scale (like a pawn being worth about 100).
+pub fn get_expected_result(score: i32, scaling: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-scaling * score as f64 / 400.0))
+}
+
+fn evaluate_for_white(board: &Board, params: &EvalParams, pawn_table: &mut PawnTable) -> i32 {
+    let score = eval::evaluate(board, params, pawn_table);
+
+    match board.current_player {
+        Player::White => score,
+        Player::Black => -score,
+    }
+}
+
+// Optimizes the weights with Adam, where the gradient of every weight is estimated by changing it by one in both directions.
+// The weights are kept as floating point numbers, so that small steps add up even though the evaluation rounds them.
+pub struct Tuner {
+    positions: Vec<TuningPosition>,
+    scaling: f64,
+    learning_rate: f64,
+    weights: Vec<f64>,
+    first_moments: Vec<f64>,
+    second_moments: Vec<f64>,
+    steps: i32,
+}
+
+impl Tuner {
+    // Every position is replaced by the quiet position its quiescence search ends in (with the given weights), just once.
+    // The evaluation is only meaningful in quiet positions, and doing this at every step would be far slower.
+    pub fn new(positions: Vec<TuningPosition>, params: EvalParams) -> Self {
+        let tt = TranspositionTable::new(1);
+        let positions = positions
+            .par_iter()
+            .with_min_len(MIN_BATCH_SIZE)
+            .map_init(
+                || {
+                    let mut searcher = Searcher::new(&tt);
+                    searcher.set_params(params);
+
+                    searcher
+                },
+                |searcher, position| TuningPosition {
+                    board: searcher.get_quiet_position(&position.board),
+                    result: position.result,
+                },
+            )
+            .collect();
+        let weights = params
+            .to_vec()
+            .into_iter()
+            .map(|weight| weight as f64)
+            .collect::<Vec<_>>();
+
+        Self {
+            positions,
+            scaling: 1.0,
+            learning_rate: 1.0,
+            first_moments: vec![0.0; weights.len()],
+            second_moments: vec![0.0; weights.len()],
+            weights,
+            steps: 0,
+        }
+    }
+
+    pub fn get_scaling(&self) -> f64 {
+        self.scaling
+    }
+
+    // How much the weights may change at every step, roughly.
+    pub fn set_learning_rate(&mut self, learning_rate: f64) {
+        self.learning_rate = learning_rate;
+    }
+
+    // The tuned weights, rounded to what the evaluation uses.
+    pub fn get_params(&self) -> EvalParams {
+        let weights = self
+            .weights
+            .iter()
+            .map(|weight| weight.round() as i32)
+            .collect::<Vec<_>>();
+
+        EvalParams::from_slice(&weights).expect("Every weight is tuned")
+    }
+
+    fn get_error_with_scaling(&self, params: &EvalParams, scaling: f64) -> f64 {
+        let total = self
+            .positions
+            .par_iter()
+            .with_min_len(MIN_BATCH_SIZE)
+            .map_init(PawnTable::new, |pawn_table, position| {
+                let score = evaluate_for_white(&position.board, params, pawn_table);
+
+                (position.result - get_expected_result(score, scaling)).powi(2)
+            })
+            .sum::<f64>();
+
+        total / self.positions.len().max(1) as f64
+    }
+
+    pub fn get_error(&self, params: &EvalParams) -> f64 {
+        self.get_error_with_scaling(params, self.scaling)
+    }
+
+    // Finds the scaling which fits the current weights best, by narrowing down the range around the best one found so far.
+    // It stays fixed while tuning, since the weights could otherwise all shrink or grow together.
+    pub fn fit_scaling(&mut self) -> f64 {
+        let params = self.get_params();
+        let mut best = (
+            self.get_error_with_scaling(&params, self.scaling),
+            self.scaling,
+        );
+
+        for step in [0.1, 0.01, 0.001] {
+            let center = best.1;
+
+            for offset in -10..=10 {
+                let scaling = center + offset as f64 * step;
+
+                if scaling <= 0.0 {
+                    continue;
+                }
+
+                let error = self.get_error_with_scaling(&params, scaling);
+
+                if error < best.0 {
+                    best = (error, scaling);
+                }
+            }
+        }
+
+        self.scaling = best.1;
+
+        best.0
+    }
+
+    fn get_gradient(&self) -> Vec<f64> {
+        let weights = self.get_params().to_vec();
+
+        (0..weights.len())
+            .map(|index| {
+                let mut changed = weights.clone();
+
+                changed[index] = weights[index] + 1;
+                let higher = self.get_error(&EvalParams::from_slice(&changed).unwrap());
+
+                changed[index] = weights[index] - 1;
+                let lower = self.get_error(&EvalParams::from_slice(&changed).unwrap());
+
+                (higher - lower) / 2.0
+            })
+            .collect()
+    }
+
+    // Takes a step over all of the positions, and returns the error afterwards.
+    pub fn step(&mut self) -> f64 {
+        let gradient = self.get_gradient();
+
+        self.steps += 1;
+
+        for (index, slope) in gradient.into_iter().enumerate() {
+            let first_moment = &mut self.first_moments[index];
+            let second_moment = &mut self.second_moments[index];
+
+            *first_moment = FIRST_MOMENT_DECAY * *first_moment + (1.0 - FIRST_MOMENT_DECAY) * slope;
+            *second_moment =
+                SECOND_MOMENT_DECAY * *second_moment + (1.0 - SECOND_MOMENT_DECAY) * slope * slope;
+
+            // The moments start at zero, so they are corrected for being biased towards it at first.
+            let first_estimate = *first_moment / (1.0 - FIRST_MOMENT_DECAY.powi(self.steps));
+            let second_estimate = *second_moment / (1.0 - SECOND_MOMENT_DECAY.powi(self.steps));
+
+            self.weights[index] -=
+                self.learning_rate * first_estimate / (second_estimate.sqrt() + EPSILON);
+        }
+
+        self.get_error(&self.get_params())
+    }
+}
+
+// Runs the tuner from the command line, given "<data file> <weights file> [epochs]".
+// The weights file is written after every epoch, so the tuning can be stopped at any time. It is loaded with the "EvalFile" option.
+pub fn run(args: &[String]) -> Result<(), &'static str> {
+    let (data_path, output_path, epochs) = match args {
+        [data_path, output_path] => (data_path, output_path, DEFAULT_EPOCHS),
+        [data_path, output_path, epochs] => (
+            data_path,
+            output_path,
+            epochs.parse().map_err(|_| "Epoch count must be a number")?,
+        ),
+        _ => return Err("Usage: tune <data file> <weights file> [epochs]"),
+    };
+
+    let data = fs::read_to_string(data_path).map_err(|_| "Tuning data couldn't be read")?;
+    let positions = parse_positions(&data)?;
+
+    println!("Loaded {} positions", positions.len());
+
+    let mut tuner = Tuner::new(positions, EvalParams::default());
+    let error = tuner.fit_scaling();
+
+    println!("Scaling {:.3}, error {:.6}", tuner.get_scaling(), error);
+
+    for epoch in 1..=epochs {
+        let error = tuner.step();
+
+        println!("Epoch {}, error {:.6}", epoch, error);
+
+        fs::write(output_path, tuner.get_params().to_string())
+            .map_err(|_| "Weights file couldn't be written")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::engine::params::EvalParams;
+
+    use super::{get_expected_result, parse_positions, Tuner, TuningPosition};
+
+    #[test]
+    fn parses_positions() {
+        let positions = parse_positions(
+            "4k3/8/8/8/8/8/8/4K2Q w - - 0 1 [1.0]\n\n\
+             4k3/8/8/8/8/8/8/4K3 b - - 0 1; 1/2-1/2\n\
+             4k2q/8/8/8/8/8/8/4K3 w - - 0 1; -850; 0-1\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            positions
+                .iter()
+                .map(|position| position.result)
+                .collect::<Vec<_>>(),
+            [1.0, 0.5, 0.0]
+        );
+
+        assert!(TuningPosition::from_str("4k3/8/8/8/8/8/8/4K3 w - - 0 1").is_err());
+        assert!(TuningPosition::from_str("4k3/8/8/8/8/8/8/4K3 w - - 0 1; 2-0").is_err());
+        assert!(TuningPosition::from_str("4k3/8/8/8/8/8/8/9 w - - 0 1; 1-0").is_err());
+    }
+
+    #[test]
+    fn expected_results() {
+        assert_eq!(get_expected_result(0, 1.0), 0.5);
+        assert!(get_expected_result(400, 1.0) > 0.9);
+        assert!(
+            (get_expected_result(150, 1.3) + get_expected_result(-150, 1.3) - 1.0).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn tuning_reduces_error() {
+        // An extra knight or bishop only draws here, so the tuner should learn the minor pieces are worth less.
+        let positions = parse_positions(
+            "4k3/pp6/8/8/8/8/PP6/2N1K3 w - - 0 1; 1/2-1/2\n\
+             2b1k3/pp6/8/8/8/8/PP6/4K3 b - - 0 1; 1/2-1/2\n\
+             4k3/5ppp/8/8/8/8/5PPP/3NK3 w - - 0 1; 1/2-1/2\n\
+             3nk3/5ppp/8/8/8/8/5PPP/4K3 w - - 0 1; 1/2-1/2\n\
+             4k3/8/8/8/8/8/5PPP/4K3 w - - 0 1; 1-0\n\
+             4k3/5ppp/8/8/8/8/8/4K3 b - - 0 1; 0-1\n\
+             4k3/8/8/8/8/8/8/3QK3 w - - 0 1; 1-0\n",
+        )
+        .unwrap();
+        let mut tuner = Tuner::new(positions, EvalParams::default());
+        let initial_error = tuner.get_error(&EvalParams::default());
+        let fitted_error = tuner.fit_scaling();
+
+        assert!(fitted_error <= initial_error);
+
+        let mut error = fitted_error;
+
+        for _ in 0..10 {
+            error = tuner.step();
+        }
+
+        assert!(error < fitted_error);
+
+        let params = tuner.get_params();
+        let defaults = EvalParams::default();
+
+        assert!(params.material[3] < defaults.material[3]);
+        assert_eq!(tuner.get_error(&params), error);
+    }
+}
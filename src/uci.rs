@@ -0,0 +1,925 @@
+// See: https://www.shredderchess.com/chess-features/uci-universal-chess-interface.html
+use std::{
+    fs,
+    io::{self, BufRead},
+    mem,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "nnue")]
+use crate::engine::nnue::Network;
+use crate::{
+    book::Book,
+    engine::{
+        mate::MateSearcher,
+        params::EvalParams,
+        search::{Line, MATE, MATE_BOUND},
+        syzygy::Tablebases,
+        threads::{SearchThreads, MAX_MULTI_PV, MAX_THREADS},
+        time::{Clock, SearchLimits, SystemClock, TimeManager},
+        tt::{TranspositionTable, DEFAULT_SIZE_MB},
+    },
+    game::board::Board,
+    generators::{Move, MoveGen, Square},
+    Player,
+};
+
+const MAX_HASH_MB: usize = 65536;
+
+// Formats the move in the long algebraic notation used by UCI (like "e2e4", "e1g1" or "e7e8q").
+pub fn format_move(board: &Board, chess_move: Move) -> String {
+    match chess_move {
+        Move::Regular { origin, target, .. } => format!("{}{}", origin, target),
+        Move::EnPassant { origin } => {
+            format!(
+                "{}{}",
+                origin,
+                board.ep_info.capture_point.first_one_square()
+            )
+        }
+        Move::Promotion {
+            origin,
+            target,
+            promotion_to,
+        } => format!("{}{}{}", origin, target, promotion_to.into_piece_char()),
+        Move::CastleKS => match board.current_player {
+            Player::White => format!("{}{}", Square::E1, Square::G1),
+            Player::Black => format!("{}{}", Square::E8, Square::G8),
+        },
+        Move::CastleQS => match board.current_player {
+            Player::White => format!("{}{}", Square::E1, Square::C1),
+            Player::Black => format!("{}{}", Square::E8, Square::C8),
+        },
+    }
+}
+
+// Only legal moves are accepted, so the move is found by comparing against the generated ones.
+pub fn parse_move(board: &Board, text: &str) -> Option<Move> {
+    MoveGen::run(*board)
+        .into_iter()
+        .find(|&chess_move| format_move(board, chess_move) == text)
+}
+
+// Mate scores are reported in moves rather than plies, with a negative amount when the engine is getting mated.
+pub fn format_score(score: i32) -> String {
+    if score >= MATE_BOUND {
+        format!("mate {}", (MATE - score + 1) / 2)
+    } else if score <= -MATE_BOUND {
+        format!("mate -{}", (MATE + score) / 2)
+    } else {
+        format!("cp {}", score)
+    }
+}
+
+// Formats a line of moves, each of which is played on the board before formatting the next.
+pub fn format_pv(board: &Board, pv: &[Move]) -> String {
+    let mut board = *board;
+
+    pv.iter()
+        .map(|&chess_move| {
+            let text = format_move(&board, chess_move);
+            board.make_move(chess_move);
+
+            text
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Returns the value following the name in the command, like the "5" in "go depth 5".
+fn get_value<T: FromStr>(tokens: &[&str], name: &str) -> Option<T> {
+    tokens
+        .iter()
+        .position(|&token| token == name)
+        .and_then(|index| tokens.get(index + 1))
+        .and_then(|value| value.parse().ok())
+}
+
+// The move the engine expects the opponent to reply with, which it ponders on during the opponent's turn.
+// It is the second move of the principal variation, or else the best move stored in the table for the position after the best move.
+fn get_ponder_move(tt: &TranspositionTable, board: &Board, line: &Line) -> Option<Move> {
+    let (best_move, _, pv) = line;
+
+    if let Some(&ponder_move) = pv.get(1) {
+        return Some(ponder_move);
+    }
+
+    let mut board = *board;
+    board.make_move(*best_move);
+
+    // Entries can be overwritten by other positions with the same key, so the move is checked for legality.
+    tt.probe(board.hash, 0)
+        .and_then(|entry| entry.best_move)
+        .filter(|&ponder_move| MoveGen::run(board).contains(&ponder_move))
+}
+
+// Parses the parameters of the "go" command. Unknown parameters are ignored.
+pub fn parse_limits(tokens: &[&str]) -> SearchLimits {
+    let get_millis = |name| get_value(tokens, name).map(Duration::from_millis);
+
+    SearchLimits {
+        white_time: get_millis("wtime"),
+        black_time: get_millis("btime"),
+        white_increment: get_millis("winc").unwrap_or_default(),
+        black_increment: get_millis("binc").unwrap_or_default(),
+        moves_to_go: get_value(tokens, "movestogo"),
+        move_time: get_millis("movetime"),
+        depth: get_value(tokens, "depth"),
+        nodes: get_value(tokens, "nodes"),
+        mate: get_value(tokens, "mate"),
+        infinite: tokens.contains(&"infinite"),
+    }
+}
+
+pub struct Uci {
+    board: Board,
+    // The hashes of the positions of the game before the current one, so the search can tell repetitions.
+    game_hashes: Vec<u64>,
+    tt: Arc<TranspositionTable>,
+    // The move ordering tables of the threads are kept between the searches of a game.
+    // While searching they are owned by the search thread, which hands them back once it is done.
+    threads: SearchThreads,
+    // The search runs on its own thread, so that commands like "stop" can still be read.
+    search_thread: Option<JoinHandle<SearchThreads>>,
+    stop_signal: Arc<AtomicBool>,
+    // Set by "go ponder", and cleared by "ponderhit" once the opponent played the move the engine pondered on.
+    ponder_signal: Arc<AtomicBool>,
+    // Whether "go mate" only tries checking moves for the attacker.
+    mate_checks_only: bool,
+    // Book moves are played without searching, but only when "OwnBook" is on.
+    book: Option<Book>,
+    own_book: bool,
+}
+
+impl Default for Uci {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Uci {
+    pub fn new() -> Self {
+        Self {
+            board: Board::default(),
+            game_hashes: Vec::new(),
+            tt: Arc::new(TranspositionTable::new(DEFAULT_SIZE_MB)),
+            threads: SearchThreads::default(),
+            search_thread: None,
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            ponder_signal: Arc::new(AtomicBool::new(false)),
+            mate_checks_only: false,
+            book: None,
+            own_book: false,
+        }
+    }
+
+    pub fn run(&mut self) {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            if !self.handle_command(&line) {
+                return;
+            }
+        }
+
+        // The input ended without a "quit", so the running search is ended the same way.
+        self.stop_search();
+    }
+
+    // Returns false once the engine should quit.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("uci") => {
+                println!("id name fisher");
+                println!("id author the fisher developers");
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_SIZE_MB, MAX_HASH_MB
+                );
+                println!(
+                    "option name Threads type spin default 1 min 1 max {}",
+                    MAX_THREADS
+                );
+                println!("option name Ponder type check default false");
+                println!("option name MateChecksOnly type check default false");
+                println!(
+                    "option name MultiPV type spin default 1 min 1 max {}",
+                    MAX_MULTI_PV
+                );
+                println!("option name EvalFile type string default <empty>");
+                println!("option name SyzygyPath type string default <empty>");
+                println!("option name OwnBook type check default false");
+                println!("option name BookFile type string default <empty>");
+                #[cfg(feature = "nnue")]
+                println!("option name EvalNetwork type string default <empty>");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => {
+                self.stop_search();
+                self.tt.clear();
+                self.threads.clear();
+            }
+            Some("setoption") => {
+                self.stop_search();
+
+                if let Err(error) = self.set_option(&tokens.collect::<Vec<_>>()) {
+                    println!("info string {}", error);
+                }
+            }
+            Some("position") => {
+                if let Err(error) = self.set_position(&tokens.collect::<Vec<_>>()) {
+                    println!("info string {}", error);
+                }
+            }
+            Some("go") => self.go(&tokens.collect::<Vec<_>>()),
+            Some("ponderhit") => {
+                // The search goes on, but is now bound by its time limits.
+                self.ponder_signal.store(false, Ordering::Relaxed);
+
+                if let Some(search_thread) = &self.search_thread {
+                    search_thread.thread().unpark();
+                }
+            }
+            Some("stop") => self.stop_search(),
+            Some("quit") => {
+                self.stop_search();
+
+                return false;
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    fn set_option(&mut self, tokens: &[&str]) -> Result<(), &'static str> {
+        // Both the name and the value can contain spaces (like paths do), and buttons have no value at all.
+        let (name, value) = match tokens {
+            ["name", rest @ ..] => {
+                let value_start = rest
+                    .iter()
+                    .position(|&token| token == "value")
+                    .unwrap_or(rest.len());
+
+                (
+                    rest[..value_start].join(" "),
+                    rest.get(value_start + 1..).unwrap_or_default().join(" "),
+                )
+            }
+            _ => return Err("Option must be given as \"name <name> value <value>\""),
+        };
+        let value = value.as_str();
+
+        match name.as_str() {
+            "Hash" => {
+                let size_mb = value
+                    .parse::<usize>()
+                    .map_err(|_| "Hash size must be a number")?
+                    .clamp(1, MAX_HASH_MB);
+
+                self.tt = Arc::new(TranspositionTable::new(size_mb));
+            }
+            "Threads" => {
+                let count = value
+                    .parse::<usize>()
+                    .map_err(|_| "Thread count must be a number")?;
+
+                self.threads.resize(count);
+            }
+            "MultiPV" => {
+                let line_count = value
+                    .parse::<usize>()
+                    .map_err(|_| "Line count must be a number")?;
+
+                self.threads.set_multi_pv(line_count);
+            }
+            "MateChecksOnly" => {
+                self.mate_checks_only = value
+                    .parse()
+                    .map_err(|_| "MateChecksOnly must be \"true\" or \"false\"")?;
+            }
+            // The weights file written by the tuner. Without one, the default weights are used.
+            "EvalFile" => {
+                let params = if value == "<empty>" {
+                    EvalParams::default()
+                } else {
+                    fs::read_to_string(value)
+                        .map_err(|_| "Evaluation file couldn't be read")?
+                        .parse()?
+                };
+
+                self.threads.set_params(params);
+            }
+            #[cfg(feature = "nnue")]
+            "EvalNetwork" => {
+                let network = if value == "<empty>" {
+                    None
+                } else {
+                    Some(Arc::new(Network::load(value)?))
+                };
+
+                self.threads.set_network(network);
+            }
+            // The directories of the Syzygy tablebases, separated like in the "PATH" environment variable.
+            "SyzygyPath" => {
+                let tablebases = if value == "<empty>" {
+                    None
+                } else {
+                    Some(Arc::new(Tablebases::new(value)?))
+                };
+
+                self.threads.set_tablebases(tablebases);
+            }
+            "OwnBook" => {
+                self.own_book = value
+                    .parse()
+                    .map_err(|_| "OwnBook must be \"true\" or \"false\"")?;
+            }
+            // An opening book in the Polyglot format.
+            "BookFile" => {
+                self.book = if value == "<empty>" {
+                    None
+                } else {
+                    Some(Book::load(value)?)
+                };
+            }
+            // The GUI only tells whether pondering is allowed, and asks for it with "go ponder" when it is.
+            "Ponder" => {
+                value
+                    .parse::<bool>()
+                    .map_err(|_| "Ponder must be \"true\" or \"false\"")?;
+            }
+            _ => return Err("Unknown option"),
+        }
+
+        Ok(())
+    }
+
+    fn set_position(&mut self, tokens: &[&str]) -> Result<(), &'static str> {
+        let moves_start = tokens
+            .iter()
+            .position(|&token| token == "moves")
+            .unwrap_or(tokens.len());
+
+        let mut board = match &tokens[..moves_start] {
+            ["startpos"] => Board::default(),
+            ["fen", fen @ ..] => Board::from_str(&fen.join(" "))?,
+            _ => return Err("Position must be \"startpos\" or \"fen <fen>\""),
+        };
+
+        let mut game_hashes = Vec::new();
+
+        for text in tokens.iter().skip(moves_start + 1) {
+            let chess_move = parse_move(&board, text).ok_or("Position contains an illegal move")?;
+
+            game_hashes.push(board.hash);
+            board.make_move(chess_move);
+        }
+
+        self.board = board;
+        self.game_hashes = game_hashes;
+
+        Ok(())
+    }
+
+    fn go(&mut self, tokens: &[&str]) {
+        self.stop_search();
+
+        // Searches which analyse the position (rather than play a move) skip the book.
+        let is_analysis = ["infinite", "ponder", "mate"]
+            .iter()
+            .any(|parameter| tokens.contains(parameter));
+
+        if let Some(book_move) = self.get_book_move().filter(|_| !is_analysis) {
+            println!("bestmove {}", format_move(&self.board, book_move));
+
+            return;
+        }
+
+        let limits = parse_limits(tokens);
+        // The clock starts as soon as the command is received.
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock::new());
+        let mut time = TimeManager::new(&limits, self.board.current_player, Arc::clone(&clock));
+        let mut mate_time = TimeManager::new(&limits, self.board.current_player, clock);
+        let board = self.board;
+        let tt = Arc::clone(&self.tt);
+        let stop_signal = Arc::clone(&self.stop_signal);
+        let ponder_signal = Arc::clone(&self.ponder_signal);
+        let mut threads = mem::take(&mut self.threads);
+
+        threads.set_game_hashes(self.game_hashes.clone());
+
+        self.ponder_signal
+            .store(tokens.contains(&"ponder"), Ordering::Relaxed);
+        time.set_ponder_signal(Arc::clone(&ponder_signal));
+        mate_time.set_ponder_signal(Arc::clone(&ponder_signal));
+
+        let mut mate_searcher = MateSearcher::new();
+
+        mate_searcher.set_checks_only(self.mate_checks_only);
+        mate_searcher.set_time_manager(mate_time);
+        mate_searcher.set_stop_signal(Arc::clone(&stop_signal));
+
+        tt.new_search();
+
+        self.search_thread = Some(thread::spawn(move || {
+            // With "go mate", the mate is looked for first. Without one, the regular search goes on with the other limits.
+            let mate_line = limits
+                .mate
+                .and_then(|moves| mate_searcher.search(&board, moves));
+
+            let lines = if let Some(line) = mate_line {
+                let score = MATE - line.len() as i32;
+
+                println!(
+                    "info depth {} score {} nodes {} time {} pv {}",
+                    line.len(),
+                    format_score(score),
+                    mate_searcher.nodes,
+                    mate_searcher.get_elapsed().as_millis(),
+                    format_pv(&board, &line)
+                );
+
+                vec![(line[0], score, line)]
+            } else {
+                threads.search(&tt, &board, time, Arc::clone(&stop_signal), |info| {
+                    println!(
+                        "info depth {} seldepth {} multipv {} score {} nodes {} nps {} time {} hashfull {} pv {}",
+                        info.depth,
+                        info.seldepth,
+                        info.multi_pv,
+                        format_score(info.score),
+                        info.nodes,
+                        info.nps,
+                        info.time.as_millis(),
+                        tt.hashfull(),
+                        format_pv(&board, &info.pv)
+                    );
+                })
+            };
+
+            // The search can end early (like when it finds a mate), but while pondering or searching infinitely the GUI expects the best move only once it asks for it.
+            while !stop_signal.load(Ordering::Relaxed)
+                && (limits.infinite || ponder_signal.load(Ordering::Relaxed))
+            {
+                thread::park();
+            }
+
+            match lines.first() {
+                Some(line) => {
+                    let best_move = format_move(&board, line.0);
+                    let mut after_best_move = board;
+                    after_best_move.make_move(line.0);
+
+                    match get_ponder_move(&tt, &board, line) {
+                        Some(ponder_move) => println!(
+                            "bestmove {} ponder {}",
+                            best_move,
+                            format_move(&after_best_move, ponder_move)
+                        ),
+                        None => println!("bestmove {}", best_move),
+                    }
+                }
+                None => println!("bestmove 0000"),
+            }
+
+            threads
+        }));
+    }
+
+    fn get_book_move(&self) -> Option<Move> {
+        let book = self.book.as_ref().filter(|_| self.own_book)?;
+        let random = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+
+        book.pick_move(&self.board, random)
+    }
+
+    // Stops the running search (if there is one) and waits for it to report its best move.
+    fn stop_search(&mut self) {
+        if let Some(search_thread) = self.search_thread.take() {
+            self.stop_signal.store(true, Ordering::Relaxed);
+            search_thread.thread().unpark();
+            self.threads = search_thread.join().expect("Search thread panicked");
+            self.stop_signal.store(false, Ordering::Relaxed);
+            self.ponder_signal.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn run() {
+    Uci::new().run();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs, process,
+        str::FromStr,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        book::Book,
+        engine::{
+            params::EvalParams,
+            search::MATE,
+            tt::{Bound, TableEntry, TranspositionTable},
+        },
+        game::{board::Board, pgn},
+        generators::{Move, MoveGen},
+    };
+
+    use super::{
+        format_move, format_pv, format_score, get_ponder_move, parse_limits, parse_move, Uci,
+    };
+
+    // Gives the search thread some time, and tells whether it is still running afterwards.
+    fn is_searching(uci: &Uci, wait: Duration) -> bool {
+        let start = Instant::now();
+
+        while start.elapsed() < wait {
+            if uci
+                .search_thread
+                .as_ref()
+                .is_none_or(|search_thread| search_thread.is_finished())
+            {
+                return false;
+            }
+
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        true
+    }
+
+    #[test]
+    fn move_round_trip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/8/8/3pP3/8/8/8/4K2k w - d6 0 1",
+        ] {
+            let board = Board::from_str(fen).unwrap();
+
+            for chess_move in MoveGen::run(board) {
+                assert_eq!(
+                    parse_move(&board, &format_move(&board, chess_move)),
+                    Some(chess_move)
+                );
+            }
+        }
+
+        let board = Board::from_str("8/8/8/3pP3/8/8/8/4K2k w - d6 0 1").unwrap();
+        assert_eq!(format_move(&board, Move::from_str("e5").unwrap()), "e5d6");
+        assert_eq!(
+            format_move(&Board::default(), Move::from_str("ng1f3").unwrap()),
+            "g1f3"
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let board = Board::default();
+        let pv = ["pe2e4", "pe7e5", "ng1f3", "nb8c6", "bf1c4", "ng8f6", "ks"]
+            .map(|chess_move| Move::from_str(chess_move).unwrap());
+
+        assert_eq!(format_pv(&board, &pv), "e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1");
+    }
+
+    #[test]
+    fn limits() {
+        let limits = parse_limits(&[
+            "wtime",
+            "60000",
+            "btime",
+            "30000",
+            "winc",
+            "1000",
+            "movestogo",
+            "12",
+            "nodes",
+            "50000",
+        ]);
+
+        assert_eq!(limits.white_time, Some(Duration::from_millis(60000)));
+        assert_eq!(limits.black_time, Some(Duration::from_millis(30000)));
+        assert_eq!(limits.white_increment, Duration::from_millis(1000));
+        assert_eq!(limits.black_increment, Duration::ZERO);
+        assert_eq!(limits.moves_to_go, Some(12));
+        assert_eq!(limits.nodes, Some(50000));
+        assert_eq!(limits.depth, None);
+        assert!(!limits.infinite);
+
+        assert!(parse_limits(&["infinite"]).infinite);
+        assert_eq!(parse_limits(&["depth", "7"]).depth, Some(7));
+        assert_eq!(parse_limits(&["mate", "3"]).mate, Some(3));
+        assert_eq!(
+            parse_limits(&["movetime", "250"]).move_time,
+            Some(Duration::from_millis(250))
+        );
+    }
+
+    #[test]
+    fn searches_stop() {
+        let mut uci = Uci::new();
+
+        assert!(uci.handle_command("position startpos"));
+        assert!(uci.handle_command("go infinite"));
+        assert!(uci.handle_command("stop"));
+        assert!(uci.search_thread.is_none());
+
+        assert!(uci.handle_command("go depth 3"));
+        assert!(!uci.handle_command("quit"));
+
+        let mut uci = Uci::new();
+
+        assert!(uci.handle_command("setoption name Threads value 3"));
+        assert_eq!(uci.threads.get_count(), 3);
+        assert!(uci.handle_command("go depth 4"));
+        assert!(uci.handle_command("stop"));
+        assert_eq!(uci.threads.get_count(), 3);
+
+        assert!(uci.handle_command("setoption name MultiPV value 4"));
+        assert_eq!(uci.threads.get_multi_pv(), 4);
+        assert!(uci.handle_command("go depth 3"));
+        assert!(uci.handle_command("stop"));
+        assert_eq!(uci.threads.get_multi_pv(), 4);
+    }
+
+    #[test]
+    fn eval_file() {
+        let mut uci = Uci::new();
+        let path = env::temp_dir().join(format!("fisher-weights-{}.txt", process::id()));
+        let params = EvalParams {
+            bishop_pair_bonus: 45,
+            ..Default::default()
+        };
+
+        fs::write(&path, params.to_string()).unwrap();
+
+        assert!(uci.handle_command(&format!("setoption name EvalFile value {}", path.display())));
+        assert_eq!(*uci.threads.get_params(), params);
+
+        // A broken file keeps the weights loaded before.
+        fs::write(&path, "bishop_pair_bonus many").unwrap();
+
+        assert!(uci.handle_command(&format!("setoption name EvalFile value {}", path.display())));
+        assert_eq!(*uci.threads.get_params(), params);
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(uci.handle_command("setoption name EvalFile value <empty>"));
+        assert_eq!(*uci.threads.get_params(), EvalParams::default());
+    }
+
+    #[test]
+    fn syzygy_path() {
+        let mut uci = Uci::new();
+        let directory = env::temp_dir().join(format!("fisher-uci-syzygy-{}", process::id()));
+
+        // The tables are only read once they are probed.
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("KQvK.rtbw"), []).unwrap();
+
+        assert!(uci.handle_command(&format!(
+            "setoption name SyzygyPath value {}",
+            directory.display()
+        )));
+        assert_eq!(
+            uci.threads
+                .get_tablebases()
+                .map(|tablebases| tablebases.get_max_pieces()),
+            Some(3)
+        );
+
+        assert!(uci.handle_command("setoption name SyzygyPath value <empty>"));
+        assert!(uci.threads.get_tablebases().is_none());
+
+        fs::remove_dir_all(&directory).unwrap();
+
+        // A missing directory doesn't load anything.
+        assert!(uci.handle_command(&format!(
+            "setoption name SyzygyPath value {}",
+            directory.display()
+        )));
+        assert!(uci.threads.get_tablebases().is_none());
+    }
+
+    #[test]
+    fn book_file() {
+        let mut uci = Uci::new();
+        let path = env::temp_dir().join(format!("fisher-book-{}.bin", process::id()));
+        let games = pgn::parse_games("[Result \"1-0\"]\n1. d4 d5 2. c4 1-0");
+
+        fs::write(&path, Book::from_games(&games, 10).to_bytes()).unwrap();
+
+        assert!(uci.handle_command(&format!("setoption name BookFile value {}", path.display())));
+        assert!(uci.book.is_some());
+
+        // The book is only used once it is turned on.
+        assert!(uci.handle_command("position startpos moves d2d4 d7d5"));
+        assert_eq!(uci.get_book_move(), None);
+        assert!(uci.handle_command("setoption name OwnBook value true"));
+        assert_eq!(uci.get_book_move(), Some(Move::from_str("pc2c4").unwrap()));
+        assert!(uci.handle_command("go wtime 1000 btime 1000"));
+        assert!(uci.search_thread.is_none());
+
+        // Out of the book, the search plays as usual.
+        assert!(uci.handle_command("position startpos moves e2e4"));
+        assert_eq!(uci.get_book_move(), None);
+        assert!(uci.handle_command("go depth 2"));
+        assert!(!is_searching(&uci, Duration::from_secs(10)));
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(uci.handle_command("setoption name BookFile value <empty>"));
+        assert!(uci.book.is_none());
+    }
+
+    #[cfg(feature = "nnue")]
+    #[test]
+    fn eval_network() {
+        use crate::engine::nnue::Network;
+
+        let mut uci = Uci::new();
+        let path = env::temp_dir().join(format!("fisher-network-{}.nnue", process::id()));
+
+        fs::write(&path, Network::random(1).to_bytes()).unwrap();
+
+        assert!(uci.handle_command(&format!(
+            "setoption name EvalNetwork value {}",
+            path.display()
+        )));
+        assert!(uci.threads.get_network().is_some());
+        assert!(uci.handle_command("go depth 3"));
+        assert!(uci.handle_command("stop"));
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(uci.handle_command("setoption name EvalNetwork value <empty>"));
+        assert!(uci.threads.get_network().is_none());
+
+        // A missing file doesn't load anything.
+        assert!(uci.handle_command(&format!(
+            "setoption name EvalNetwork value {}",
+            path.display()
+        )));
+        assert!(uci.threads.get_network().is_none());
+    }
+
+    #[test]
+    fn pondering_waits() {
+        let mut uci = Uci::new();
+
+        // The depth is quickly reached, but the best move is held back until the opponent plays the expected move.
+        assert!(uci.handle_command("position startpos moves e2e4 e7e5"));
+        assert!(uci.handle_command("go ponder depth 2"));
+        assert!(is_searching(&uci, Duration::from_millis(100)));
+        assert!(uci.handle_command("ponderhit"));
+        assert!(!is_searching(&uci, Duration::from_secs(10)));
+
+        // A mate ends the deepening early, which an infinite search doesn't report before it is stopped.
+        assert!(uci.handle_command("position fen 6k1/5ppp/8/8/8/8/5PPP/4R1K1 w - - 0 1"));
+        assert!(uci.handle_command("go infinite"));
+        assert!(is_searching(&uci, Duration::from_millis(100)));
+        assert!(uci.handle_command("stop"));
+        assert!(uci.search_thread.is_none());
+
+        // Stopping while pondering ends the search, like when the opponent played another move.
+        assert!(uci.handle_command("setoption name Ponder value true"));
+        assert!(uci.handle_command("go ponder wtime 100 btime 100"));
+        assert!(is_searching(&uci, Duration::from_millis(200)));
+        assert!(uci.handle_command("stop"));
+        assert!(uci.search_thread.is_none());
+    }
+
+    #[test]
+    fn mate_searches() {
+        let mut uci = Uci::new();
+
+        assert!(uci.handle_command("setoption name MateChecksOnly value true"));
+        assert!(uci.mate_checks_only);
+
+        // The mate is found without any other limit.
+        assert!(uci.handle_command(
+            "position fen r1b3kr/ppp1Bp1p/1b6/n2P4/2p3q1/2Q2N2/P4PPP/RN2R1K1 w - - 1 1"
+        ));
+        assert!(uci.handle_command("go mate 3"));
+        assert!(!is_searching(&uci, Duration::from_secs(10)));
+
+        // Without a mate, the regular search takes over.
+        assert!(uci.handle_command("position startpos"));
+        assert!(uci.handle_command("go mate 2 depth 3"));
+        assert!(!is_searching(&uci, Duration::from_secs(10)));
+        assert!(uci.handle_command("setoption name MateChecksOnly value maybe"));
+        assert!(uci.mate_checks_only);
+    }
+
+    #[test]
+    fn ponder_moves() {
+        let table = TranspositionTable::new(1);
+        let board = Board::default();
+        let best_move = Move::from_str("pe2e4").unwrap();
+        let reply = Move::from_str("pe7e5").unwrap();
+
+        assert_eq!(
+            get_ponder_move(&table, &board, &(best_move, 0, vec![best_move, reply])),
+            Some(reply)
+        );
+
+        // Without a reply in the line, the table is used, as long as its move is legal.
+        let line = (best_move, 0, vec![best_move]);
+        let mut after_best_move = board;
+        after_best_move.make_move(best_move);
+
+        assert_eq!(get_ponder_move(&table, &board, &line), None);
+
+        for (stored_move, ponder_move) in [(reply, Some(reply)), (best_move, None)] {
+            table.store(
+                after_best_move.hash,
+                TableEntry {
+                    best_move: Some(stored_move),
+                    score: 0,
+                    depth: 1,
+                    bound: Bound::Exact,
+                },
+                0,
+            );
+
+            assert_eq!(get_ponder_move(&table, &board, &line), ponder_move);
+        }
+    }
+
+    #[test]
+    fn scores() {
+        assert_eq!(format_score(35), "cp 35");
+        assert_eq!(format_score(MATE - 1), "mate 1");
+        assert_eq!(format_score(MATE - 3), "mate 2");
+        assert_eq!(format_score(-MATE + 2), "mate -1");
+    }
+
+    #[test]
+    fn positions() {
+        let mut uci = Uci::new();
+
+        assert!(uci.handle_command("position startpos moves e2e4 e7e5 g1f3"));
+        assert_eq!(
+            uci.board.hash,
+            Board::from_str("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2")
+                .unwrap()
+                .hash
+        );
+
+        assert!(uci.handle_command("position fen 8/8/8/8/8/8/8/4K2k w - - 0 1 moves e1d2"));
+        assert_eq!(
+            uci.board.hash,
+            Board::from_str("8/8/8/8/8/8/3K4/7k b - - 1 1")
+                .unwrap()
+                .hash
+        );
+
+        // An illegal move leaves the position as it was.
+        assert!(uci.handle_command("position startpos moves e2e5"));
+        assert_eq!(
+            uci.board.hash,
+            Board::from_str("8/8/8/8/8/8/3K4/7k b - - 1 1")
+                .unwrap()
+                .hash
+        );
+    }
+
+    #[test]
+    fn options_with_spaces() {
+        let mut uci = Uci::new();
+
+        assert!(uci.set_option(&["name", "Hash", "value", "2"]).is_ok());
+        // The value is everything after "value", and the name everything before it.
+        assert_eq!(
+            uci.set_option(&["name", "Hash", "value", "1", "6"]),
+            Err("Hash size must be a number")
+        );
+        assert_eq!(
+            uci.set_option(&["name", "Hash", "Size", "value", "16"]),
+            Err("Unknown option")
+        );
+        assert_eq!(
+            uci.set_option(&["value", "3"]),
+            Err("Option must be given as \"name <name> value <value>\"")
+        );
+    }
+}